@@ -1,10 +1,11 @@
 use std::collections::HashMap;
-use std::fs::read_to_string; 
-use std::io::{self, Error, ErrorKind};
+use std::io::{self, Error};
 use std::process::Command;
 
 use serde::{Serialize, Deserialize};
 
+use crate::sysctl;
+
 #[derive(Debug, Clone, Serialize, Deserialize,  Default)]
 pub enum Architecture {
     AMD64,
@@ -105,7 +106,7 @@ impl Cpu {
             .expect("lscpu command not found.");
         
         if !output.status.success() {
-            return Err(Error::new(ErrorKind::Other, "Failed to execute lscpu"));
+            return Err(Error::other("Failed to execute lscpu"));
         }
 
         let lscpu_output = String::from_utf8_lossy(&output.stdout);
@@ -213,7 +214,7 @@ fn parse_cache_sizes(info: &HashMap<String, String>) -> (u64, u64, u64) {
 
 impl Architecture {
     pub fn current() -> Architecture {
-        match read_to_string("/proc/sys/kernel/arch") {
+        match sysctl::read("kernel.arch") {
             Ok(arch) => match_cpu_arch(&arch),
             Err(_) => Architecture::Unknown
         }
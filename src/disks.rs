@@ -1,8 +1,16 @@
+use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, Read, Seek, SeekFrom};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use serde::{Serialize, Deserialize};
 
+use crate::sysfs;
+use crate::udev::{self, UdevRecord};
+
+const SYS_BLOCK: &str = "/sys/block";
+const SYS_CLASS_BLOCK: &str = "/sys/class/block";
+const DEV: &str = "/dev";
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum DiskLabel {
     GPT,
@@ -15,12 +23,52 @@ pub struct Disk {
     name: String,
     uuid: String,
     model: String,
+    serial: Option<String>,
+    wwn: Option<String>,
+    path_id: Option<String>,
+    names: PersistentNames,
     disklabel_type: String,
     size: u64,
     sector_size: u64,
     n_sectors: u64,
     io_size: u32,
-    partitions: Vec<Partition>
+    partitions: Vec<Partition>,
+    loop_info: Option<LoopInfo>,
+    zram_info: Option<ZramInfo>,
+}
+
+/// A kernel block device as seen under `/sys/class/block`.
+///
+/// Partition membership and the disk/partition hierarchy are taken from
+/// sysfs itself rather than guessed from device names, so they hold for
+/// every driver (`sda1`, `nvme0n1p1`, `mmcblk0p1`, `md0p1`, `xvda1`, ...).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockDevice {
+    name: String,
+    sys_path: PathBuf,
+}
+
+/// Backing store and flags of a bound loop device, from
+/// `/sys/block/loopN/loop/`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LoopInfo {
+    pub backing_file: String,
+    pub offset: u64,
+    pub sizelimit: u64,
+    pub autoclear: bool,
+    pub partscan: bool,
+    pub read_only: bool,
+}
+
+/// Compression state of a zram device, from `/sys/block/zramN/`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ZramInfo {
+    pub comp_algorithm: String,
+    pub disksize: u64,
+    pub orig_data_size: u64,
+    pub compr_data_size: u64,
+    pub mem_used_total: u64,
+    pub compression_ratio: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,15 +80,103 @@ pub struct Partition {
     size: u64,
     uuid: String,
     part_type: String,
+    part_uuid: Option<String>,
+    part_label: Option<String>,
+    fs_type: Option<String>,
+    label: Option<String>,
+    names: PersistentNames,
 }
 
-impl DiskLabel {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for DiskLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::GPT => String::from("GPT"),
-            Self::MBR => String::from("MBR"),
-            Self::Unknown => String::from("Unknown")
+            Self::GPT => f.write_str("GPT"),
+            Self::MBR => f.write_str("MBR"),
+            Self::Unknown => f.write_str("Unknown")
+        }
+    }
+}
+
+impl BlockDevice {
+    pub fn new(name: &str) -> io::Result<Self> {
+        Self::read_from(Path::new(SYS_CLASS_BLOCK), name)
+    }
+
+    /// Looks `name` up in a `/sys/class/block` directory.
+    pub fn read_from(class_block: &Path, name: &str) -> io::Result<Self> {
+        let sys_path = fs::canonicalize(class_block.join(name))?;
+
+        Ok(BlockDevice { name: name.to_string(), sys_path })
+    }
+
+    /// A device whose directory has already been resolved.
+    fn at(sys_path: PathBuf) -> Option<Self> {
+        let name = sys_path.file_name()?.to_string_lossy().to_string();
+        Some(BlockDevice { name, sys_path })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The device directory under `/sys/devices` this device resolves to.
+    pub fn sys_path(&self) -> &Path {
+        &self.sys_path
+    }
+
+    /// A block device is a partition exactly when the kernel gives it a
+    /// `partition` attribute.
+    pub fn is_partition(&self) -> bool {
+        self.sys_path.join("partition").is_file()
+    }
+
+    pub fn partition_number(&self) -> Option<u32> {
+        sysfs::read_u64(&self.sys_path.join("partition"))
+            .map(|number| number as u32)
+    }
+
+    /// The `major:minor` device number from the `dev` attribute.
+    pub fn dev(&self) -> Option<(u32, u32)> {
+        let dev = sysfs::read_string(&self.sys_path.join("dev"))?;
+        let (major, minor) = dev.split_once(':')?;
+        Some((major.parse().ok()?, minor.parse().ok()?))
+    }
+
+    /// The udev database entry for this device, if udev is running.
+    pub fn udev(&self) -> Option<UdevRecord> {
+        let (major, minor) = self.dev()?;
+        udev::read_block_device(major, minor)
+    }
+
+    /// The disk holding this partition, i.e. the sysfs directory it lives
+    /// in. Whole disks have no parent.
+    pub fn parent(&self) -> Option<BlockDevice> {
+        if !self.is_partition() {
+            return None;
         }
+
+        BlockDevice::at(self.sys_path.parent()?.to_path_buf())
+    }
+
+    /// The partitions of this disk, ordered by partition number.
+    pub fn children(&self) -> Vec<BlockDevice> {
+        if self.is_partition() {
+            return Vec::new();
+        }
+
+        let mut children: Vec<BlockDevice> = match fs::read_dir(&self.sys_path)
+        {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.join("partition").is_file())
+                .filter_map(BlockDevice::at)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        children.sort_by_key(|child| child.partition_number());
+        children
     }
 }
 
@@ -54,23 +190,43 @@ impl Partition {
             ));
         }
 
-        let uuid = match get_uuid_from_dir("/dev/disk/by-uuid", part) {
-            Ok(Some(uuid)) => uuid,
-            Ok(None) => {
-                format!("UUID not found for partition: {}", part)
+        let udev = BlockDevice::new(part).ok().and_then(|dev| dev.udev());
+
+        let uuid = match udev_property(udev.as_ref(), "ID_FS_UUID") {
+            Some(uuid) => uuid,
+            None => match get_uuid_from_dir("/dev/disk/by-uuid", part) {
+                Ok(Some(uuid)) => uuid,
+                Ok(None) => {
+                    format!("UUID not found for partition: {}", part)
+                }
+                Err(e) => {
+                    format!("Error while parsing the UUID for {}: {}", part, e)
+                }
             }
-            Err(e) => format!("Error while parsing the UUID for {}: {}", part, e)
-        };        
-        
-        let part_type = get_partition_type(&part)
-            .expect(&format!("Unable to get partition type {}", part))
-            .unwrap_or("Unknown partition type.".to_string());
+        };
+
+        let part_type = match udev_property(udev.as_ref(), "ID_PART_ENTRY_TYPE")
+        {
+            Some(part_type) => part_type,
+            None => get_partition_type(part)?
+                .unwrap_or("Unknown partition type.".to_string()),
+        };
+
+        let part_uuid = udev_property(udev.as_ref(), "ID_PART_ENTRY_UUID")
+            .or_else(|| link_name("/dev/disk/by-partuuid", part));
+
+        let part_label = udev_property(udev.as_ref(), "ID_PART_ENTRY_NAME")
+            .or_else(|| link_name("/dev/disk/by-partlabel", part));
 
-        let (sectors, start, end) = get_partition_sectors(device, part)
-            .expect(&format!("Unable to get sector info: {}", part));
+        let fs_type = udev_property(udev.as_ref(), "ID_FS_TYPE");
 
-        let size = sectors * get_sector_size(&device)
-            .expect(&format!("Unable to get device size {}", part));
+        let label = udev_property(udev.as_ref(), "ID_FS_LABEL")
+            .or_else(|| link_name("/dev/disk/by-label", part));
+
+        let names = PersistentNames::new(part).unwrap_or_default();
+
+        let (sectors, start, end) = get_partition_sectors(device, part)?;
+        let size = sectors * get_sector_size(device)?;
 
         Ok(Partition {
             name: part.to_string(),
@@ -80,6 +236,11 @@ impl Partition {
             size,
             uuid,
             part_type,
+            part_uuid,
+            part_label,
+            fs_type,
+            label,
+            names,
         })
     }
 }
@@ -94,59 +255,91 @@ impl Disk {
             ));
         }
 
-        let uuid = get_device_uuid(device)
-            .expect(&format!("Unable to get device UUID {}", &device));
+        // Loop and zram devices have neither a persistent id nor a
+        // `device/model`, and an unused one carries no disk label.
+        let uuid = get_device_uuid(device).unwrap_or_default();
+
+        let udev = BlockDevice::new(device).ok().and_then(|dev| dev.udev());
 
         let model = get_device_model(device)
-            .expect(&format!("Unable to get device model {}", &device));
+            .ok()
+            .filter(|model| !model.is_empty())
+            .or_else(|| udev.as_ref()
+                .and_then(|record| record.first_property(
+                    &["ID_MODEL_FROM_DATABASE", "ID_MODEL"]
+                ))
+                .map(ToString::to_string))
+            .unwrap_or_default();
+
+        let serial = udev.as_ref()
+            .and_then(|record| record.first_property(
+                &["ID_SERIAL_SHORT", "ID_SERIAL"]
+            ))
+            .map(ToString::to_string);
+
+        let wwn = udev_property(udev.as_ref(), "ID_WWN");
+        let path_id = udev_property(udev.as_ref(), "ID_PATH");
+        let names = PersistentNames::new(device).unwrap_or_default();
 
         let disklabel_type = detect_disklabel(device)
-            .expect(&format!("Unable to get disk label type {}", &device))
+            .unwrap_or(DiskLabel::Unknown)
             .to_string();
 
-        let size = read_capacity(device)
-            .expect(&format!("Unable to get capacity {}", &device));
-
-        let sector_size = get_sector_size(device)
-            .expect(&format!("Unable to get sector size {}", &device));
-
-        let n_sectors = size / sector_size as u64;
-        let io_size = get_io_size(device)
-            .expect(&format!("Unable to get io size {}", &device));
+        let size = read_capacity(device)?;
+        let sector_size = get_sector_size(device)?;
+        let n_sectors = size / sector_size;
+        let io_size = get_io_size(device)?;
 
         let partitions = get_partitions(device)
             .into_iter()
-            .filter(|part| !part.contains("loop"))
             .map(|part| Partition::new(device, &part))
-            .collect::<io::Result<Vec<Partition>>>()
-            .expect(&format!("Unable to get partitions {}", &device));
+            .collect::<io::Result<Vec<Partition>>>()?;
+
+        let loop_info = get_loop_info(device);
+        let zram_info = get_zram_info(device);
 
         Ok(Disk {
             name: device.to_string(),
             uuid,
             model,
+            serial,
+            wwn,
+            path_id,
+            names,
             disklabel_type,
             size,
             sector_size,
             n_sectors,
             io_size,
             partitions,
+            loop_info,
+            zram_info,
         })
     }
 }
 
+/// Returns whether `device` is a loop or zram device rather than a disk.
+pub fn is_virtual_device(device: &str) -> bool {
+    device.starts_with("loop") || device.starts_with("zram")
+}
 
+/// Lists the devices in `/sys/block`, leaving out loop and zram devices.
 pub fn get_block_devices() -> Vec<String> {
+    list_block_devices(false)
+}
+
+/// Lists the devices in `/sys/block`, including loop and zram devices
+/// when `include_virtual` is set.
+pub fn list_block_devices(include_virtual: bool) -> Vec<String> {
     let mut block_devices = Vec::new();
     
     // Read the contents of /sys/block
     if let Ok(entries) = fs::read_dir("/sys/block") {
         for entry in entries.flatten() {
-            let device_name = entry.file_name();
+            let device_name = entry.file_name().to_string_lossy().to_string();
 
-            if !device_name.to_string_lossy().starts_with("loop") {
-                let device_path = format!("{}", device_name.to_string_lossy());
-                block_devices.push(device_path);
+            if include_virtual || !is_virtual_device(&device_name) {
+                block_devices.push(device_name);
             }
         }
     }
@@ -154,39 +347,45 @@ pub fn get_block_devices() -> Vec<String> {
     block_devices
 }
 
-pub fn get_partitions(device_name: &str) -> Vec<String> {
-    let device_path = Path::new("/sys/block").join(device_name);
-
-    if !device_path.is_dir() {
-        return Vec::new();
-    }
+/// Lists every device under `/sys/class/block`, disks and partitions alike.
+pub fn get_all_block_devices() -> Vec<BlockDevice> {
+    read_all_block_devices(Path::new(SYS_CLASS_BLOCK))
+}
 
-    let mut partitions = Vec::new();
+/// Lists every device in a `/sys/class/block` directory.
+pub fn read_all_block_devices(class_block: &Path) -> Vec<BlockDevice> {
+    let mut devices = Vec::new();
 
-    if let Ok(entries) = fs::read_dir(device_path) {
+    if let Ok(entries) = fs::read_dir(class_block) {
         for entry in entries.flatten() {
-            let partition_name = entry.file_name();
-
-            if partition_name != device_name 
-                && partition_name.to_string_lossy().starts_with(device_name) {
-                let partition_path = format!("{}", 
-                    partition_name.to_string_lossy());
-                partitions.push(partition_path);
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Ok(device) = BlockDevice::read_from(class_block, &name) {
+                devices.push(device);
             }
         }
     }
 
-    partitions
+    devices
+}
+
+/// Lists the partitions of `device_name` as reported by sysfs.
+pub fn get_partitions(device_name: &str) -> Vec<String> {
+    match BlockDevice::new(device_name) {
+        Ok(device) => device.children()
+            .into_iter()
+            .map(|child| child.name)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
 }
 
 pub fn detect_disklabel(device: &str) -> io::Result<DiskLabel> {
     let path = format!("/dev/{}", device);
-    let mut file = File::open(&path)
-        .expect(&format!("Unable to open file: {}", path));
+    let mut file = File::open(&path)?;
 
+    // An empty loop or zram device has no first sector to read.
     let mut mbr = [0u8; 512];
-    file.read_exact(&mut mbr)
-        .expect(&format!("Unable to read Disk data from: {}", path));
+    file.read_exact(&mut mbr)?;
 
     if mbr[510] != 0x55 || mbr[511] != 0xAA {
         return Err(io::Error::new(
@@ -197,16 +396,10 @@ pub fn detect_disklabel(device: &str) -> io::Result<DiskLabel> {
 
     let part_type = mbr[450];
     if part_type == 0xEE {
-        file.seek(SeekFrom::Start(512))
-            .expect(&format!(
-                "Unable to do direct seek on mbr for: {}",
-                &device
-            ));
+        file.seek(SeekFrom::Start(512))?;
 
         let mut gpt = [0u8; 8];
-
-        file.read_exact(&mut gpt)
-            .expect(&format!("Unable to read exact bytes from: {}", &device));
+        file.read_exact(&mut gpt)?;
         if &gpt == b"EFI PART" {
             return Ok(DiskLabel::GPT);
         }
@@ -216,8 +409,7 @@ pub fn detect_disklabel(device: &str) -> io::Result<DiskLabel> {
 
 pub fn get_sector_size(device: &str) -> io::Result<u64> {
     let path = format!("/sys/block/{}/queue/logical_block_size", device);
-    let size_str = fs::read_to_string(&path)
-        .expect(&format!("Unable to read file content: {}", &path));
+    let size_str = fs::read_to_string(&path)?;
 
     size_str.trim().parse().map_err(|e| {
         io::Error::new(
@@ -231,72 +423,365 @@ pub fn read_capacity(device: &str) -> io::Result<u64> {
     let sector_size = get_sector_size(device).unwrap_or(512);
     let path = format!("/sys/class/block/{}/size", device);
     
-    let size_str = fs::read_to_string(path)
-        .expect(&format!("Unable to open path: {}", &device));
+    let size_str = fs::read_to_string(path)?;
 
     let capacity_in_sectors = size_str.trim().parse::<u64>().map_err(|e| {
         io::Error::new(
             io::ErrorKind::InvalidData, format!("Invalid capacity: {}", e)
         )
-    }).expect(&format!("Unable to parse capacity for {}", &device));
+    })?;
 
     Ok(capacity_in_sectors * sector_size)
 }
 
 pub fn get_device_model(device: &str) -> io::Result<String> {
     let path = format!("/sys/block/{}/device/model", device);
-    let model = fs::read_to_string(&path)
-        .expect(&format!("Unable to read contents of: {}", &path));
+    let model = fs::read_to_string(&path)?;
 
     Ok(model.trim().to_string())
 }
 
+/// Reads the loop configuration of `device`, or `None` if it is not a
+/// bound loop device.
+pub fn get_loop_info(device: &str) -> Option<LoopInfo> {
+    read_loop_info(Path::new(SYS_BLOCK), device)
+}
+
+/// Reads the loop configuration of `device` in a `/sys/block` directory.
+pub fn read_loop_info(sys_block: &Path, device: &str) -> Option<LoopInfo> {
+    let block_path = sys_block.join(device);
+    let loop_path = block_path.join("loop");
+
+    if !loop_path.is_dir() {
+        return None;
+    }
+
+    Some(LoopInfo {
+        backing_file: sysfs::read_string(&loop_path.join("backing_file"))
+            .unwrap_or_default(),
+        offset: sysfs::read_u64(&loop_path.join("offset")).unwrap_or(0),
+        sizelimit: sysfs::read_u64(&loop_path.join("sizelimit")).unwrap_or(0),
+        autoclear: sysfs::read_u64(&loop_path.join("autoclear")) == Some(1),
+        partscan: sysfs::read_u64(&loop_path.join("partscan")) == Some(1),
+        read_only: sysfs::read_u64(&block_path.join("ro")) == Some(1),
+    })
+}
+
+/// Reads the compression state of `device`, or `None` if it is not a
+/// zram device.
+pub fn get_zram_info(device: &str) -> Option<ZramInfo> {
+    read_zram_info(Path::new(SYS_BLOCK), device)
+}
+
+/// Reads the compression state of `device` in a `/sys/block` directory.
+pub fn read_zram_info(sys_block: &Path, device: &str) -> Option<ZramInfo> {
+    let block_path = sys_block.join(device);
+    let disksize = sysfs::read_u64(&block_path.join("disksize"))?;
+
+    // mm_stat: orig_data_size compr_data_size mem_used_total mem_limit
+    //          mem_used_max same_pages pages_compacted huge_pages ...
+    let mm_stat: Vec<u64> = sysfs::read_string(&block_path.join("mm_stat"))
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|field| field.parse().ok())
+        .collect();
+
+    let orig_data_size = mm_stat.first().copied().unwrap_or(0);
+    let compr_data_size = mm_stat.get(1).copied().unwrap_or(0);
+    let mem_used_total = mm_stat.get(2).copied().unwrap_or(0);
+
+    let compression_ratio = if compr_data_size > 0 {
+        Some(orig_data_size as f64 / compr_data_size as f64)
+    } else {
+        None
+    };
+
+    let comp_algorithm = sysfs::read_string(&block_path.join("comp_algorithm"))
+        .map(|algos| sysfs::selected_option(&algos))
+        .unwrap_or_default();
+
+    Some(ZramInfo {
+        comp_algorithm,
+        disksize,
+        orig_data_size,
+        compr_data_size,
+        mem_used_total,
+        compression_ratio,
+    })
+}
+
+/// Returns the name of a symlink in `path` that resolves to `/dev/<device>`.
+/// Links are compared by canonical target, so `sda` never matches `sda1`.
 pub fn get_uuid_from_dir(
         path: &str, device: &str
     ) -> io::Result<Option<String>> {
-    if Path::new(path).exists() {
-        for entry_result in fs::read_dir(path)
-            .expect(&format!("Unable to read UUID Path: {}", &path)) {
+    let links = links_to_device(Path::new(DEV), Path::new(path), device)?;
+    Ok(links.into_iter().next())
+}
 
-            let entry = entry_result
-                .expect(
-                    &format!("No entry found for UUID Link Lookup: {}", &path)
-                );
+/// Lists, sorted, every symlink name in `dir` resolving to `<dev>/<device>`.
+fn links_to_device(
+    dev: &Path, dir: &Path, device: &str
+) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
 
-            let target = fs::read_link(entry.path())
-                .expect(&format!("Unable to read link: {}", &path));
+    if !dir.is_dir() {
+        return Ok(names);
+    }
 
-            if target.to_string_lossy().contains(device) {
-                if let Some(uuid) = entry.file_name().to_str() {
-                    return Ok(Some(uuid.to_string()));
-                }
-            }
+    let target = match fs::canonicalize(dev.join(device)) {
+        Ok(target) => target,
+        Err(_) => return Ok(names),
+    };
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+
+        if fs::canonicalize(entry.path()).is_ok_and(|link| link == target) {
+            names.push(entry.file_name().to_string_lossy().to_string());
         }
     }
-    Ok(None)
+
+    names.sort();
+    Ok(names)
 }
 
+fn udev_property(udev: Option<&UdevRecord>, key: &str) -> Option<String> {
+    udev?.property(key).map(ToString::to_string)
+}
 
+/// Name of the symlink in `dir` pointing at `device`, used when there is
+/// no udev database to ask.
+fn link_name(dir: &str, device: &str) -> Option<String> {
+    get_uuid_from_dir(dir, device).ok().flatten()
+        .map(|name| decode_devnode_name(&name))
+}
+
+/// Returns a persistent identifier for a whole disk: its `by-id` name,
+/// preferring the transport-independent `wwn-` form, or else the
+/// filesystem UUID of an unpartitioned disk.
 pub fn get_device_uuid(device: &str) -> io::Result<String> {
-    if let Some(uuid) = get_uuid_from_dir("/dev/disk/by-id", device)
-        .expect(&format!("Unable to get disk UUID: {}", &device)) {
-        if let Some(id) = uuid.split('-').last() {
-            return Ok(id.to_string());
-        }
+    let by_id = links_to_device(
+        Path::new(DEV), Path::new("/dev/disk/by-id"), device
+    )?;
+
+    if let Some(id) = by_id.iter().find(|id| id.starts_with("wwn-"))
+        .or_else(|| by_id.first()) {
+        return Ok(id.clone());
     }
 
-    if let Some(uuid) = get_uuid_from_dir("/dev/disk/by-uuid", device)
-        .expect(&format!("Unable to get device UUID {}", &device)) {
+    if let Some(uuid) = get_uuid_from_dir("/dev/disk/by-uuid", device)? {
         return Ok(uuid);
     }
 
     Err(io::Error::new(io::ErrorKind::NotFound, "UUID not found"))
 }
 
+/// The persistent device names `/dev/disk/by-*` provides, keyed by the
+/// `fstab`-style tag that refers to each directory.
+const PERSISTENT_DIRS: [(&str, &str); 7] = [
+    ("ID", "by-id"),
+    ("UUID", "by-uuid"),
+    ("PARTUUID", "by-partuuid"),
+    ("LABEL", "by-label"),
+    ("PARTLABEL", "by-partlabel"),
+    ("PATH", "by-path"),
+    ("DISKSEQ", "by-diskseq"),
+];
+
+/// Every persistent name of one block device, as the link names found
+/// under `/dev/disk/by-*`. Labels keep udev's `\xNN` escaping.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersistentNames {
+    pub by_id: Vec<String>,
+    pub by_uuid: Vec<String>,
+    pub by_partuuid: Vec<String>,
+    pub by_label: Vec<String>,
+    pub by_partlabel: Vec<String>,
+    pub by_path: Vec<String>,
+    pub by_diskseq: Vec<String>,
+}
+
+impl PersistentNames {
+    /// Collects the persistent names of `device` (e.g. `sda1`), taking
+    /// them from the udev database when available and from the
+    /// `/dev/disk/by-*` links otherwise.
+    pub fn new(device: &str) -> io::Result<Self> {
+        let mut names = PersistentNames::default();
+
+        let udev = BlockDevice::new(device).ok().and_then(|dev| dev.udev());
+
+        if let Some(record) = udev.filter(|record| !record.symlinks.is_empty())
+        {
+            for link in &record.symlinks {
+                if let Some((dir, name)) = link.strip_prefix("disk/")
+                    .and_then(|rest| rest.split_once('/'))
+                    && let Some(list) = names.list_mut(dir) {
+                    list.push(name.to_string());
+                }
+            }
+
+            for (_, dir) in PERSISTENT_DIRS {
+                if let Some(list) = names.list_mut(dir) {
+                    list.sort();
+                }
+            }
+
+            return Ok(names);
+        }
+
+        Self::from_links(Path::new(DEV), device)
+    }
+
+    /// Collects the persistent names of `device` from the `disk/by-*`
+    /// links of a `/dev` directory alone, matching them by target.
+    pub fn from_links(dev: &Path, device: &str) -> io::Result<Self> {
+        let mut names = PersistentNames::default();
+
+        for (_, dir) in PERSISTENT_DIRS {
+            let links = links_to_device(
+                dev, &dev.join("disk").join(dir), device
+            )?;
+
+            if let Some(list) = names.list_mut(dir) {
+                *list = links;
+            }
+        }
+
+        Ok(names)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        PERSISTENT_DIRS.iter()
+            .all(|(_, dir)| self.list(dir).is_none_or(|list| list.is_empty()))
+    }
+
+    fn list(&self, dir: &str) -> Option<&Vec<String>> {
+        match dir {
+            "by-id" => Some(&self.by_id),
+            "by-uuid" => Some(&self.by_uuid),
+            "by-partuuid" => Some(&self.by_partuuid),
+            "by-label" => Some(&self.by_label),
+            "by-partlabel" => Some(&self.by_partlabel),
+            "by-path" => Some(&self.by_path),
+            "by-diskseq" => Some(&self.by_diskseq),
+            _ => None,
+        }
+    }
+
+    fn list_mut(&mut self, dir: &str) -> Option<&mut Vec<String>> {
+        match dir {
+            "by-id" => Some(&mut self.by_id),
+            "by-uuid" => Some(&mut self.by_uuid),
+            "by-partuuid" => Some(&mut self.by_partuuid),
+            "by-label" => Some(&mut self.by_label),
+            "by-partlabel" => Some(&mut self.by_partlabel),
+            "by-path" => Some(&mut self.by_path),
+            "by-diskseq" => Some(&mut self.by_diskseq),
+            _ => None,
+        }
+    }
+}
+
+/// Resolves a device reference to its kernel name (e.g. `sda1`).
+///
+/// Accepts `fstab`-style specs (`UUID=...`, `PARTUUID=...`, `LABEL=...`,
+/// `PARTLABEL=...`, `ID=...`, `PATH=...`, `DISKSEQ=...`, optionally
+/// quoted), any path such as `/dev/disk/by-id/...` or `/dev/mapper/...`,
+/// a bare kernel name, or a bare persistent link name.
+pub fn resolve_device(spec: &str) -> Option<String> {
+    resolve_device_in(Path::new(DEV), Path::new(SYS_CLASS_BLOCK), spec)
+}
+
+/// Resolves `spec` against a `/dev` and a `/sys/class/block` directory.
+/// Paths under `/dev/` are looked up in `dev` instead.
+pub fn resolve_device_in(
+    dev: &Path, class_block: &Path, spec: &str
+) -> Option<String> {
+    let spec = spec.trim();
+    let kernel_name = |path: &Path| kernel_name(dev, class_block, path);
+    let disk = dev.join("disk");
+
+    if let Some((tag, value)) = spec.split_once('=')
+        && let Some((_, dir)) = PERSISTENT_DIRS.iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(tag)) {
+        let value = value.trim_matches('"');
+        let dir = disk.join(dir);
+
+        return kernel_name(&dir.join(value))
+            .or_else(|| kernel_name(&dir.join(encode_devnode_name(value))));
+    }
+
+    if spec.starts_with('/') {
+        return match Path::new(spec).strip_prefix(DEV) {
+            Ok(rest) => kernel_name(&dev.join(rest)),
+            Err(_) => kernel_name(Path::new(spec)),
+        };
+    }
+
+    if class_block.join(spec).exists() {
+        return Some(spec.to_string());
+    }
+
+    PERSISTENT_DIRS.iter()
+        .find_map(|(_, dir)| kernel_name(&disk.join(dir).join(spec)))
+}
+
+/// Undo udev's `\xNN` escaping of a `/dev/disk/by-*` link name.
+pub fn decode_devnode_name(name: &str) -> String {
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+
+    while let Some((&first, tail)) = rest.split_first() {
+        if first == b'\\' && tail.len() >= 3 && tail[0] == b'x'
+            && let Ok(hex) = std::str::from_utf8(&tail[1..3])
+            && let Ok(byte) = u8::from_str_radix(hex, 16) {
+            bytes.push(byte);
+            rest = &tail[3..];
+        } else {
+            bytes.push(first);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+/// Follows `path` to its device node in `dev` and returns the node's
+/// kernel name, if `class_block` knows it.
+fn kernel_name(dev: &Path, class_block: &Path, path: &Path) -> Option<String> {
+    let node = fs::canonicalize(path).ok()?;
+    let dev = fs::canonicalize(dev).ok()?;
+    let name = node.strip_prefix(dev).ok()?.to_string_lossy().to_string();
+
+    if class_block.join(&name).exists() {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+/// Escape a name the way udev does for `/dev/disk/by-label` and friends:
+/// ASCII outside `[A-Za-z0-9#+-.:=@_]` becomes `\xNN`, while non-ASCII
+/// characters are kept as they are.
+pub fn encode_devnode_name(name: &str) -> String {
+    let mut encoded = String::with_capacity(name.len());
+
+    for c in name.chars() {
+        let allowed = c.is_ascii_alphanumeric() || "#+-.:=@_".contains(c);
+        if allowed || !c.is_ascii() {
+            encoded.push(c);
+        } else {
+            encoded.push_str(&format!("\\x{:02x}", c as u32));
+        }
+    }
+
+    encoded
+}
+
 pub fn get_io_size(device: &str) -> io::Result<u32> {
     let path = format!("/sys/block/{}/queue/optimal_io_size", device);
-    let io_size_str = fs::read_to_string(&path)
-        .expect(&format!("Unable to read contents of: {}", &path));
+    let io_size_str = fs::read_to_string(&path)?;
 
     io_size_str.trim().parse().map_err(|e| {
         io::Error::new(
@@ -316,21 +801,17 @@ pub fn get_partition_sectors(
         );
     }
 
-    let start = fs::read_to_string(partition_path.join("start"))
-        .expect(&format!("Unable to get partition sectors for: {}", &partition))
+    let start = fs::read_to_string(partition_path.join("start"))?
         .trim()
         .parse::<u64>()
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData,
-            format!("Invalid start sector: {}", e)))
-        .expect(&format!("Unable to fetch sector start: {}", &partition));
+            format!("Invalid start sector: {}", e)))?;
     
-    let size = fs::read_to_string(partition_path.join("size"))
-        .expect(&format!("Unable to get sector size for: {}", &partition))
+    let size = fs::read_to_string(partition_path.join("size"))?
         .trim()
         .parse::<u64>()
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData,
-            format!("Invalid size: {}", e)))
-        .expect(&format!("Unable to fetch sector size: {}", &partition));
+            format!("Invalid size: {}", e)))?;
 
     let end = start + size - 1;
     
@@ -358,14 +839,12 @@ pub fn get_partition_type(device: &str) -> io::Result<Option<String>> {
     
     match file.read_to_string(&mut buffer) {
         Ok(_) => (),
-        Err(e) => return Err(io::Error::new(io::ErrorKind::Other, 
+        Err(e) => return Err(io::Error::other(
             format!("Failed to read partition file: {}", e))),
     }
 
     if buffer.is_empty() {
-        Err( io::Error::new(
-            io::ErrorKind::Other, "Partition type is empty or unreadable"
-        ))
+        Err(io::Error::other("Partition type is empty or unreadable"))
     } else {
         Ok(Some(buffer.trim().to_string()))
     }
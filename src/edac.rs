@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// One DIMM (or rank, for drivers that report ranks) of a memory
+/// controller. `size` is in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct EdacDimm {
+    /// Directory name, e.g. `dimm0` or `rank3`.
+    pub name: String,
+    /// Motherboard silkscreen label, if the firmware or an admin set one.
+    pub label: Option<String>,
+    /// Position on the controller, e.g. `channel 0 slot 1`.
+    pub location: Option<String>,
+    pub size: Option<u64>,
+    pub mem_type: Option<String>,
+    pub dev_type: Option<String>,
+    pub edac_mode: Option<String>,
+    pub ce_count: u64,
+    pub ue_count: u64,
+}
+
+/// One memory controller from `/sys/devices/system/edac/mc`. The
+/// `noinfo` counts are errors that could not be attributed to a DIMM.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MemoryController {
+    /// Directory name, e.g. `mc0`.
+    pub name: String,
+    /// Driver's name for the controller, e.g. `Skylake Socket#0 IMC#0`.
+    pub mc_name: Option<String>,
+    pub size: Option<u64>,
+    pub ce_count: u64,
+    pub ce_noinfo_count: u64,
+    pub ue_count: u64,
+    pub ue_noinfo_count: u64,
+    pub seconds_since_reset: Option<u64>,
+    pub dimms: Vec<EdacDimm>,
+}
+
+impl MemoryController {
+    /// DIMMs that have logged at least one error.
+    pub fn failing_dimms(&self) -> Vec<&EdacDimm> {
+        self.dimms.iter()
+            .filter(|dimm| dimm.ce_count > 0 || dimm.ue_count > 0)
+            .collect()
+    }
+}
+
+/// Returns every EDAC memory controller, or nothing when no EDAC driver is
+/// loaded.
+pub fn get_memory_controllers() -> Vec<MemoryController> {
+    read_memory_controllers(Path::new("/sys/devices/system/edac/mc"))
+}
+
+/// Like [`get_memory_controllers`], reading the `mc` directory at `root`.
+pub fn read_memory_controllers(root: &Path) -> Vec<MemoryController> {
+    numbered_dirs(root, "mc")
+        .into_iter()
+        .map(|(name, dir)| {
+            let count = |file: &str| {
+                sysfs::read_u64(&dir.join(file)).unwrap_or(0)
+            };
+
+            // Drivers expose either `dimm*` or `rank*` directories.
+            let dimms: Vec<EdacDimm> = numbered_dirs(&dir, "dimm")
+                .into_iter()
+                .chain(numbered_dirs(&dir, "rank"))
+                .map(|(name, dir)| read_dimm(name, &dir))
+                .collect();
+
+            MemoryController {
+                mc_name: sysfs::read_string(&dir.join("mc_name")),
+                size: sysfs::read_u64(&dir.join("size_mb"))
+                    .map(|mb| mb * 1024 * 1024),
+                ce_count: count("ce_count"),
+                ce_noinfo_count: count("ce_noinfo_count"),
+                ue_count: count("ue_count"),
+                ue_noinfo_count: count("ue_noinfo_count"),
+                seconds_since_reset: sysfs::read_u64(
+                    &dir.join("seconds_since_reset")
+                ),
+                name,
+                dimms,
+            }
+        })
+        .collect()
+}
+
+/// Corrected errors across all controllers.
+pub fn total_ce_count(controllers: &[MemoryController]) -> u64 {
+    controllers.iter().map(|mc| mc.ce_count).sum()
+}
+
+/// Uncorrected errors across all controllers.
+pub fn total_ue_count(controllers: &[MemoryController]) -> u64 {
+    controllers.iter().map(|mc| mc.ue_count).sum()
+}
+
+fn read_dimm(name: String, dir: &Path) -> EdacDimm {
+    let string = |file: &str| {
+        sysfs::read_string(&dir.join(file)).filter(|value| !value.is_empty())
+    };
+
+    EdacDimm {
+        label: string("dimm_label"),
+        location: string("dimm_location"),
+        size: sysfs::read_u64(&dir.join("size")).map(|mb| mb * 1024 * 1024),
+        mem_type: string("dimm_mem_type"),
+        dev_type: string("dimm_dev_type"),
+        edac_mode: string("dimm_edac_mode"),
+        ce_count: sysfs::read_u64(&dir.join("dimm_ce_count")).unwrap_or(0),
+        ue_count: sysfs::read_u64(&dir.join("dimm_ue_count")).unwrap_or(0),
+        name,
+    }
+}
+
+/// List the `<prefix><n>` directories in `dir`, ordered by `n`.
+fn numbered_dirs(dir: &Path, prefix: &str) -> Vec<(String, PathBuf)> {
+    let mut dirs: Vec<(u32, String, PathBuf)> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let index = name.strip_prefix(prefix)?.parse().ok()?;
+                Some((index, name, entry.path()))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    dirs.sort_by_key(|(index, _, _)| *index);
+    dirs.into_iter().map(|(_, name, path)| (name, path)).collect()
+}
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// `enabled` setting of transparent hugepages. `Inherit` only appears in
+/// the per-size (mTHP) controls and defers to the top-level setting.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThpMode {
+    Always,
+    Madvise,
+    Never,
+    Inherit,
+}
+
+/// `defrag` setting of transparent hugepages.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThpDefrag {
+    Always,
+    Defer,
+    DeferMadvise,
+    Madvise,
+    Never,
+}
+
+/// `shmem_enabled` setting of transparent hugepages for tmpfs and shmem.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShmemThpMode {
+    Always,
+    WithinSize,
+    Advise,
+    Never,
+    Deny,
+    Force,
+    Inherit,
+}
+
+/// One hugetlb pool from `/sys/kernel/mm/hugepages/hugepages-<size>kB`.
+/// `page_size` is in bytes, the rest are page counts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HugePagePool {
+    pub page_size: u64,
+    pub nr: u64,
+    pub free: u64,
+    pub reserved: u64,
+    pub surplus: u64,
+    pub overcommit: u64,
+}
+
+/// A hugetlb pool as seen from one NUMA node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeHugePagePool {
+    pub node: u32,
+    pub page_size: u64,
+    pub nr: u64,
+    pub free: u64,
+    pub surplus: u64,
+}
+
+/// khugepaged settings and counters from
+/// `/sys/kernel/mm/transparent_hugepage/khugepaged`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Khugepaged {
+    pub defrag: Option<bool>,
+    pub pages_to_scan: Option<u64>,
+    pub scan_sleep_millisecs: Option<u64>,
+    pub alloc_sleep_millisecs: Option<u64>,
+    pub max_ptes_none: Option<u64>,
+    pub max_ptes_swap: Option<u64>,
+    pub max_ptes_shared: Option<u64>,
+    pub full_scans: Option<u64>,
+    pub pages_collapsed: Option<u64>,
+}
+
+/// Per-size controls of multi-size THP (Linux 6.8+), from
+/// `/sys/kernel/mm/transparent_hugepage/hugepages-<size>kB`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MthpSize {
+    pub page_size: u64,
+    pub enabled: Option<ThpMode>,
+    pub shmem_enabled: Option<ShmemThpMode>,
+    pub stats: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TransparentHugePages {
+    pub enabled: Option<ThpMode>,
+    pub defrag: Option<ThpDefrag>,
+    pub shmem_enabled: Option<ShmemThpMode>,
+    pub use_zero_page: Option<bool>,
+    pub hpage_pmd_size: Option<u64>,
+    pub khugepaged: Khugepaged,
+    pub sizes: Vec<MthpSize>,
+}
+
+/// Every hugetlb pool, its per-node split, and the THP configuration.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HugePages {
+    pub pools: Vec<HugePagePool>,
+    pub nodes: Vec<NodeHugePagePool>,
+    pub transparent: TransparentHugePages,
+}
+
+impl ThpMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match sysfs::selected_option(value).as_str() {
+            "always" => Some(Self::Always),
+            "madvise" => Some(Self::Madvise),
+            "never" => Some(Self::Never),
+            "inherit" => Some(Self::Inherit),
+            _ => None,
+        }
+    }
+}
+
+impl ThpDefrag {
+    pub fn parse(value: &str) -> Option<Self> {
+        match sysfs::selected_option(value).as_str() {
+            "always" => Some(Self::Always),
+            "defer" => Some(Self::Defer),
+            "defer+madvise" => Some(Self::DeferMadvise),
+            "madvise" => Some(Self::Madvise),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+impl ShmemThpMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match sysfs::selected_option(value).as_str() {
+            "always" => Some(Self::Always),
+            "within_size" => Some(Self::WithinSize),
+            "advise" => Some(Self::Advise),
+            "never" => Some(Self::Never),
+            "deny" => Some(Self::Deny),
+            "force" => Some(Self::Force),
+            "inherit" => Some(Self::Inherit),
+            _ => None,
+        }
+    }
+}
+
+impl HugePages {
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/sys"))
+    }
+
+    /// Like [`HugePages::new`], reading `kernel/mm` and `devices/system/node`
+    /// under the sysfs mount at `sys`.
+    pub fn read_from(sys: &Path) -> Self {
+        let mm = sys.join("kernel/mm");
+
+        HugePages {
+            pools: read_pools(&mm.join("hugepages")),
+            nodes: read_node_pools(&sys.join("devices/system/node")),
+            transparent: TransparentHugePages::read_from(
+                &mm.join("transparent_hugepage")
+            ),
+        }
+    }
+}
+
+impl TransparentHugePages {
+    /// Reads `/sys/kernel/mm/transparent_hugepage`; every field is empty
+    /// on kernels built without THP.
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/sys/kernel/mm/transparent_hugepage"))
+    }
+
+    /// Like [`TransparentHugePages::new`], reading the directory at `thp`.
+    pub fn read_from(thp: &Path) -> Self {
+        let khugepaged = thp.join("khugepaged");
+        let number = |name: &str| sysfs::read_u64(&khugepaged.join(name));
+
+        TransparentHugePages {
+            enabled: sysfs::read_string(&thp.join("enabled"))
+                .and_then(|value| ThpMode::parse(&value)),
+            defrag: sysfs::read_string(&thp.join("defrag"))
+                .and_then(|value| ThpDefrag::parse(&value)),
+            shmem_enabled: sysfs::read_string(&thp.join("shmem_enabled"))
+                .and_then(|value| ShmemThpMode::parse(&value)),
+            use_zero_page: sysfs::read_u64(&thp.join("use_zero_page"))
+                .map(|value| value == 1),
+            hpage_pmd_size: sysfs::read_u64(&thp.join("hpage_pmd_size")),
+            khugepaged: Khugepaged {
+                defrag: number("defrag").map(|value| value == 1),
+                pages_to_scan: number("pages_to_scan"),
+                scan_sleep_millisecs: number("scan_sleep_millisecs"),
+                alloc_sleep_millisecs: number("alloc_sleep_millisecs"),
+                max_ptes_none: number("max_ptes_none"),
+                max_ptes_swap: number("max_ptes_swap"),
+                max_ptes_shared: number("max_ptes_shared"),
+                full_scans: number("full_scans"),
+                pages_collapsed: number("pages_collapsed"),
+            },
+            sizes: read_mthp_sizes(thp),
+        }
+    }
+}
+
+/// Parse a `hugepages-<size>kB` directory name into the page size in bytes.
+fn page_size_of(dir_name: &str) -> Option<u64> {
+    let kb = dir_name.strip_prefix("hugepages-")?.strip_suffix("kB")?;
+    Some(kb.parse::<u64>().ok()? * 1024)
+}
+
+/// List the `hugepages-<size>kB` directories under `dir` with their page
+/// sizes, smallest first.
+fn size_dirs(dir: &Path) -> Vec<(u64, PathBuf)> {
+    let mut dirs: Vec<(u64, PathBuf)> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                Some((page_size_of(&name)?, entry.path()))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    dirs.sort_by_key(|(size, _)| *size);
+    dirs
+}
+
+fn read_pools(dir: &Path) -> Vec<HugePagePool> {
+    size_dirs(dir)
+        .into_iter()
+        .map(|(page_size, dir)| {
+            let count = |name: &str| {
+                sysfs::read_u64(&dir.join(name)).unwrap_or(0)
+            };
+            HugePagePool {
+                page_size,
+                nr: count("nr_hugepages"),
+                free: count("free_hugepages"),
+                reserved: count("resv_hugepages"),
+                surplus: count("surplus_hugepages"),
+                overcommit: count("nr_overcommit_hugepages"),
+            }
+        })
+        .collect()
+}
+
+fn read_node_pools(dir: &Path) -> Vec<NodeHugePagePool> {
+    let mut pools = Vec::new();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return pools;
+    };
+
+    let mut nodes: Vec<(u32, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let node = name.strip_prefix("node")?.parse().ok()?;
+            Some((node, entry.path()))
+        })
+        .collect();
+    nodes.sort_by_key(|(node, _)| *node);
+
+    for (node, path) in nodes {
+        for (page_size, dir) in size_dirs(&path.join("hugepages")) {
+            let count = |name: &str| {
+                sysfs::read_u64(&dir.join(name)).unwrap_or(0)
+            };
+            pools.push(NodeHugePagePool {
+                node,
+                page_size,
+                nr: count("nr_hugepages"),
+                free: count("free_hugepages"),
+                surplus: count("surplus_hugepages"),
+            });
+        }
+    }
+
+    pools
+}
+
+fn read_mthp_sizes(thp: &Path) -> Vec<MthpSize> {
+    size_dirs(thp)
+        .into_iter()
+        .map(|(page_size, dir)| {
+            let stats = fs::read_dir(dir.join("stats"))
+                .map(|entries| entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        let value = sysfs::read_u64(&entry.path())?;
+                        let name = entry.file_name();
+                        Some((name.to_string_lossy().to_string(), value))
+                    })
+                    .collect())
+                .unwrap_or_default();
+
+            MthpSize {
+                page_size,
+                enabled: sysfs::read_string(&dir.join("enabled"))
+                    .and_then(|value| ThpMode::parse(&value)),
+                shmem_enabled: sysfs::read_string(&dir.join("shmem_enabled"))
+                    .and_then(|value| ShmemThpMode::parse(&value)),
+                stats,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,97 @@
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// What ksmd has been told to do through `run`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KsmRun {
+    Stopped,
+    Running,
+    /// Stopped, with all merged pages split back apart.
+    Unmerge,
+}
+
+/// Kernel Same-page Merging state from `/sys/kernel/mm/ksm`. Page counts
+/// are in pages, `general_profit` in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Ksm {
+    pub run: Option<KsmRun>,
+    /// Shared pages in use, each backing one or more merged pages.
+    pub pages_shared: Option<u64>,
+    /// Page table entries pointing at a shared page, i.e. the pages saved.
+    pub pages_sharing: Option<u64>,
+    /// Pages checked and found unique so far.
+    pub pages_unshared: Option<u64>,
+    /// Pages changing too fast to be merged.
+    pub pages_volatile: Option<u64>,
+    pub full_scans: Option<u64>,
+    /// Memory saved minus KSM's own metadata (Linux 6.1+). Negative when
+    /// merging costs more than it saves.
+    pub general_profit: Option<i64>,
+    pub ksm_zero_pages: Option<u64>,
+    pub pages_to_scan: Option<u64>,
+    pub sleep_millisecs: Option<u64>,
+    pub merge_across_nodes: Option<bool>,
+    pub use_zero_pages: Option<bool>,
+    pub max_page_sharing: Option<u64>,
+    pub advisor_mode: Option<String>,
+}
+
+impl KsmRun {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "0" => Some(Self::Stopped),
+            "1" => Some(Self::Running),
+            "2" => Some(Self::Unmerge),
+            _ => None,
+        }
+    }
+}
+
+impl Ksm {
+    /// Reads `/sys/kernel/mm/ksm`; every field is empty on kernels built
+    /// without KSM.
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/sys/kernel/mm/ksm"))
+    }
+
+    /// Like [`Ksm::new`], reading the KSM directory at `dir`.
+    pub fn read_from(dir: &Path) -> Self {
+        let number = |name: &str| sysfs::read_u64(&dir.join(name));
+        let string = |name: &str| sysfs::read_string(&dir.join(name));
+
+        Ksm {
+            run: string("run").and_then(|value| KsmRun::parse(&value)),
+            pages_shared: number("pages_shared"),
+            pages_sharing: number("pages_sharing"),
+            pages_unshared: number("pages_unshared"),
+            pages_volatile: number("pages_volatile"),
+            full_scans: number("full_scans"),
+            general_profit: string("general_profit")
+                .and_then(|value| value.parse().ok()),
+            ksm_zero_pages: number("ksm_zero_pages"),
+            pages_to_scan: number("pages_to_scan"),
+            sleep_millisecs: number("sleep_millisecs"),
+            merge_across_nodes: number("merge_across_nodes")
+                .map(|value| value == 1),
+            use_zero_pages: number("use_zero_pages").map(|value| value == 1),
+            max_page_sharing: number("max_page_sharing"),
+            advisor_mode: string("advisor_mode")
+                .map(|value| sysfs::selected_option(&value)),
+        }
+    }
+
+    /// Average number of merged pages per shared page. Higher is better.
+    pub fn sharing_ratio(&self) -> Option<f64> {
+        let shared = self.pages_shared.filter(|shared| *shared > 0)?;
+        Some(self.pages_sharing? as f64 / shared as f64)
+    }
+
+    /// Unique pages per merged page. A high ratio means ksmd spends its
+    /// scanning on memory it cannot merge.
+    pub fn unshared_ratio(&self) -> Option<f64> {
+        let sharing = self.pages_sharing.filter(|sharing| *sharing > 0)?;
+        Some(self.pages_unshared? as f64 / sharing as f64)
+    }
+}
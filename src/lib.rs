@@ -6,4 +6,28 @@ pub mod cpu;
 
 pub mod memory;
 
-pub mod mount;
\ No newline at end of file
+pub mod mount;
+
+pub mod udev;
+
+pub mod vmstat;
+
+pub mod swap;
+
+pub mod hugepages;
+
+pub mod sysctl;
+
+pub mod zones;
+
+pub mod numa;
+
+pub mod ksm;
+
+pub mod memory_blocks;
+
+pub mod edac;
+
+pub mod net;
+
+mod sysfs;
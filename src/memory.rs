@@ -1,11 +1,12 @@
 use std::fs;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
 
+use crate::hugepages::ThpMode;
+use crate::sysctl;
+
 /// Holds system memory and VM tunable statistics, with defaults on error.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct MemoryInfo {
     pub total: u64,
     pub free: u64,
@@ -24,88 +25,434 @@ pub struct MemoryInfo {
     pub swappiness: u64,
     pub nr_hugepages: u64,
     pub transparent_hugepages: bool,
+    pub meminfo: Meminfo,
+    pub tunables: VmTunables,
+}
+
+/// Declares `VmTunables` with one optional field per `/proc/sys/vm` file,
+/// along with the reader filling them in.
+macro_rules! vm_tunables {
+    ($($field:ident => $file:literal),* $(,)?) => {
+        /// Kernel VM tunables from `/proc/sys/vm`. A field is `None` when
+        /// the file is missing or unreadable, so it is never confused with
+        /// a tunable that is really set to 0.
+        #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+        pub struct VmTunables {
+            $(pub $field: Option<u64>,)*
+        }
+
+        impl VmTunables {
+            pub fn new() -> Self {
+                VmTunables {
+                    $($field: sysctl::read_u64(concat!("vm.", $file)).ok(),)*
+                }
+            }
+        }
+    };
+}
+
+vm_tunables! {
+    overcommit_memory => "overcommit_memory",
+    overcommit_ratio => "overcommit_ratio",
+    overcommit_kbytes => "overcommit_kbytes",
+    admin_reserve_kbytes => "admin_reserve_kbytes",
+    user_reserve_kbytes => "user_reserve_kbytes",
+    dirty_ratio => "dirty_ratio",
+    dirty_background_ratio => "dirty_background_ratio",
+    dirty_bytes => "dirty_bytes",
+    dirty_background_bytes => "dirty_background_bytes",
+    dirty_expire_centisecs => "dirty_expire_centisecs",
+    dirty_writeback_centisecs => "dirty_writeback_centisecs",
+    dirtytime_expire_seconds => "dirtytime_expire_seconds",
+    swappiness => "swappiness",
+    page_cluster => "page-cluster",
+    min_free_kbytes => "min_free_kbytes",
+    watermark_scale_factor => "watermark_scale_factor",
+    watermark_boost_factor => "watermark_boost_factor",
+    vfs_cache_pressure => "vfs_cache_pressure",
+    zone_reclaim_mode => "zone_reclaim_mode",
+    min_unmapped_ratio => "min_unmapped_ratio",
+    min_slab_ratio => "min_slab_ratio",
+    compaction_proactiveness => "compaction_proactiveness",
+    extfrag_threshold => "extfrag_threshold",
+    max_map_count => "max_map_count",
+    nr_hugepages => "nr_hugepages",
+    nr_overcommit_hugepages => "nr_overcommit_hugepages",
+    laptop_mode => "laptop_mode",
+    panic_on_oom => "panic_on_oom",
+    oom_kill_allocating_task => "oom_kill_allocating_task",
+    stat_interval => "stat_interval",
+}
+
+/// A VM tunable whose value is outside what a lint recommends.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TunableFinding {
+    pub name: String,
+    pub current: Option<u64>,
+    pub recommended: String,
+    pub reason: String,
+}
+
+impl VmTunables {
+    /// Checks the tunables against common guidance for database hosts
+    /// (PostgreSQL, MySQL, Oracle, MongoDB) and returns every mismatch.
+    /// Strict overcommit is PostgreSQL's advice alone and is reported as
+    /// such. Tunables the kernel does not report are skipped.
+    pub fn lint_database(&self) -> Vec<TunableFinding> {
+        let mut findings = Vec::new();
+        let mut check = |name: &str, current: Option<u64>, ok: bool,
+                         recommended: &str, reason: &str| {
+            if current.is_some() && !ok {
+                findings.push(TunableFinding {
+                    name: name.to_string(),
+                    current,
+                    recommended: recommended.to_string(),
+                    reason: reason.to_string(),
+                });
+            }
+        };
+
+        let swappiness = self.swappiness.unwrap_or(0);
+        check("vm.swappiness", self.swappiness,
+            (1..=10).contains(&swappiness), "1-10",
+            "swapping out the buffer pool hurts far more than dropping \
+             page cache, while 0 invites the OOM killer instead");
+
+        let overcommit = self.overcommit_memory.unwrap_or(2);
+        check("vm.overcommit_memory", self.overcommit_memory, overcommit == 2,
+            "2 (PostgreSQL)",
+            "PostgreSQL wants allocations to fail rather than the OOM \
+             killer taking down the postmaster");
+
+        // Explicit byte limits override the ratios; only lint the ratios
+        // when they are in effect.
+        if self.dirty_bytes.unwrap_or(0) == 0 {
+            let ratio = self.dirty_ratio.unwrap_or(0);
+            check("vm.dirty_ratio", self.dirty_ratio, ratio <= 10,
+                "10 or less, or set vm.dirty_bytes",
+                "a large dirty limit causes long write stalls at checkpoint");
+        }
+
+        if self.dirty_background_bytes.unwrap_or(0) == 0 {
+            let ratio = self.dirty_background_ratio.unwrap_or(0);
+            check("vm.dirty_background_ratio", self.dirty_background_ratio,
+                ratio <= 5, "5 or less, or set vm.dirty_background_bytes",
+                "background writeback should start before dirty pages \
+                 pile up");
+        }
+
+        let zone_reclaim = self.zone_reclaim_mode.unwrap_or(0);
+        check("vm.zone_reclaim_mode", self.zone_reclaim_mode,
+            zone_reclaim == 0, "0",
+            "node-local reclaim evicts cache instead of using remote memory");
+
+        let max_map_count = self.max_map_count.unwrap_or(u64::MAX);
+        check("vm.max_map_count", self.max_map_count,
+            max_map_count >= 262144, "262144 or more",
+            "memory-mapped storage engines run out of mappings");
+
+        let min_free = self.min_free_kbytes.unwrap_or(u64::MAX);
+        check("vm.min_free_kbytes", self.min_free_kbytes,
+            min_free >= 65536, "65536 or more",
+            "a small reserve lets bursts of allocation fall into direct \
+             reclaim");
+
+        findings
+    }
+}
+
+/// Coarse memory pressure, graded by the share of memory still available
+/// (see [`Meminfo::pressure`]).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MemoryPressure {
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+/// Declares `Meminfo` with one optional field per known `/proc/meminfo` key,
+/// along with the key lookups used by `parse` and `get`.
+macro_rules! meminfo_fields {
+    ($($field:ident => $key:literal),* $(,)?) => {
+        /// The full contents of `/proc/meminfo`.
+        ///
+        /// Sizes are in bytes; the `HugePages_*` entries are page counts, as
+        /// the kernel reports them without a unit. A field is `None` when the
+        /// running kernel does not report that key. Keys not listed here are
+        /// kept in `other` under their kernel name.
+        #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+        pub struct Meminfo {
+            $(pub $field: Option<u64>,)*
+            pub other: BTreeMap<String, u64>,
+        }
+
+        impl Meminfo {
+            /// Look up a value by its `/proc/meminfo` key, e.g. `"Shmem"`
+            /// or `"HugePages_Total"`.
+            pub fn get(&self, key: &str) -> Option<u64> {
+                match key {
+                    $($key => self.$field,)*
+                    _ => self.other.get(key).copied(),
+                }
+            }
+
+            /// All reported values keyed by their `/proc/meminfo` name.
+            pub fn to_map(&self) -> BTreeMap<String, u64> {
+                let mut map = self.other.clone();
+                $(
+                    if let Some(value) = self.$field {
+                        map.insert($key.to_string(), value);
+                    }
+                )*
+                map
+            }
+
+            fn set(&mut self, key: &str, value: u64) {
+                match key {
+                    $($key => self.$field = Some(value),)*
+                    _ => {
+                        self.other.insert(key.to_string(), value);
+                    }
+                }
+            }
+        }
+    };
+}
+
+meminfo_fields! {
+    mem_total => "MemTotal",
+    mem_free => "MemFree",
+    mem_available => "MemAvailable",
+    buffers => "Buffers",
+    cached => "Cached",
+    swap_cached => "SwapCached",
+    active => "Active",
+    inactive => "Inactive",
+    active_anon => "Active(anon)",
+    inactive_anon => "Inactive(anon)",
+    active_file => "Active(file)",
+    inactive_file => "Inactive(file)",
+    unevictable => "Unevictable",
+    mlocked => "Mlocked",
+    high_total => "HighTotal",
+    high_free => "HighFree",
+    low_total => "LowTotal",
+    low_free => "LowFree",
+    mmap_copy => "MmapCopy",
+    swap_total => "SwapTotal",
+    swap_free => "SwapFree",
+    zswap => "Zswap",
+    zswapped => "Zswapped",
+    dirty => "Dirty",
+    writeback => "Writeback",
+    anon_pages => "AnonPages",
+    mapped => "Mapped",
+    shmem => "Shmem",
+    kreclaimable => "KReclaimable",
+    slab => "Slab",
+    sreclaimable => "SReclaimable",
+    sunreclaim => "SUnreclaim",
+    kernel_stack => "KernelStack",
+    shadow_call_stack => "ShadowCallStack",
+    page_tables => "PageTables",
+    sec_page_tables => "SecPageTables",
+    nfs_unstable => "NFS_Unstable",
+    bounce => "Bounce",
+    writeback_tmp => "WritebackTmp",
+    commit_limit => "CommitLimit",
+    committed_as => "Committed_AS",
+    vmalloc_total => "VmallocTotal",
+    vmalloc_used => "VmallocUsed",
+    vmalloc_chunk => "VmallocChunk",
+    percpu => "Percpu",
+    hardware_corrupted => "HardwareCorrupted",
+    anon_huge_pages => "AnonHugePages",
+    shmem_huge_pages => "ShmemHugePages",
+    shmem_pmd_mapped => "ShmemPmdMapped",
+    file_huge_pages => "FileHugePages",
+    file_pmd_mapped => "FilePmdMapped",
+    cma_total => "CmaTotal",
+    cma_free => "CmaFree",
+    unaccepted => "Unaccepted",
+    balloon => "Balloon",
+    huge_pages_total => "HugePages_Total",
+    huge_pages_free => "HugePages_Free",
+    huge_pages_rsvd => "HugePages_Rsvd",
+    huge_pages_surp => "HugePages_Surp",
+    hugepagesize => "Hugepagesize",
+    hugetlb => "Hugetlb",
+    direct_map_4k => "DirectMap4k",
+    direct_map_2m => "DirectMap2M",
+    direct_map_4m => "DirectMap4M",
+    direct_map_1g => "DirectMap1G",
+}
+
+impl Meminfo {
+    /// Reads `/proc/meminfo`, leaving every field empty if it can't be read.
+    pub fn new() -> Self {
+        fs::read_to_string("/proc/meminfo")
+            .map(|content| Self::parse(&content))
+            .unwrap_or_default()
+    }
+
+    /// Parse the text of a `/proc/meminfo` file, skipping malformed lines.
+    pub fn parse(content: &str) -> Self {
+        let mut meminfo = Meminfo::default();
+        for line in content.lines() {
+            if let Some((k, v)) = parse_line(line) {
+                meminfo.set(&k, v);
+            }
+        }
+        meminfo
+    }
+
+    /// Memory available for new workloads without swapping: `MemAvailable`,
+    /// or `MemFree` on kernels older than 3.14 that lack it, as `free` does.
+    pub fn available(&self) -> u64 {
+        self.mem_available.or(self.mem_free).unwrap_or(0)
+    }
+
+    /// The `buff/cache` column of `free`: `Buffers + Cached + SReclaimable`.
+    pub fn buff_cache(&self) -> u64 {
+        self.buffers.unwrap_or(0)
+            + self.cached.unwrap_or(0)
+            + self.sreclaimable.unwrap_or(0)
+    }
+
+    /// The `used` column of `free` (procps-ng 4.0.1 and later):
+    /// `MemTotal - available`.
+    pub fn used(&self) -> u64 {
+        self.mem_total.unwrap_or(0).saturating_sub(self.available())
+    }
+
+    /// The `used` column of `free` before procps-ng 4.0.1:
+    /// `MemTotal - MemFree - buff/cache`, or `MemTotal - MemFree` if that
+    /// would go negative.
+    pub fn used_legacy(&self) -> u64 {
+        let total = self.mem_total.unwrap_or(0);
+        let free = self.mem_free.unwrap_or(0);
+        total.checked_sub(free + self.buff_cache())
+            .unwrap_or(total.saturating_sub(free))
+    }
+
+    /// The used memory bar of htop:
+    /// `MemTotal - MemFree - Buffers - Cached - SReclaimable`, or
+    /// `MemTotal - MemFree` if that would go negative. It matches
+    /// [`Meminfo::used_legacy`]; shared memory is shown as cache.
+    pub fn used_htop(&self) -> u64 {
+        self.used_legacy()
+    }
+
+    /// The cache bar of htop: `Cached + SReclaimable - Shmem`.
+    pub fn cached_htop(&self) -> u64 {
+        (self.cached.unwrap_or(0) + self.sreclaimable.unwrap_or(0))
+            .saturating_sub(self.shmem.unwrap_or(0))
+    }
+
+    /// `SwapTotal - SwapFree`, as in the swap row of `free`.
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.unwrap_or(0)
+            .saturating_sub(self.swap_free.unwrap_or(0))
+    }
+
+    /// [`Meminfo::used`] as a percentage of `MemTotal`.
+    pub fn used_percent(&self) -> Option<f64> {
+        percent(self.used(), self.mem_total.unwrap_or(0))
+    }
+
+    /// [`Meminfo::available`] as a percentage of `MemTotal`.
+    pub fn available_percent(&self) -> Option<f64> {
+        percent(self.available(), self.mem_total.unwrap_or(0))
+    }
+
+    /// [`Meminfo::swap_used`] as a percentage of `SwapTotal`; `None`
+    /// without swap.
+    pub fn swap_used_percent(&self) -> Option<f64> {
+        percent(self.swap_used(), self.swap_total.unwrap_or(0))
+    }
+
+    /// `Committed_AS / CommitLimit`. Above 1.0 the kernel has promised
+    /// more memory than it could back under `vm.overcommit_memory = 2`.
+    pub fn commit_ratio(&self) -> Option<f64> {
+        let limit = self.commit_limit.filter(|limit| *limit > 0)?;
+        Some(self.committed_as? as f64 / limit as f64)
+    }
+
+    /// Grades [`Meminfo::available_percent`]: `Low` at 20% or more,
+    /// `Moderate` from 10%, `High` from 5% and `Critical` below that.
+    pub fn pressure(&self) -> Option<MemoryPressure> {
+        let available = self.available_percent()?;
+        Some(match available {
+            a if a >= 20.0 => MemoryPressure::Low,
+            a if a >= 10.0 => MemoryPressure::Moderate,
+            a if a >= 5.0 => MemoryPressure::High,
+            _ => MemoryPressure::Critical,
+        })
+    }
+}
+
+fn percent(part: u64, whole: u64) -> Option<f64> {
+    if whole == 0 {
+        None
+    } else {
+        Some(part as f64 * 100.0 / whole as f64)
+    }
 }
 
 impl MemoryInfo {
     /// Reads all memory info and VM tunables,
     /// substituting defaults if any read fails.
     pub fn new() -> Self {
-        let m = parse_meminfo("/proc/meminfo");
+        let m = Meminfo::new();
+        let t = VmTunables::new();
         MemoryInfo {
-            total: get_kb_default(&m, "MemTotal"),
-            free: get_kb_default(&m, "MemFree"),
-            available: get_kb_default(&m, "MemAvailable"),
-            buffers: get_kb_default(&m, "Buffers"),
-            cached: get_kb_default(&m, "Cached"),
-            swap_total:get_kb_default(&m, "SwapTotal"),
-            swap_free: get_kb_default(&m, "SwapFree"),
-            anon_pages: get_kb_default(&m, "AnonPages"),
-            kernel_stack: get_kb_default(&m, "KernelStack"),
-            hugepage_size: get_kb_default(&m, "Hugepagesize"),    
-            dirty_ratio: read_u64_default("/proc/sys/vm/dirty_ratio", 0),
-            dirty_background_ratio: 
-                read_u64_default("/proc/sys/vm/dirty_background_ratio", 0),
-
-            max_map_count: read_u64_default("/proc/sys/vm/max_map_count", 0),
-            overcommit_ratio: 
-                read_u64_default("/proc/sys/vm/overcommit_ratio", 0),
-
-            swappiness: read_u64_default("/proc/sys/vm/swappiness", 0),
-            nr_hugepages: read_u64_default("/proc/sys/vm/nr_hugepages", 0),
+            total: m.mem_total.unwrap_or(0),
+            free: m.mem_free.unwrap_or(0),
+            available: m.mem_available.unwrap_or(0),
+            buffers: m.buffers.unwrap_or(0),
+            cached: m.cached.unwrap_or(0),
+            swap_total: m.swap_total.unwrap_or(0),
+            swap_free: m.swap_free.unwrap_or(0),
+            anon_pages: m.anon_pages.unwrap_or(0),
+            kernel_stack: m.kernel_stack.unwrap_or(0),
+            hugepage_size: m.hugepagesize.unwrap_or(0),
+            dirty_ratio: t.dirty_ratio.unwrap_or(0),
+            dirty_background_ratio: t.dirty_background_ratio.unwrap_or(0),
+            max_map_count: t.max_map_count.unwrap_or(0),
+            overcommit_ratio: t.overcommit_ratio.unwrap_or(0),
+            swappiness: t.swappiness.unwrap_or(0),
+            nr_hugepages: t.nr_hugepages.unwrap_or(0),
             transparent_hugepages: read_transparent_hugepages_default(),
+            meminfo: m,
+            tunables: t,
         }
     }
 }
 
-/// Parse `/proc/meminfo` into key → bytes, skipping unreadable lines.
-fn parse_meminfo(path: &str) -> HashMap<String,u64> {
-    let mut map = HashMap::new();
-    if let Ok(file) = File::open(path) {
-        let reader = BufReader::new(file);
-        for line in reader.lines().flatten() {
-            if let Some((k, v)) = parse_line(&line) {
-                map.insert(k, v);
-            }
-        }
-    }
-    map
-}
-
-/// Parse a “Key: <value> kB” line into (Key, value_in_bytes).
+/// Parse a “Key: <value> kB” line into (Key, value_in_bytes), or a
+/// unitless “Key: <value>” line such as `HugePages_Total` into (Key, value).
 fn parse_line(line: &str) -> Option<(String,u64)> {
     let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() >= 3 && parts[0].ends_with(':') && parts[2] == "kB" {
-        let key = parts[0].trim_end_matches(':').to_string();
-        if let Ok(kb) = parts[1].parse::<u64>() {
-            return Some((key, kb * 1024));
-        }
+    if parts.len() < 2 || !parts[0].ends_with(':') {
+        return None;
     }
-    None
-}
 
-/// Lookup a key in the meminfo map, defaulting to zero.
-fn get_kb_default(map: &HashMap<String,u64>, key: &str) -> u64 {
-    *map.get(key).unwrap_or(&0)
+    let key = parts[0].trim_end_matches(':').to_string();
+    let value = parts[1].parse::<u64>().ok()?;
+    match parts.get(2) {
+        Some(&"kB") => Some((key, value * 1024)),
+        None => Some((key, value)),
+        Some(_) => None,
+    }
 }
 
-/// Read a one‑line file as u64, returning `default` on any error.
-fn read_u64_default(path: &str, default: u64) -> u64 {
-    fs::read_to_string(path)
-        .map(|s| s.trim().parse::<u64>().unwrap_or(default))
-        .unwrap_or(default)
-}
 
 /// Return whether transparent hugepages are enabled, defaulting to false.
 fn read_transparent_hugepages_default() -> bool {
     let path = "/sys/kernel/mm/transparent_hugepage/enabled";
-    if let Ok(s) = fs::read_to_string(path) {
-        if let Some(start) = s.find('[') {
-            if let Some(end) = s[start+1..].find(']') {
-                let mode = &s[start+1..start+1+end];
-                return mode != "never";
-            }
-        }
-        return s.contains("always") || s.contains("madvise");
-    }
-    false
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| ThpMode::parse(&s))
+        .is_some_and(|mode| mode != ThpMode::Never)
 }
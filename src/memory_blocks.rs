@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// `state` of a memory block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum MemoryBlockState {
+    Online,
+    Offline,
+    GoingOffline,
+    Other(String),
+}
+
+/// One hotpluggable memory block from `/sys/devices/system/memory`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MemoryBlock {
+    pub index: u64,
+    /// Physical address of the first byte of the block.
+    pub start: u64,
+    pub state: MemoryBlockState,
+    pub node: Option<u32>,
+    /// Whether the kernel may be able to offline the block. Kernels since
+    /// 5.12 always report `true` here.
+    pub removable: Option<bool>,
+    /// The zone an online block belongs to, or the zones an offline block
+    /// could be onlined to. Empty for online blocks spanning several zones.
+    pub zones: Vec<String>,
+}
+
+/// Memory hotplug state: the block size, the policy for new blocks and
+/// every block, in address order.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MemoryBlocks {
+    pub block_size: Option<u64>,
+    /// What happens to hot-added blocks: `offline`, `online`,
+    /// `online_kernel` or `online_movable`.
+    pub auto_online_blocks: Option<String>,
+    pub blocks: Vec<MemoryBlock>,
+}
+
+impl MemoryBlockState {
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "online" => Self::Online,
+            "offline" => Self::Offline,
+            "going-offline" => Self::GoingOffline,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl MemoryBlocks {
+    /// Reads `/sys/devices/system/memory`, which is missing on kernels
+    /// without memory hotplug support.
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/sys/devices/system/memory"))
+    }
+
+    /// Like [`MemoryBlocks::new`], reading the memory directory at `root`.
+    pub fn read_from(root: &Path) -> Self {
+        let block_size = sysfs::read_string(&root.join("block_size_bytes"))
+            .and_then(|hex| u64::from_str_radix(&hex, 16).ok());
+
+        let mut blocks: Vec<MemoryBlock> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let index = name.strip_prefix("memory")?.parse().ok()?;
+                    Some(read_block(&entry.path(), index, block_size))
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        blocks.sort_by_key(|block| block.index);
+
+        MemoryBlocks {
+            block_size,
+            auto_online_blocks: sysfs::read_string(
+                &root.join("auto_online_blocks")
+            ),
+            blocks,
+        }
+    }
+
+    pub fn online(&self) -> impl Iterator<Item = &MemoryBlock> {
+        self.blocks.iter()
+            .filter(|block| block.state == MemoryBlockState::Online)
+    }
+
+    pub fn offline(&self) -> impl Iterator<Item = &MemoryBlock> {
+        self.blocks.iter()
+            .filter(|block| block.state == MemoryBlockState::Offline)
+    }
+
+    /// Bytes of memory in online blocks.
+    pub fn online_bytes(&self) -> u64 {
+        self.online().count() as u64 * self.block_size.unwrap_or(0)
+    }
+
+    /// Bytes of memory in offline blocks.
+    pub fn offline_bytes(&self) -> u64 {
+        self.offline().count() as u64 * self.block_size.unwrap_or(0)
+    }
+}
+
+fn read_block(dir: &Path, index: u64, block_size: Option<u64>) -> MemoryBlock {
+    let phys_index = sysfs::read_string(&dir.join("phys_index"))
+        .and_then(|hex| u64::from_str_radix(&hex, 16).ok())
+        .unwrap_or(index);
+
+    let node = fs::read_dir(dir).ok().and_then(|entries| {
+        entries.flatten().find_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("node")?.parse().ok()
+        })
+    });
+
+    let zones = sysfs::read_string(&dir.join("valid_zones"))
+        .filter(|zones| zones != "none")
+        .map(|zones| zones.split_whitespace().map(String::from).collect())
+        .unwrap_or_default();
+
+    MemoryBlock {
+        index,
+        start: phys_index * block_size.unwrap_or(0),
+        state: sysfs::read_string(&dir.join("state"))
+            .map(|state| MemoryBlockState::parse(&state))
+            .unwrap_or(MemoryBlockState::Other(String::new())),
+        node,
+        removable: sysfs::read_u64(&dir.join("removable"))
+            .map(|value| value == 1),
+        zones,
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+use crate::udev::{self, UdevRecord};
+
+pub mod conntrack;
+pub mod dns;
+pub mod namespace;
+#[cfg(feature = "nftables")]
+pub mod nftables;
+pub mod protocols;
+pub mod route;
+pub mod sockets;
+pub mod stats;
+pub mod topology;
+pub mod wireless;
+
+mod netlink;
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// `ARPHRD_LOOPBACK` from `<linux/if_arp.h>`.
+const ARPHRD_LOOPBACK: u16 = 772;
+
+/// `IFF_TAP` from `<linux/if_tun.h>`, set in `tun_flags` of TAP devices.
+const IFF_TAP: u32 = 0x2;
+
+/// `IFF_UP` from `<net/if.h>`.
+const IFF_UP: u32 = 0x1;
+
+/// RFC 2863 operational state, from `operstate`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    Up,
+    Down,
+    Dormant,
+    LowerLayerDown,
+    NotPresent,
+    Testing,
+    Unknown,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    Full,
+    Half,
+}
+
+/// What kind of device an interface is, as far as sysfs tells.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum InterfaceKind {
+    Ether,
+    Loopback,
+    Bridge,
+    Bond,
+    Vlan,
+    Veth,
+    Tun,
+    Tap,
+    Wireguard,
+    Wireless,
+    /// Another `DEVTYPE` (e.g. `vxlan`), or `type <n>` with the ARP
+    /// hardware type when there is none.
+    Other(String),
+}
+
+/// A network interface from `/sys/class/net`. Its bonds, bridges and
+/// VLANs come from [`topology::Topology::attach`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Interface {
+    pub name: String,
+    pub ifindex: u32,
+    /// Index of the interface this one sends through, which differs from
+    /// `ifindex` for VLANs, macvlans and veth pairs.
+    pub iflink: Option<u32>,
+    pub kind: InterfaceKind,
+    /// ARP hardware type (`ARPHRD_*`).
+    pub link_type: Option<u16>,
+    pub mac: Option<String>,
+    pub mtu: Option<u32>,
+    pub flags: Option<u32>,
+    pub operstate: OperState,
+    /// `None` while the interface is administratively down.
+    pub carrier: Option<bool>,
+    /// Link speed in Mbit/s, if the driver reports one.
+    pub speed: Option<u32>,
+    pub duplex: Option<Duplex>,
+    pub driver: Option<String>,
+    /// Whether the interface has no device behind it, i.e. lives in
+    /// `/sys/devices/virtual/net`.
+    pub is_virtual: bool,
+    /// PCI address of the device behind the interface, e.g. `0000:3b:00.0`.
+    pub pci_slot: Option<String>,
+    /// Bridge or bond this interface is enslaved to.
+    pub master: Option<String>,
+    /// Interfaces this one is stacked on, e.g. the parent of a VLAN.
+    pub lower: Vec<String>,
+    /// Interfaces stacked on this one.
+    pub upper: Vec<String>,
+}
+
+impl OperState {
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "up" => Self::Up,
+            "down" => Self::Down,
+            "dormant" => Self::Dormant,
+            "lowerlayerdown" => Self::LowerLayerDown,
+            "notpresent" => Self::NotPresent,
+            "testing" => Self::Testing,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl Duplex {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "full" => Some(Self::Full),
+            "half" => Some(Self::Half),
+            _ => None,
+        }
+    }
+}
+
+impl Interface {
+    pub fn new(name: &str) -> io::Result<Self> {
+        Self::read_from(Path::new(SYS_CLASS_NET), name)
+    }
+
+    /// Like [`Interface::new`], reading the interface `name` from a
+    /// `/sys/class/net` directory.
+    pub fn read_from(sys_class_net: &Path, name: &str) -> io::Result<Self> {
+        let dir = sys_class_net.join(name);
+        let ifindex = sysfs::read_u64(&dir.join("ifindex"))
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound, format!("No interface {}", name)
+            ))? as u32;
+
+        let number = |file: &str| sysfs::read_u64(&dir.join(file));
+        let iflink = number("iflink").map(|index| index as u32);
+        let link_type = number("type").map(|value| value as u16);
+        let lower = adjacent(&dir, "lower_");
+
+        Ok(Interface {
+            name: name.to_string(),
+            ifindex,
+            iflink,
+            kind: detect_kind(&dir, ifindex, iflink, link_type, &lower),
+            link_type,
+            mac: sysfs::read_string(&dir.join("address"))
+                .filter(|mac| !mac.is_empty()),
+            mtu: number("mtu").map(|mtu| mtu as u32),
+            flags: sysfs::read_string(&dir.join("flags"))
+                .and_then(|hex| parse_hex(&hex)),
+            operstate: sysfs::read_string(&dir.join("operstate"))
+                .map(|state| OperState::parse(&state))
+                .unwrap_or(OperState::Unknown),
+            carrier: number("carrier").map(|carrier| carrier == 1),
+            speed: sysfs::read_string(&dir.join("speed"))
+                .and_then(|speed| speed.parse().ok()),
+            duplex: sysfs::read_string(&dir.join("duplex"))
+                .and_then(|duplex| Duplex::parse(&duplex)),
+            driver: link_name(&dir.join("device/driver")),
+            is_virtual: fs::canonicalize(&dir).is_ok_and(|path| {
+                path.parent().is_some_and(|net| {
+                    net.ends_with("devices/virtual/net")
+                })
+            }),
+            pci_slot: pci_slot(&dir),
+            master: link_name(&dir.join("master")),
+            lower,
+            upper: adjacent(&dir, "upper_"),
+        })
+    }
+
+    /// Whether the interface is administratively up (`ip link set up`).
+    pub fn is_up(&self) -> bool {
+        self.flags.is_some_and(|flags| flags & IFF_UP != 0)
+    }
+
+    /// The interface's record in the udev database, with names such as
+    /// `ID_NET_NAME_PATH`.
+    pub fn udev(&self) -> Option<UdevRecord> {
+        udev::read_net_device(self.ifindex)
+    }
+}
+
+/// Returns every interface in `/sys/class/net`, ordered by index.
+pub fn get_interfaces() -> Vec<Interface> {
+    read_interfaces(Path::new(SYS_CLASS_NET))
+}
+
+/// Reads every interface in a `/sys/class/net` directory, ordered by index.
+pub fn read_interfaces(sys_class_net: &Path) -> Vec<Interface> {
+    let mut interfaces: Vec<Interface> = match fs::read_dir(sys_class_net) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                Interface::read_from(sys_class_net, &name).ok()
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    interfaces.sort_by_key(|interface| interface.ifindex);
+    interfaces
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// The file name a symlink points at, if `link` is one.
+fn link_name(link: &Path) -> Option<String> {
+    fs::read_link(link)
+        .ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Names of the interfaces linked from `dir` as `<prefix><name>`.
+fn adjacent(dir: &Path, prefix: &str) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_prefix(prefix).map(String::from)
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    names.sort();
+    names
+}
+
+fn devtype(dir: &Path) -> Option<String> {
+    fs::read_to_string(dir.join("uevent"))
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE=").map(String::from))
+}
+
+fn detect_kind(
+    dir: &Path,
+    ifindex: u32,
+    iflink: Option<u32>,
+    link_type: Option<u16>,
+    lower: &[String],
+) -> InterfaceKind {
+    if link_type == Some(ARPHRD_LOOPBACK) {
+        return InterfaceKind::Loopback;
+    }
+
+    if let Some(tun_flags) = sysfs::read_string(&dir.join("tun_flags"))
+        .and_then(|hex| parse_hex(&hex)) {
+        return if tun_flags & IFF_TAP != 0 {
+            InterfaceKind::Tap
+        } else {
+            InterfaceKind::Tun
+        };
+    }
+
+    match devtype(dir).as_deref() {
+        Some("bridge") => return InterfaceKind::Bridge,
+        Some("bond") => return InterfaceKind::Bond,
+        Some("vlan") => return InterfaceKind::Vlan,
+        Some("wireguard") => return InterfaceKind::Wireguard,
+        Some("wlan") => return InterfaceKind::Wireless,
+        Some(other) => return InterfaceKind::Other(other.to_string()),
+        None => {}
+    }
+
+    if dir.join("phy80211").exists() || dir.join("wireless").exists() {
+        return InterfaceKind::Wireless;
+    }
+
+    match link_type {
+        // A veth sends through its peer, but unlike a macvlan or VLAN it
+        // is not stacked on it.
+        Some(1) if iflink.is_some_and(|iflink| iflink != ifindex)
+            && lower.is_empty()
+            && !dir.join("device").exists() => InterfaceKind::Veth,
+        Some(1) => InterfaceKind::Ether,
+        Some(other) => InterfaceKind::Other(format!("type {}", other)),
+        None => InterfaceKind::Other(String::new()),
+    }
+}
+
+/// The PCI function the interface's device sits on, looking through
+/// intermediate buses such as virtio or USB.
+fn pci_slot(dir: &Path) -> Option<String> {
+    let mut device: PathBuf = fs::canonicalize(dir.join("device")).ok()?;
+
+    loop {
+        if link_name(&device.join("subsystem")).as_deref() == Some("pci") {
+            return device.file_name()
+                .map(|name| name.to_string_lossy().to_string());
+        }
+        if !device.pop() || device == Path::new("/sys/devices") {
+            return None;
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+
+/// Connection tracking statistics of one CPU, from a row of
+/// `/proc/net/stat/nf_conntrack`. Counters older kernels lack read as 0.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ConntrackCpuStats {
+    pub cpu: usize,
+    /// Lookups that found an existing entry.
+    pub found: u64,
+    pub invalid: u64,
+    pub insert: u64,
+    /// Entries that could not be inserted, e.g. on a race with another CPU.
+    pub insert_failed: u64,
+    /// Packets dropped because a new entry could not be allocated, usually
+    /// because the table was full.
+    pub drop: u64,
+    /// Entries evicted to make room for new ones.
+    pub early_drop: u64,
+    pub icmp_error: u64,
+    pub expect_new: u64,
+    pub expect_create: u64,
+    pub expect_delete: u64,
+    pub search_restart: u64,
+    /// Insert races resolved without dropping the packet.
+    pub clash_resolve: u64,
+    /// Length of the longest hash chain seen.
+    pub chain_length: u64,
+}
+
+/// Connection tracking table usage.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Conntrack {
+    /// Entries in use in this network namespace.
+    pub count: Option<u64>,
+    /// The most entries the table may hold, shared by all namespaces.
+    pub max: Option<u64>,
+    pub buckets: Option<u64>,
+    pub per_cpu: Vec<ConntrackCpuStats>,
+}
+
+impl Conntrack {
+    /// Reads the counters of the process's network namespace. Every field
+    /// is empty when the `nf_conntrack` module isn't loaded.
+    pub fn new() -> Self {
+        Self::read_from(
+            Path::new("/proc/sys/net/netfilter"), Path::new("/proc/net")
+        )
+    }
+
+    /// Reads `nf_conntrack_*` in a `/proc/sys/net/netfilter` directory and
+    /// `stat/nf_conntrack` in a `/proc/net` directory.
+    pub fn read_from(netfilter: &Path, proc_net: &Path) -> Self {
+        let number = |name: &str| sysfs::read_u64(&netfilter.join(name));
+
+        Conntrack {
+            count: number("nf_conntrack_count"),
+            max: number("nf_conntrack_max"),
+            buckets: number("nf_conntrack_buckets"),
+            per_cpu: fs::read_to_string(proc_net.join("stat/nf_conntrack"))
+                .map(|content| parse_conntrack_stat(&content))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Share of the table in use, from 0 to 1.
+    pub fn usage(&self) -> Option<f64> {
+        match (self.count, self.max) {
+            (Some(count), Some(max)) if max > 0 => {
+                Some(count as f64 / max as f64)
+            }
+            _ => None,
+        }
+    }
+
+    /// The per-CPU counters summed; `chain_length` is the longest of them.
+    pub fn totals(&self) -> ConntrackCpuStats {
+        let mut total = ConntrackCpuStats::default();
+        for cpu in &self.per_cpu {
+            total.found += cpu.found;
+            total.invalid += cpu.invalid;
+            total.insert += cpu.insert;
+            total.insert_failed += cpu.insert_failed;
+            total.drop += cpu.drop;
+            total.early_drop += cpu.early_drop;
+            total.icmp_error += cpu.icmp_error;
+            total.expect_new += cpu.expect_new;
+            total.expect_create += cpu.expect_create;
+            total.expect_delete += cpu.expect_delete;
+            total.search_restart += cpu.search_restart;
+            total.clash_resolve += cpu.clash_resolve;
+            total.chain_length = total.chain_length.max(cpu.chain_length);
+        }
+        total
+    }
+}
+
+/// Parse the text of `/proc/net/stat/nf_conntrack`: a header of column
+/// names, then one row of hex values for each possible CPU.
+pub fn parse_conntrack_stat(content: &str) -> Vec<ConntrackCpuStats> {
+    let mut lines = content.lines();
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let names: Vec<&str> = header.split_whitespace().collect();
+
+    lines.enumerate()
+        .filter_map(|(cpu, line)| {
+            let values: BTreeMap<&str, u64> = names.iter()
+                .copied()
+                .zip(line.split_whitespace())
+                .filter_map(|(name, value)| {
+                    Some((name, u64::from_str_radix(value, 16).ok()?))
+                })
+                .collect();
+            if values.is_empty() {
+                return None;
+            }
+
+            let get = |name: &str| values.get(name).copied().unwrap_or(0);
+
+            Some(ConntrackCpuStats {
+                cpu,
+                found: get("found"),
+                invalid: get("invalid"),
+                insert: get("insert"),
+                insert_failed: get("insert_failed"),
+                drop: get("drop"),
+                early_drop: get("early_drop"),
+                icmp_error: get("icmp_error"),
+                expect_new: get("expect_new"),
+                expect_create: get("expect_create"),
+                expect_delete: get("expect_delete"),
+                search_restart: get("search_restart"),
+                clash_resolve: get("clashres"),
+                chain_length: get("chainlength"),
+            })
+        })
+        .collect()
+}
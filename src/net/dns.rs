@@ -0,0 +1,475 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+/// glibc's `MAXNS`: nameservers past the third are ignored.
+const MAXNS: usize = 3;
+
+/// Addresses systemd-resolved listens on for its stub resolver.
+const RESOLVED_STUBS: [IpAddr; 2] = [
+    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 53)),
+    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 54)),
+];
+
+/// What glibc uses when `nsswitch.conf` has no `hosts` line.
+const DEFAULT_HOSTS_SERVICES: &str = "dns [!UNAVAIL=return] files";
+
+/// Resolver options from the `options` lines of `resolv.conf`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResolverOptions {
+    pub ndots: u32,
+    /// Seconds to wait for each server.
+    pub timeout: u32,
+    /// Rounds over all servers before giving up.
+    pub attempts: u32,
+    pub rotate: bool,
+    pub edns0: bool,
+    pub trust_ad: bool,
+    pub use_vc: bool,
+    pub single_request: bool,
+    pub single_request_reopen: bool,
+    pub no_tld_query: bool,
+    pub no_aaaa: bool,
+    /// Options not listed above, as written.
+    pub other: Vec<String>,
+}
+
+/// The contents of a `resolv.conf` file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ResolvConf {
+    /// Every `nameserver`, in order. An IPv6 zone (`%eth0`) is dropped.
+    pub nameservers: Vec<IpAddr>,
+    /// The `domain` keyword, if it was the last of `domain` and `search`.
+    pub domain: Option<String>,
+    /// The search list in effect: the last `search` line, or the `domain`.
+    pub search: Vec<String>,
+    pub sortlist: Vec<String>,
+    pub options: ResolverOptions,
+}
+
+/// How systemd-resolved manages `/etc/resolv.conf`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResolvedMode {
+    /// Not managed by systemd-resolved.
+    #[default]
+    NotManaged,
+    /// Queries go to the resolved stub listener on 127.0.0.53, which
+    /// forwards them to the upstream servers.
+    Stub,
+    /// A link to `/run/systemd/resolve/resolv.conf`: resolved keeps the
+    /// upstream servers up to date, but queries go to them directly.
+    Uplink,
+}
+
+/// One line of `/etc/hosts`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HostsEntry {
+    pub address: IpAddr,
+    pub hostname: String,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Hosts {
+    pub entries: Vec<HostsEntry>,
+}
+
+/// A lookup result in an `nsswitch.conf` action, e.g. `NOTFOUND`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NsStatus {
+    Success,
+    NotFound,
+    Unavail,
+    TryAgain,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NsActionKind {
+    Return,
+    Continue,
+    Merge,
+}
+
+/// One `[STATUS=action]` criterion, `negated` for `[!STATUS=action]`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NsAction {
+    pub negated: bool,
+    pub status: NsStatus,
+    pub action: NsActionKind,
+}
+
+/// A service of an `nsswitch.conf` database, with the actions that follow
+/// it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NsService {
+    pub name: String,
+    pub actions: Vec<NsAction>,
+}
+
+/// The databases of `/etc/nsswitch.conf`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NsSwitch {
+    pub databases: BTreeMap<String, Vec<NsService>>,
+}
+
+/// Where a host name lookup can be answered.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum HostSource {
+    /// `/etc/hosts`, with its number of entries.
+    Files { entries: usize },
+    /// The libc stub resolver, querying `nameservers`. In stub mode those
+    /// are systemd-resolved, which forwards to `upstream`.
+    Dns { nameservers: Vec<IpAddr>, upstream: Vec<IpAddr> },
+    /// nss-resolve, asking systemd-resolved over its own interface.
+    Resolve { upstream: Vec<IpAddr> },
+    /// nss-myhostname: the local host name, `localhost` and `_gateway`.
+    Myhostname,
+    /// nss-mdns, with the exact service name (`mdns4_minimal`, ...).
+    Mdns(String),
+    Other(String),
+}
+
+/// One step of the host name resolution order.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ResolutionStep {
+    pub source: HostSource,
+    /// When to stop after this step, on top of stopping on success.
+    pub actions: Vec<NsAction>,
+}
+
+/// Name resolution configuration, read from files only.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DnsConfig {
+    /// `/etc/resolv.conf`.
+    pub resolv_conf: ResolvConf,
+    pub resolved_mode: ResolvedMode,
+    /// The upstream servers systemd-resolved forwards to, from
+    /// `/run/systemd/resolve/resolv.conf`.
+    pub resolved_upstream: Option<ResolvConf>,
+    pub hosts: Hosts,
+    /// `None` without an `/etc/nsswitch.conf`.
+    pub nsswitch: Option<NsSwitch>,
+}
+
+impl Default for ResolverOptions {
+    /// The glibc defaults.
+    fn default() -> Self {
+        ResolverOptions {
+            ndots: 1,
+            timeout: 5,
+            attempts: 2,
+            rotate: false,
+            edns0: false,
+            trust_ad: false,
+            use_vc: false,
+            single_request: false,
+            single_request_reopen: false,
+            no_tld_query: false,
+            no_aaaa: false,
+            other: Vec::new(),
+        }
+    }
+}
+
+impl ResolverOptions {
+    /// Apply one option word, clamping values the way glibc does.
+    fn apply(&mut self, option: &str) {
+        let (name, value) = match option.split_once(':') {
+            Some((name, value)) => (name, value.parse::<u32>().ok()),
+            None => (option, None),
+        };
+
+        match (name, value) {
+            ("ndots", Some(value)) => self.ndots = value.min(15),
+            ("timeout", Some(value)) => self.timeout = value.min(30),
+            ("attempts", Some(value)) => self.attempts = value.min(5),
+            ("rotate", None) => self.rotate = true,
+            ("edns0", None) => self.edns0 = true,
+            ("trust-ad", None) => self.trust_ad = true,
+            ("use-vc", None) => self.use_vc = true,
+            ("single-request", None) => self.single_request = true,
+            ("single-request-reopen", None) => {
+                self.single_request_reopen = true
+            }
+            ("no-tld-query", None) => self.no_tld_query = true,
+            ("no-aaaa", None) => self.no_aaaa = true,
+            _ => self.other.push(option.to_string()),
+        }
+    }
+}
+
+impl ResolvConf {
+    /// Reads a `resolv.conf` file, returning `None` if it can't be read.
+    pub fn read(path: &Path) -> Option<Self> {
+        fs::read_to_string(path).ok().map(|content| Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let mut conf = ResolvConf::default();
+
+        for line in content.lines() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("nameserver") => {
+                    let address = words.next().and_then(parse_address);
+                    conf.nameservers.extend(address);
+                }
+                Some("domain") => {
+                    conf.domain = words.next().map(String::from);
+                    conf.search = conf.domain.iter().cloned().collect();
+                }
+                Some("search") => {
+                    conf.domain = None;
+                    conf.search = words.map(String::from).collect();
+                }
+                Some("sortlist") => {
+                    conf.sortlist = words.map(String::from).collect();
+                }
+                Some("options") => {
+                    for option in words {
+                        conf.options.apply(option);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        conf
+    }
+
+    /// The nameservers glibc actually queries.
+    pub fn effective_nameservers(&self) -> &[IpAddr] {
+        &self.nameservers[..self.nameservers.len().min(MAXNS)]
+    }
+
+    /// Whether every nameserver is a systemd-resolved stub listener.
+    pub fn is_resolved_stub(&self) -> bool {
+        !self.nameservers.is_empty()
+            && self.nameservers.iter().all(|ns| RESOLVED_STUBS.contains(ns))
+    }
+}
+
+impl Hosts {
+    pub fn parse(content: &str) -> Self {
+        let entries = content.lines()
+            .filter_map(|line| {
+                let line = line.split('#').next()?;
+                let mut words = line.split_whitespace();
+
+                Some(HostsEntry {
+                    address: parse_address(words.next()?)?,
+                    hostname: words.next()?.to_string(),
+                    aliases: words.map(String::from).collect(),
+                })
+            })
+            .collect();
+
+        Hosts { entries }
+    }
+
+    /// Addresses `name` resolves to, matching host names and aliases
+    /// without regard to case.
+    pub fn lookup(&self, name: &str) -> Vec<IpAddr> {
+        self.entries.iter()
+            .filter(|entry| {
+                entry.hostname.eq_ignore_ascii_case(name)
+                    || entry.aliases.iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(name))
+            })
+            .map(|entry| entry.address)
+            .collect()
+    }
+
+    /// The host name of the first entry for `address`.
+    pub fn hostname_of(&self, address: IpAddr) -> Option<&str> {
+        self.entries.iter()
+            .find(|entry| entry.address == address)
+            .map(|entry| entry.hostname.as_str())
+    }
+}
+
+impl NsSwitch {
+    pub fn parse(content: &str) -> Self {
+        let databases = content.lines()
+            .filter_map(|line| {
+                let line = line.split('#').next()?;
+                let (database, services) = line.split_once(':')?;
+                let database = database.trim();
+                if database.is_empty() {
+                    return None;
+                }
+
+                Some((database.to_string(), parse_services(services)))
+            })
+            .collect();
+
+        NsSwitch { databases }
+    }
+
+    pub fn services(&self, database: &str) -> Option<&[NsService]> {
+        self.databases.get(database).map(Vec::as_slice)
+    }
+}
+
+impl DnsConfig {
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/"))
+    }
+
+    /// Reads the configuration under `root`, which is `/` on a live system.
+    pub fn read_from(root: &Path) -> Self {
+        let resolv_path = root.join("etc/resolv.conf");
+        let resolv_conf = ResolvConf::read(&resolv_path).unwrap_or_default();
+
+        let link = fs::read_link(&resolv_path).ok();
+        let link_name = link.as_ref()
+            .and_then(|link| link.file_name())
+            .and_then(|name| name.to_str());
+        let links_to_resolved = link.as_ref()
+            .and_then(|link| link.parent())
+            .is_some_and(|dir| dir.ends_with("systemd/resolve"));
+
+        let resolved_mode = match link_name {
+            Some("stub-resolv.conf") if links_to_resolved => ResolvedMode::Stub,
+            Some("resolv.conf") if links_to_resolved => ResolvedMode::Uplink,
+            _ if resolv_conf.is_resolved_stub() => ResolvedMode::Stub,
+            _ => ResolvedMode::NotManaged,
+        };
+
+        let resolved_upstream = ResolvConf::read(
+            &root.join("run/systemd/resolve/resolv.conf")
+        );
+
+        let hosts = fs::read_to_string(root.join("etc/hosts"))
+            .map(|content| Hosts::parse(&content))
+            .unwrap_or_default();
+        let nsswitch = fs::read_to_string(root.join("etc/nsswitch.conf"))
+            .ok()
+            .map(|content| NsSwitch::parse(&content));
+
+        DnsConfig {
+            resolv_conf,
+            resolved_mode,
+            resolved_upstream,
+            hosts,
+            nsswitch,
+        }
+    }
+
+    /// The `hosts` services of `nsswitch.conf`, or glibc's default.
+    pub fn host_services(&self) -> Vec<NsService> {
+        self.nsswitch.as_ref()
+            .and_then(|nsswitch| nsswitch.services("hosts"))
+            .map(<[NsService]>::to_vec)
+            .unwrap_or_else(|| parse_services(DEFAULT_HOSTS_SERVICES))
+    }
+
+    /// The order in which a host name lookup through libc tries its
+    /// sources, with the servers each DNS step ends up asking.
+    pub fn resolution_order(&self) -> Vec<ResolutionStep> {
+        let upstream: Vec<IpAddr> = self.resolved_upstream.as_ref()
+            .map(|conf| conf.effective_nameservers().to_vec())
+            .unwrap_or_default();
+
+        self.host_services()
+            .into_iter()
+            .map(|service| {
+                let source = match service.name.as_str() {
+                    "files" => HostSource::Files {
+                        entries: self.hosts.entries.len(),
+                    },
+                    "dns" => HostSource::Dns {
+                        nameservers: self.resolv_conf
+                            .effective_nameservers()
+                            .to_vec(),
+                        upstream: if self.resolved_mode == ResolvedMode::Stub {
+                            upstream.clone()
+                        } else {
+                            Vec::new()
+                        },
+                    },
+                    "resolve" => HostSource::Resolve {
+                        upstream: upstream.clone(),
+                    },
+                    "myhostname" => HostSource::Myhostname,
+                    name if name.starts_with("mdns") => {
+                        HostSource::Mdns(name.to_string())
+                    }
+                    name => HostSource::Other(name.to_string()),
+                };
+
+                ResolutionStep { source, actions: service.actions }
+            })
+            .collect()
+    }
+}
+
+/// Parse an address, dropping an IPv6 zone such as `%eth0`.
+fn parse_address(text: &str) -> Option<IpAddr> {
+    text.split('%').next()?.parse().ok()
+}
+
+/// Parse the services of one database, like
+/// `files mdns4_minimal [NOTFOUND=return] dns`.
+fn parse_services(spec: &str) -> Vec<NsService> {
+    let mut services: Vec<NsService> = Vec::new();
+    let mut rest = spec.trim_start();
+
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let (criteria, after) = inner.split_once(']')
+                .unwrap_or((inner, ""));
+            if let Some(service) = services.last_mut() {
+                service.actions.extend(parse_actions(criteria));
+            }
+            rest = after.trim_start();
+        } else {
+            let end = rest.find(|c: char| c.is_whitespace() || c == '[')
+                .unwrap_or(rest.len());
+            services.push(NsService {
+                name: rest[..end].to_string(),
+                actions: Vec::new(),
+            });
+            rest = rest[end..].trim_start();
+        }
+    }
+
+    services
+}
+
+/// Parse the inside of a `[...]` block. Whitespace is allowed around `=`,
+/// and names are case-insensitive.
+fn parse_actions(criteria: &str) -> Vec<NsAction> {
+    let criteria = criteria.split('=')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("=");
+
+    criteria.split_whitespace()
+        .filter_map(|criterion| {
+            let (status, action) = criterion.split_once('=')?;
+            let (negated, status) = match status.strip_prefix('!') {
+                Some(status) => (true, status),
+                None => (false, status),
+            };
+
+            let status = match status.to_ascii_uppercase().as_str() {
+                "SUCCESS" => NsStatus::Success,
+                "NOTFOUND" => NsStatus::NotFound,
+                "UNAVAIL" => NsStatus::Unavail,
+                "TRYAGAIN" => NsStatus::TryAgain,
+                _ => return None,
+            };
+            let action = match action.to_ascii_lowercase().as_str() {
+                "return" => NsActionKind::Return,
+                "continue" => NsActionKind::Continue,
+                "merge" => NsActionKind::Merge,
+                _ => return None,
+            };
+
+            Some(NsAction { negated, status, action })
+        })
+        .collect()
+}
@@ -0,0 +1,235 @@
+//! Network namespaces on the host, and collecting from inside one.
+//!
+//! `/proc/net` and `/sys/class/net` show the namespace of the process
+//! that reads them. [`NetNamespace::run`] moves a helper thread into
+//! another namespace instead, with a sysfs of its own, so the collectors
+//! in [`crate::net`] can run there unchanged.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::MetadataExt;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::thread;
+use nix::libc;
+use serde::{Serialize, Deserialize};
+
+use super::Interface;
+use super::sockets::{self, InetSocket, UnixSocket};
+
+const PROC: &str = "/proc";
+const RUN_NETNS: &str = "/run/netns";
+
+/// `/proc/net` of the calling thread. `/proc/net` itself follows the
+/// process's main thread, so code inside [`NetNamespace::run`] must read
+/// this instead.
+pub const THREAD_PROC_NET: &str = "/proc/thread-self/net";
+
+/// A network namespace, and a file that refers to it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetNamespace {
+    /// Inode of the namespace, unique on the host while it exists. It is
+    /// the number in `net:[...]` links.
+    pub inode: u64,
+    /// `/run/netns/<name>` for named namespaces, else `/proc/<pid>/ns/net`
+    /// of one of its processes; the lowest PID when listed.
+    pub path: PathBuf,
+    /// Name given by `ip netns add`.
+    pub name: Option<String>,
+    /// Processes in the namespace, in PID order. Only filled in by
+    /// [`list_namespaces`].
+    pub pids: Vec<u32>,
+}
+
+/// What [`NetNamespace::collect`] finds inside a namespace.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NamespaceSnapshot {
+    pub namespace: NetNamespace,
+    pub interfaces: Vec<Interface>,
+    pub inet_sockets: Vec<InetSocket>,
+    pub unix_sockets: Vec<UnixSocket>,
+}
+
+impl NetNamespace {
+    /// The namespace of a process.
+    pub fn of_pid(pid: u32) -> io::Result<Self> {
+        Self::from_path(Path::new(PROC).join(pid.to_string()).join("ns/net"))
+    }
+
+    /// A namespace created by `ip netns add <name>`.
+    pub fn named(name: &str) -> io::Result<Self> {
+        let mut namespace = Self::from_path(Path::new(RUN_NETNS).join(name))?;
+        namespace.name = Some(name.to_string());
+        Ok(namespace)
+    }
+
+    /// The namespace a file refers to, such as `/proc/<pid>/ns/net` or a
+    /// bind mount of it.
+    pub fn from_path(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let inode = fs::metadata(&path)?.ino();
+        Ok(NetNamespace { inode, path, name: None, pids: Vec::new() })
+    }
+
+    /// Run `f` on a helper thread inside this namespace and return its
+    /// result. `f` gets the `/proc/net` directory to read, and the thread
+    /// has a private mount namespace with a fresh `/sys`, so
+    /// [`crate::net::get_interfaces`] sees the namespace's interfaces.
+    /// Netlink sockets opened by `f` also belong to the namespace.
+    ///
+    /// Needs `CAP_SYS_ADMIN`, both to enter the namespace and to mount
+    /// sysfs. A panic in `f` is passed on to the caller.
+    pub fn run<T, F>(&self, f: F) -> io::Result<T>
+    where
+        T: Send,
+        F: FnOnce(&Path) -> T + Send,
+    {
+        let file = File::open(&self.path)?;
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                enter(&file)?;
+                Ok(f(Path::new(THREAD_PROC_NET)))
+            })
+            .join()
+            .unwrap_or_else(|payload| panic::resume_unwind(payload))
+        })
+    }
+
+    /// Collect the interfaces and sockets of this namespace.
+    pub fn collect(&self) -> io::Result<NamespaceSnapshot> {
+        let (interfaces, inet_sockets, unix_sockets) = self.run(|proc_net| {
+            (
+                super::get_interfaces(),
+                sockets::read_all_inet_sockets(proc_net),
+                sockets::read_unix_sockets(proc_net),
+            )
+        })?;
+
+        Ok(NamespaceSnapshot {
+            namespace: self.clone(),
+            interfaces,
+            inet_sockets,
+            unix_sockets,
+        })
+    }
+}
+
+/// Move the calling thread into the namespace `file` refers to, and give
+/// it a mount namespace with a sysfs mounted from there.
+fn enter(file: &File) -> io::Result<()> {
+    let check = |result: libc::c_int| match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    };
+
+    unsafe {
+        check(libc::setns(file.as_raw_fd(), libc::CLONE_NEWNET))?;
+        check(libc::unshare(libc::CLONE_NEWNS))?;
+        // Keep the sysfs mount below from propagating to the host.
+        check(libc::mount(
+            c"none".as_ptr(), c"/".as_ptr(), std::ptr::null(),
+            libc::MS_REC | libc::MS_PRIVATE, std::ptr::null(),
+        ))?;
+        let mounted = check(libc::mount(
+            c"sysfs".as_ptr(), c"/sys".as_ptr(), c"sysfs".as_ptr(), 0,
+            std::ptr::null(),
+        ));
+        // The kernel won't stack a sysfs on itself, which it is when /sys
+        // already belongs to this namespace.
+        match mounted {
+            Err(error) if error.raw_os_error() == Some(libc::EBUSY) => Ok(()),
+            other => other,
+        }
+    }
+}
+
+/// Every network namespace with a process in it or a name, by inode.
+/// Processes whose namespace can't be read are left out.
+pub fn list_namespaces() -> Vec<NetNamespace> {
+    read_namespaces(Path::new(PROC), Path::new(RUN_NETNS))
+}
+
+/// Scans `<proc>/<pid>/ns/net` links and the files in a `/run/netns`
+/// directory.
+pub fn read_namespaces(proc: &Path, run_netns: &Path) -> Vec<NetNamespace> {
+    let mut namespaces: BTreeMap<u64, NetNamespace> = BTreeMap::new();
+
+    if let Ok(processes) = fs::read_dir(proc) {
+        for process in processes.flatten() {
+            let Some(pid) = process.file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            let path = process.path().join("ns/net");
+            let Some(inode) = fs::read_link(&path)
+                .ok()
+                .and_then(|target| parse_link(&target.to_string_lossy()))
+                else {
+                continue;
+            };
+
+            namespaces.entry(inode)
+                .or_insert_with(|| NetNamespace {
+                    inode,
+                    path,
+                    name: None,
+                    pids: Vec::new(),
+                })
+                .pids
+                .push(pid);
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(run_netns) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = fs::metadata(&path) else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            let namespace = namespaces.entry(metadata.ino())
+                .or_insert_with(|| NetNamespace {
+                    inode: metadata.ino(),
+                    path: path.clone(),
+                    name: None,
+                    pids: Vec::new(),
+                });
+            // A name outlives the processes, so prefer it for entering.
+            namespace.path = path;
+            namespace.name = Some(name);
+        }
+    }
+
+    let mut namespaces: Vec<NetNamespace> = namespaces.into_values()
+        .collect();
+    for namespace in &mut namespaces {
+        namespace.pids.sort_unstable();
+        if namespace.name.is_none() {
+            namespace.path = proc.join(namespace.pids[0].to_string())
+                .join("ns/net");
+        }
+    }
+    namespaces
+}
+
+/// Collect the interfaces and sockets of every namespace on the host.
+/// Namespaces that disappear or can't be entered are left out.
+pub fn collect_all() -> Vec<NamespaceSnapshot> {
+    list_namespaces()
+        .iter()
+        .filter_map(|namespace| namespace.collect().ok())
+        .collect()
+}
+
+/// Parse the inode out of a namespace link such as `net:[4026531840]`.
+fn parse_link(target: &str) -> Option<u64> {
+    target.strip_prefix("net:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,263 @@
+//! A small netlink client: enough to send one request, collect the replies
+//! and walk their attributes. Message layouts are left to the callers.
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::fd::{AsRawFd, OwnedFd};
+
+use nix::sys::socket::{
+    self, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol,
+    SockType,
+};
+
+pub(crate) const NLM_F_REQUEST: u16 = 0x1;
+pub(crate) const NLM_F_MULTI: u16 = 0x2;
+pub(crate) const NLM_F_ACK: u16 = 0x4;
+pub(crate) const NLM_F_DUMP: u16 = 0x300;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLMSG_HDRLEN: usize = 16;
+
+const NLA_HDRLEN: usize = 4;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+/// From `<linux/genetlink.h>`.
+pub(crate) const GENL_HDRLEN: usize = 4;
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+/// Large enough for the biggest message the kernel sends in one dump.
+const RECV_BUFFER: usize = 64 * 1024;
+
+/// One reply, without its `nlmsghdr`.
+pub(crate) struct Message {
+    pub msg_type: u16,
+    pub payload: Vec<u8>,
+}
+
+pub(crate) struct NetlinkSocket {
+    fd: OwnedFd,
+    seq: u32,
+}
+
+/// Round `len` up to the 4-byte alignment of netlink messages and
+/// attributes.
+pub(crate) fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+impl NetlinkSocket {
+    pub(crate) fn open(protocol: SockProtocol) -> io::Result<Self> {
+        let fd = socket::socket(
+            AddressFamily::Netlink,
+            SockType::Raw,
+            SockFlag::SOCK_CLOEXEC,
+            protocol,
+        )?;
+        socket::bind(fd.as_raw_fd(), &NetlinkAddr::new(0, 0))?;
+
+        Ok(NetlinkSocket { fd, seq: 0 })
+    }
+
+    /// Dump every object of a `GET` request type.
+    pub(crate) fn dump(&mut self, msg_type: u16, payload: &[u8])
+        -> io::Result<Vec<Message>> {
+        self.request(msg_type, NLM_F_DUMP, payload)
+    }
+
+    /// Send one request and collect its replies: every part of a dump, or
+    /// the single reply or acknowledgement to anything else. An error
+    /// reply is returned as the errno it carries.
+    pub(crate) fn request(&mut self, msg_type: u16, flags: u16, payload: &[u8])
+        -> io::Result<Vec<Message>> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+
+        let len = NLMSG_HDRLEN + payload.len();
+        let mut buf = Vec::with_capacity(align(len));
+        buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        buf.extend_from_slice(&msg_type.to_ne_bytes());
+        buf.extend_from_slice(&(flags | NLM_F_REQUEST).to_ne_bytes());
+        buf.extend_from_slice(&seq.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(payload);
+        buf.resize(align(len), 0);
+
+        socket::send(self.fd.as_raw_fd(), &buf, MsgFlags::empty())?;
+
+        let mut replies = Vec::new();
+        let mut recv_buf = vec![0u8; RECV_BUFFER];
+
+        loop {
+            let received = socket::recv(
+                self.fd.as_raw_fd(), &mut recv_buf, MsgFlags::empty()
+            )?;
+            let mut data = &recv_buf[..received];
+
+            while data.len() >= NLMSG_HDRLEN {
+                let len = u32_at(data, 0) as usize;
+                if len < NLMSG_HDRLEN || len > data.len() {
+                    break;
+                }
+
+                let reply_type = u16_at(data, 4);
+                let reply_flags = u16_at(data, 6);
+                let reply_seq = u32_at(data, 8);
+                let body = &data[NLMSG_HDRLEN..len];
+                data = &data[align(len).min(data.len())..];
+
+                if reply_seq != seq {
+                    continue;
+                }
+
+                match reply_type {
+                    NLMSG_DONE => return Ok(replies),
+                    NLMSG_ERROR => {
+                        let errno = body.get(..4)
+                            .map(|bytes| i32::from_ne_bytes(
+                                bytes.try_into().unwrap()
+                            ))
+                            .unwrap_or(0);
+                        if errno == 0 {
+                            return Ok(replies);
+                        }
+                        return Err(io::Error::from_raw_os_error(-errno));
+                    }
+                    _ => {
+                        replies.push(Message {
+                            msg_type: reply_type,
+                            payload: body.to_vec(),
+                        });
+
+                        let ack = flags & NLM_F_ACK != 0;
+                        if reply_flags & NLM_F_MULTI == 0 && !ack {
+                            return Ok(replies);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl NetlinkSocket {
+    /// Look up the id of a generic netlink family such as `nl80211`. A
+    /// family whose module isn't loaded is reported as `NotFound`.
+    pub(crate) fn genl_family(&mut self, name: &str) -> io::Result<u16> {
+        let mut family_name = name.as_bytes().to_vec();
+        family_name.push(0);
+        let request = genl_message(
+            CTRL_CMD_GETFAMILY, &[(CTRL_ATTR_FAMILY_NAME, &family_name)]
+        );
+
+        let not_found = || io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No generic netlink family {}", name),
+        );
+
+        let replies = self.request(GENL_ID_CTRL, 0, &request)
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::NotFound => not_found(),
+                _ => error,
+            })?;
+
+        replies.iter()
+            .filter_map(|reply| reply.payload.get(GENL_HDRLEN..))
+            .flat_map(attributes)
+            .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+            .map(|(_, value)| u16_at(value, 0))
+            .ok_or_else(not_found)
+    }
+}
+
+/// A generic netlink request body: the `genlmsghdr` for `cmd`, then the
+/// attributes.
+pub(crate) fn genl_message(cmd: u8, attrs: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut message = vec![cmd, 0, 0, 0];
+    for (kind, value) in attrs {
+        put_attribute(&mut message, *kind, value);
+    }
+    message
+}
+
+/// Append an attribute to `buf`, padded to the attribute alignment.
+pub(crate) fn put_attribute(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    let len = NLA_HDRLEN + value.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(buf.len() + align(len) - len, 0);
+}
+
+/// Iterator over the `(type, value)` pairs of a run of netlink
+/// attributes. The nested and byte order flags are stripped from the type.
+pub(crate) struct Attributes<'a> {
+    data: &'a [u8],
+}
+
+pub(crate) fn attributes(data: &[u8]) -> Attributes<'_> {
+    Attributes { data }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < NLA_HDRLEN {
+            return None;
+        }
+
+        let len = u16_at(self.data, 0) as usize;
+        if len < NLA_HDRLEN || len > self.data.len() {
+            return None;
+        }
+
+        let kind = u16_at(self.data, 2) & NLA_TYPE_MASK;
+        let value = &self.data[NLA_HDRLEN..len];
+        self.data = &self.data[align(len).min(self.data.len())..];
+
+        Some((kind, value))
+    }
+}
+
+pub(crate) fn u16_at(data: &[u8], offset: usize) -> u16 {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_ne_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+pub(crate) fn u32_at(data: &[u8], offset: usize) -> u32 {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+pub(crate) fn u64_at(data: &[u8], offset: usize) -> u64 {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+/// A NUL-terminated string attribute.
+pub(crate) fn string(value: &[u8]) -> String {
+    let end = value.iter().position(|b| *b == 0).unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).to_string()
+}
+
+/// An IPv4 or IPv6 address attribute, told apart by its length.
+pub(crate) fn ip_addr(value: &[u8]) -> Option<IpAddr> {
+    match value.len() {
+        4 => {
+            let octets: [u8; 4] = value.try_into().ok()?;
+            Some(IpAddr::V4(Ipv4Addr::from(octets)))
+        }
+        16 => {
+            let octets: [u8; 16] = value.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
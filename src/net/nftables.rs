@@ -0,0 +1,435 @@
+//! The nftables ruleset as the kernel holds it, dumped over
+//! `NETLINK_NETFILTER`. Reading it needs `CAP_NET_ADMIN` in the user
+//! namespace that owns the network namespace.
+
+use std::io;
+use nix::sys::socket::SockProtocol;
+use serde::{Serialize, Deserialize};
+
+use super::netlink::{self, NetlinkSocket};
+
+/// `NFNL_SUBSYS_NFTABLES` from `<linux/netfilter/nfnetlink.h>`, the high
+/// byte of every nftables message type.
+const NFNL_SUBSYS_NFTABLES: u16 = 10;
+
+/// Size of `struct nfgenmsg`, which starts every nfnetlink payload.
+const NFGENMSG_LEN: usize = 4;
+
+// Message types and attributes from <linux/netfilter/nf_tables.h>.
+// Integers in nftables attributes are big-endian.
+const NFT_MSG_NEWTABLE: u16 = 0;
+const NFT_MSG_GETTABLE: u16 = 1;
+const NFT_MSG_NEWCHAIN: u16 = 3;
+const NFT_MSG_GETCHAIN: u16 = 4;
+const NFT_MSG_NEWRULE: u16 = 6;
+const NFT_MSG_GETRULE: u16 = 7;
+const NFT_MSG_NEWOBJ: u16 = 18;
+const NFT_MSG_GETOBJ: u16 = 19;
+
+const NFTA_TABLE_NAME: u16 = 1;
+const NFTA_TABLE_FLAGS: u16 = 2;
+const NFTA_TABLE_USE: u16 = 3;
+const NFTA_TABLE_HANDLE: u16 = 4;
+
+const NFTA_CHAIN_TABLE: u16 = 1;
+const NFTA_CHAIN_HANDLE: u16 = 2;
+const NFTA_CHAIN_NAME: u16 = 3;
+const NFTA_CHAIN_HOOK: u16 = 4;
+const NFTA_CHAIN_POLICY: u16 = 5;
+const NFTA_CHAIN_USE: u16 = 6;
+const NFTA_CHAIN_TYPE: u16 = 7;
+const NFTA_CHAIN_COUNTERS: u16 = 8;
+
+const NFTA_HOOK_HOOKNUM: u16 = 1;
+const NFTA_HOOK_PRIORITY: u16 = 2;
+const NFTA_HOOK_DEV: u16 = 3;
+
+const NFTA_RULE_TABLE: u16 = 1;
+const NFTA_RULE_CHAIN: u16 = 2;
+const NFTA_RULE_HANDLE: u16 = 3;
+const NFTA_RULE_EXPRESSIONS: u16 = 4;
+const NFTA_RULE_USERDATA: u16 = 7;
+
+const NFTA_LIST_ELEM: u16 = 1;
+const NFTA_EXPR_NAME: u16 = 1;
+const NFTA_EXPR_DATA: u16 = 2;
+
+const NFTA_COUNTER_BYTES: u16 = 1;
+const NFTA_COUNTER_PACKETS: u16 = 2;
+
+const NFTA_OBJ_TABLE: u16 = 1;
+const NFTA_OBJ_NAME: u16 = 2;
+const NFTA_OBJ_TYPE: u16 = 3;
+const NFTA_OBJ_DATA: u16 = 4;
+const NFTA_OBJ_HANDLE: u16 = 6;
+
+const NFT_OBJECT_COUNTER: u32 = 1;
+
+/// `NFTNL_UDATA_RULE_COMMENT` from libnftnl: the comment nft(8) stores in
+/// a rule's user data, a run of one-byte type and length records.
+const UDATA_RULE_COMMENT: u8 = 0;
+
+/// Address family of a table, `NFPROTO_*` from `<linux/netfilter.h>`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    Inet,
+    Ip,
+    Arp,
+    Netdev,
+    Bridge,
+    Ip6,
+    Other(u8),
+}
+
+impl Family {
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            1 => Self::Inet,
+            2 => Self::Ip,
+            3 => Self::Arp,
+            5 => Self::Netdev,
+            7 => Self::Bridge,
+            10 => Self::Ip6,
+            other => Self::Other(other),
+        }
+    }
+}
+
+/// Netfilter verdict, used as the policy of base chains.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Drop,
+    Accept,
+    Other(u32),
+}
+
+impl Verdict {
+    pub fn from_raw(value: u32) -> Self {
+        match value {
+            0 => Self::Drop,
+            1 => Self::Accept,
+            other => Self::Other(other),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counter {
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Table {
+    pub family: Family,
+    pub name: String,
+    pub handle: u64,
+    pub flags: u32,
+    /// Chains, sets and objects in the table.
+    pub use_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Chain {
+    pub family: Family,
+    pub table: String,
+    pub name: String,
+    pub handle: u64,
+    /// Hook number of a base chain; what it names depends on the family,
+    /// e.g. 1 is `input` for `inet` but `egress` for `netdev`.
+    pub hook: Option<u32>,
+    pub priority: Option<i32>,
+    /// Device of a `netdev` base chain.
+    pub device: Option<String>,
+    /// `filter`, `nat` or `route`, for base chains.
+    pub chain_type: Option<String>,
+    pub policy: Option<Verdict>,
+    /// References the kernel holds on the chain, such as jumps to it.
+    pub use_count: u32,
+    /// Counters of a base chain, when the kernel keeps them.
+    pub counter: Option<Counter>,
+}
+
+impl Chain {
+    pub fn is_base_chain(&self) -> bool {
+        self.hook.is_some()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub family: Family,
+    pub table: String,
+    pub chain: String,
+    pub handle: u64,
+    /// Expression names in order, e.g. `["meta", "cmp", "counter"]`.
+    pub expressions: Vec<String>,
+    /// Values of the rule's anonymous `counter` statement.
+    pub counter: Option<Counter>,
+    pub comment: Option<String>,
+}
+
+/// A named counter object, referred to by `counter name` statements.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NamedCounter {
+    pub family: Family,
+    pub table: String,
+    pub name: String,
+    pub handle: u64,
+    pub counter: Counter,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Ruleset {
+    pub tables: Vec<Table>,
+    pub chains: Vec<Chain>,
+    pub rules: Vec<Rule>,
+    pub counters: Vec<NamedCounter>,
+}
+
+impl Ruleset {
+    pub fn table(&self, family: Family, name: &str) -> Option<&Table> {
+        self.tables.iter()
+            .find(|table| table.family == family && table.name == name)
+    }
+
+    pub fn chain(&self, family: Family, table: &str, name: &str)
+        -> Option<&Chain> {
+        self.chains.iter().find(|chain| {
+            chain.family == family && chain.table == table
+                && chain.name == name
+        })
+    }
+
+    /// Rules of a chain, in evaluation order.
+    pub fn rules_of<'a>(&'a self, chain: &'a Chain)
+        -> impl Iterator<Item = &'a Rule> {
+        self.rules.iter().filter(move |rule| {
+            rule.family == chain.family && rule.table == chain.table
+                && rule.chain == chain.name
+        })
+    }
+
+    pub fn counter(&self, family: Family, table: &str, name: &str)
+        -> Option<&NamedCounter> {
+        self.counters.iter().find(|counter| {
+            counter.family == family && counter.table == table
+                && counter.name == name
+        })
+    }
+}
+
+/// Dump the tables, chains, rules and named counters of every family in
+/// the calling thread's network namespace.
+pub fn get_ruleset() -> io::Result<Ruleset> {
+    let mut socket = NetlinkSocket::open(SockProtocol::NetlinkNetFilter)?;
+
+    Ok(Ruleset {
+        tables: dump(&mut socket, NFT_MSG_GETTABLE, NFT_MSG_NEWTABLE,
+                     parse_table)?,
+        chains: dump(&mut socket, NFT_MSG_GETCHAIN, NFT_MSG_NEWCHAIN,
+                     parse_chain)?,
+        rules: dump(&mut socket, NFT_MSG_GETRULE, NFT_MSG_NEWRULE,
+                    parse_rule)?,
+        counters: dump(&mut socket, NFT_MSG_GETOBJ, NFT_MSG_NEWOBJ,
+                       parse_counter_object)?,
+    })
+}
+
+/// Dump one kind of object in all families and parse each reply of type
+/// `reply` with its family and attributes.
+fn dump<T>(
+    socket: &mut NetlinkSocket,
+    request: u16,
+    reply: u16,
+    parse: fn(Family, &[u8]) -> Option<T>,
+) -> io::Result<Vec<T>> {
+    // An nfgenmsg for NFPROTO_UNSPEC, which matches every family.
+    let header = [0u8; NFGENMSG_LEN];
+    let replies = socket.dump(NFNL_SUBSYS_NFTABLES << 8 | request, &header)?;
+
+    Ok(replies.iter()
+        .filter(|message| message.msg_type == NFNL_SUBSYS_NFTABLES << 8 | reply)
+        .filter(|message| message.payload.len() >= NFGENMSG_LEN)
+        .filter_map(|message| {
+            let family = Family::from_raw(message.payload[0]);
+            parse(family, &message.payload[NFGENMSG_LEN..])
+        })
+        .collect())
+}
+
+fn be32(value: &[u8]) -> u32 {
+    value.get(..4)
+        .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+fn be64(value: &[u8]) -> u64 {
+    value.get(..8)
+        .map(|bytes| u64::from_be_bytes(bytes.try_into().unwrap()))
+        .unwrap_or(0)
+}
+
+fn parse_table(family: Family, data: &[u8]) -> Option<Table> {
+    let mut table = Table {
+        family,
+        name: String::new(),
+        handle: 0,
+        flags: 0,
+        use_count: 0,
+    };
+
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NFTA_TABLE_NAME => table.name = netlink::string(value),
+            NFTA_TABLE_FLAGS => table.flags = be32(value),
+            NFTA_TABLE_USE => table.use_count = be32(value),
+            NFTA_TABLE_HANDLE => table.handle = be64(value),
+            _ => {}
+        }
+    }
+
+    Some(table)
+}
+
+fn parse_chain(family: Family, data: &[u8]) -> Option<Chain> {
+    let mut chain = Chain {
+        family,
+        table: String::new(),
+        name: String::new(),
+        handle: 0,
+        hook: None,
+        priority: None,
+        device: None,
+        chain_type: None,
+        policy: None,
+        use_count: 0,
+        counter: None,
+    };
+
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NFTA_CHAIN_TABLE => chain.table = netlink::string(value),
+            NFTA_CHAIN_HANDLE => chain.handle = be64(value),
+            NFTA_CHAIN_NAME => chain.name = netlink::string(value),
+            NFTA_CHAIN_HOOK => {
+                for (kind, value) in netlink::attributes(value) {
+                    match kind {
+                        NFTA_HOOK_HOOKNUM => chain.hook = Some(be32(value)),
+                        NFTA_HOOK_PRIORITY => {
+                            chain.priority = Some(be32(value) as i32);
+                        }
+                        NFTA_HOOK_DEV => {
+                            chain.device = Some(netlink::string(value));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            NFTA_CHAIN_POLICY => {
+                chain.policy = Some(Verdict::from_raw(be32(value)));
+            }
+            NFTA_CHAIN_USE => chain.use_count = be32(value),
+            NFTA_CHAIN_TYPE => chain.chain_type = Some(netlink::string(value)),
+            NFTA_CHAIN_COUNTERS => chain.counter = Some(parse_counter(value)),
+            _ => {}
+        }
+    }
+
+    Some(chain)
+}
+
+fn parse_rule(family: Family, data: &[u8]) -> Option<Rule> {
+    let mut rule = Rule {
+        family,
+        table: String::new(),
+        chain: String::new(),
+        handle: 0,
+        expressions: Vec::new(),
+        counter: None,
+        comment: None,
+    };
+
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NFTA_RULE_TABLE => rule.table = netlink::string(value),
+            NFTA_RULE_CHAIN => rule.chain = netlink::string(value),
+            NFTA_RULE_HANDLE => rule.handle = be64(value),
+            NFTA_RULE_EXPRESSIONS => {
+                let expressions = netlink::attributes(value)
+                    .filter(|(kind, _)| *kind == NFTA_LIST_ELEM);
+                for (_, expression) in expressions {
+                    let mut name = String::new();
+                    let mut expression_data: &[u8] = &[];
+                    for (kind, value) in netlink::attributes(expression) {
+                        match kind {
+                            NFTA_EXPR_NAME => name = netlink::string(value),
+                            NFTA_EXPR_DATA => expression_data = value,
+                            _ => {}
+                        }
+                    }
+
+                    if name == "counter" && rule.counter.is_none() {
+                        rule.counter = Some(parse_counter(expression_data));
+                    }
+                    rule.expressions.push(name);
+                }
+            }
+            NFTA_RULE_USERDATA => rule.comment = parse_comment(value),
+            _ => {}
+        }
+    }
+
+    Some(rule)
+}
+
+/// Named objects of other types, such as quotas, are skipped.
+fn parse_counter_object(family: Family, data: &[u8])
+    -> Option<NamedCounter> {
+    let mut table = String::new();
+    let mut name = String::new();
+    let mut handle = 0;
+    let mut object_type = None;
+    let mut counter = Counter::default();
+
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NFTA_OBJ_TABLE => table = netlink::string(value),
+            NFTA_OBJ_NAME => name = netlink::string(value),
+            NFTA_OBJ_TYPE => object_type = Some(be32(value)),
+            NFTA_OBJ_DATA => counter = parse_counter(value),
+            NFTA_OBJ_HANDLE => handle = be64(value),
+            _ => {}
+        }
+    }
+
+    if object_type != Some(NFT_OBJECT_COUNTER) {
+        return None;
+    }
+
+    Some(NamedCounter { family, table, name, handle, counter })
+}
+
+fn parse_counter(data: &[u8]) -> Counter {
+    let mut counter = Counter::default();
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NFTA_COUNTER_BYTES => counter.bytes = be64(value),
+            NFTA_COUNTER_PACKETS => counter.packets = be64(value),
+            _ => {}
+        }
+    }
+    counter
+}
+
+fn parse_comment(mut data: &[u8]) -> Option<String> {
+    while let [kind, len, rest @ ..] = data {
+        let len = *len as usize;
+        let value = rest.get(..len)?;
+        if *kind == UDATA_RULE_COMMENT {
+            return Some(netlink::string(value));
+        }
+        data = &rest[len..];
+    }
+    None
+}
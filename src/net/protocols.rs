@@ -0,0 +1,491 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// Generates a struct of counters, each read from the kernel name after a
+/// group prefix (`Tcp.` in `/proc/net/snmp`, `Udp6` in `/proc/net/snmp6`,
+/// ...). A counter is `None` when the running kernel does not report it,
+/// so it is never confused with one that really is 0.
+macro_rules! protocol_counters {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($(#[$field_meta:meta])* $field:ident: $key:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+        pub struct $name {
+            $($(#[$field_meta])* pub $field: Option<u64>,)*
+        }
+
+        impl $name {
+            fn from_counters(counters: &BTreeMap<String, u64>, prefix: &str)
+                -> Self {
+                $name {
+                    $(
+                        $field: counters.get(&format!("{}{}", prefix, $key))
+                            .copied(),
+                    )*
+                }
+            }
+
+            /// Increase of every counter since `earlier`. A counter that
+            /// went backwards counts as 0, one missing from either sample
+            /// stays `None`.
+            pub fn delta_since(&self, earlier: &Self) -> Self {
+                $name {
+                    $($field: delta(self.$field, earlier.$field),)*
+                }
+            }
+        }
+    };
+}
+
+protocol_counters! {
+    /// IPv4 counters, the `Ip` group of `/proc/net/snmp`.
+    IpStats {
+        in_receives: "InReceives",
+        in_hdr_errors: "InHdrErrors",
+        in_addr_errors: "InAddrErrors",
+        forw_datagrams: "ForwDatagrams",
+        in_unknown_protos: "InUnknownProtos",
+        in_discards: "InDiscards",
+        in_delivers: "InDelivers",
+        out_requests: "OutRequests",
+        out_discards: "OutDiscards",
+        out_no_routes: "OutNoRoutes",
+        reasm_timeout: "ReasmTimeout",
+        reasm_reqds: "ReasmReqds",
+        reasm_oks: "ReasmOKs",
+        reasm_fails: "ReasmFails",
+        frag_oks: "FragOKs",
+        frag_fails: "FragFails",
+        frag_creates: "FragCreates",
+    }
+}
+
+protocol_counters! {
+    /// More IPv4 counters, the `IpExt` group of `/proc/net/netstat`.
+    IpExtStats {
+        in_no_routes: "InNoRoutes",
+        in_truncated_pkts: "InTruncatedPkts",
+        in_mcast_pkts: "InMcastPkts",
+        out_mcast_pkts: "OutMcastPkts",
+        in_bcast_pkts: "InBcastPkts",
+        out_bcast_pkts: "OutBcastPkts",
+        in_octets: "InOctets",
+        out_octets: "OutOctets",
+        in_csum_errors: "InCsumErrors",
+        reasm_overlaps: "ReasmOverlaps",
+    }
+}
+
+protocol_counters! {
+    /// IPv6 counters from `/proc/net/snmp6`.
+    Ip6Stats {
+        in_receives: "InReceives",
+        in_hdr_errors: "InHdrErrors",
+        in_too_big_errors: "InTooBigErrors",
+        in_no_routes: "InNoRoutes",
+        in_addr_errors: "InAddrErrors",
+        in_unknown_protos: "InUnknownProtos",
+        in_truncated_pkts: "InTruncatedPkts",
+        in_discards: "InDiscards",
+        in_delivers: "InDelivers",
+        out_forw_datagrams: "OutForwDatagrams",
+        out_requests: "OutRequests",
+        out_discards: "OutDiscards",
+        out_no_routes: "OutNoRoutes",
+        reasm_timeout: "ReasmTimeout",
+        reasm_reqds: "ReasmReqds",
+        reasm_oks: "ReasmOKs",
+        reasm_fails: "ReasmFails",
+        frag_oks: "FragOKs",
+        frag_fails: "FragFails",
+        frag_creates: "FragCreates",
+        in_octets: "InOctets",
+        out_octets: "OutOctets",
+    }
+}
+
+protocol_counters! {
+    /// ICMP counters common to ICMPv4 and ICMPv6.
+    IcmpStats {
+        in_msgs: "InMsgs",
+        in_errors: "InErrors",
+        in_csum_errors: "InCsumErrors",
+        in_dest_unreachs: "InDestUnreachs",
+        in_time_excds: "InTimeExcds",
+        in_echos: "InEchos",
+        out_msgs: "OutMsgs",
+        out_errors: "OutErrors",
+        out_dest_unreachs: "OutDestUnreachs",
+        out_time_excds: "OutTimeExcds",
+        out_echos: "OutEchos",
+    }
+}
+
+protocol_counters! {
+    /// TCP counters, the `Tcp` group of `/proc/net/snmp`. They cover IPv4
+    /// and IPv6 together.
+    TcpStats {
+        active_opens: "ActiveOpens",
+        passive_opens: "PassiveOpens",
+        attempt_fails: "AttemptFails",
+        estab_resets: "EstabResets",
+        in_segs: "InSegs",
+        out_segs: "OutSegs",
+        retrans_segs: "RetransSegs",
+        in_errs: "InErrs",
+        out_rsts: "OutRsts",
+        in_csum_errors: "InCsumErrors",
+    }
+}
+
+protocol_counters! {
+    /// Linux-specific TCP counters, the `TcpExt` group of
+    /// `/proc/net/netstat`.
+    TcpExtStats {
+        syncookies_sent: "SyncookiesSent",
+        syncookies_recv: "SyncookiesRecv",
+        syncookies_failed: "SyncookiesFailed",
+        embryonic_rsts: "EmbryonicRsts",
+        prune_called: "PruneCalled",
+        rcv_pruned: "RcvPruned",
+        ofo_pruned: "OfoPruned",
+        time_waited: "TW",
+        delayed_acks: "DelayedACKs",
+        /// Connections dropped because a listener's accept queue was full.
+        listen_overflows: "ListenOverflows",
+        /// Every connection dropped by a listener, overflows included.
+        listen_drops: "ListenDrops",
+        lost_retransmit: "TCPLostRetransmit",
+        fast_retrans: "TCPFastRetrans",
+        slow_start_retrans: "TCPSlowStartRetrans",
+        syn_retrans: "TCPSynRetrans",
+        retrans_fail: "TCPRetransFail",
+        timeouts: "TCPTimeouts",
+        abort_on_data: "TCPAbortOnData",
+        abort_on_close: "TCPAbortOnClose",
+        abort_on_memory: "TCPAbortOnMemory",
+        abort_on_timeout: "TCPAbortOnTimeout",
+        abort_on_linger: "TCPAbortOnLinger",
+        abort_failed: "TCPAbortFailed",
+        memory_pressures: "TCPMemoryPressures",
+        backlog_drop: "TCPBacklogDrop",
+        req_q_full_drop: "TCPReqQFullDrop",
+        req_q_full_do_cookies: "TCPReqQFullDoCookies",
+        ofo_drop: "TCPOFODrop",
+        rcv_q_drop: "TCPRcvQDrop",
+        zero_window_drop: "TCPZeroWindowDrop",
+    }
+}
+
+protocol_counters! {
+    /// UDP or UDP-Lite counters, for IPv4 or IPv6.
+    UdpStats {
+        in_datagrams: "InDatagrams",
+        no_ports: "NoPorts",
+        in_errors: "InErrors",
+        out_datagrams: "OutDatagrams",
+        /// Datagrams dropped because the receive buffer was full.
+        rcvbuf_errors: "RcvbufErrors",
+        sndbuf_errors: "SndbufErrors",
+        in_csum_errors: "InCsumErrors",
+        ignored_multi: "IgnoredMulti",
+        mem_errors: "MemErrors",
+    }
+}
+
+/// Protocol counters from `/proc/net/snmp`, `/proc/net/snmp6` and
+/// `/proc/net/netstat`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProtocolStats {
+    pub ip: IpStats,
+    pub ip_ext: IpExtStats,
+    pub ip6: Ip6Stats,
+    pub icmp: IcmpStats,
+    pub icmp6: IcmpStats,
+    pub tcp: TcpStats,
+    pub tcp_ext: TcpExtStats,
+    /// Currently established TCP connections. This is a gauge, so a delta
+    /// keeps the later value.
+    pub tcp_curr_estab: Option<u64>,
+    pub udp: UdpStats,
+    pub udp6: UdpStats,
+    pub udp_lite: UdpStats,
+    pub udp_lite6: UdpStats,
+}
+
+/// Per-second rates between two [`ProtocolStats`] samples. A rate is
+/// `None` when the kernel lacks its counter. UDP and IP fragment rates
+/// add up IPv4 and IPv6.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ProtocolRates {
+    pub interval_secs: f64,
+    pub tcp_in_segs_per_sec: Option<f64>,
+    pub tcp_out_segs_per_sec: Option<f64>,
+    pub tcp_retransmits_per_sec: Option<f64>,
+    pub listen_overflows_per_sec: Option<f64>,
+    pub listen_drops_per_sec: Option<f64>,
+    pub udp_in_datagrams_per_sec: Option<f64>,
+    pub udp_out_datagrams_per_sec: Option<f64>,
+    /// Datagrams dropped because a receive buffer was full.
+    pub udp_rcvbuf_errors_per_sec: Option<f64>,
+    /// Packets received as fragments and waiting to be reassembled.
+    pub ip_reasm_reqds_per_sec: Option<f64>,
+    pub ip_reasm_fails_per_sec: Option<f64>,
+    /// Packets fragmented on the way out.
+    pub ip_frag_oks_per_sec: Option<f64>,
+    /// Packets dropped because they needed fragmenting but could not be,
+    /// e.g. for the Don't Fragment flag.
+    pub ip_frag_fails_per_sec: Option<f64>,
+    pub ip_frag_creates_per_sec: Option<f64>,
+}
+
+/// Socket usage from `/proc/net/sockstat` and `/proc/net/sockstat6`.
+/// Memory is in pages, fragment memory in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SockStat {
+    pub sockets_used: u64,
+    pub tcp_inuse: u64,
+    pub tcp_orphan: u64,
+    pub tcp_time_wait: u64,
+    pub tcp_alloc: u64,
+    pub tcp_mem: u64,
+    pub udp_inuse: u64,
+    pub udp_mem: u64,
+    pub udp_lite_inuse: u64,
+    pub raw_inuse: u64,
+    pub frag_inuse: u64,
+    pub frag_memory: u64,
+    pub tcp6_inuse: u64,
+    pub udp6_inuse: u64,
+    pub udp_lite6_inuse: u64,
+    pub raw6_inuse: u64,
+    pub frag6_inuse: u64,
+    pub frag6_memory: u64,
+}
+
+impl TcpStats {
+    /// Share of sent segments that were retransmissions.
+    pub fn retransmit_ratio(&self) -> Option<f64> {
+        let out_segs = self.out_segs.filter(|&segs| segs > 0)?;
+        Some(self.retrans_segs? as f64 / out_segs as f64)
+    }
+}
+
+impl ProtocolStats {
+    /// Reads the counters from `/proc/net`, leaving the groups of any file
+    /// that can't be read empty.
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/proc/net"))
+    }
+
+    /// Reads `snmp`, `snmp6` and `netstat` from `dir`.
+    pub fn read_from(dir: &Path) -> Self {
+        let read = |name: &str| {
+            fs::read_to_string(dir.join(name)).unwrap_or_default()
+        };
+
+        Self::parse(&read("snmp"), &read("snmp6"), &read("netstat"))
+    }
+
+    /// Parse the text of `/proc/net/snmp`, `/proc/net/snmp6` and
+    /// `/proc/net/netstat`.
+    pub fn parse(snmp: &str, snmp6: &str, netstat: &str) -> Self {
+        let mut counters = parse_grouped(snmp);
+        counters.extend(parse_grouped(netstat));
+        counters.extend(parse_flat(snmp6));
+
+        ProtocolStats {
+            ip: IpStats::from_counters(&counters, "Ip."),
+            ip_ext: IpExtStats::from_counters(&counters, "IpExt."),
+            ip6: Ip6Stats::from_counters(&counters, "Ip6"),
+            icmp: IcmpStats::from_counters(&counters, "Icmp."),
+            icmp6: IcmpStats::from_counters(&counters, "Icmp6"),
+            tcp: TcpStats::from_counters(&counters, "Tcp."),
+            tcp_ext: TcpExtStats::from_counters(&counters, "TcpExt."),
+            tcp_curr_estab: counters.get("Tcp.CurrEstab").copied(),
+            udp: UdpStats::from_counters(&counters, "Udp."),
+            udp6: UdpStats::from_counters(&counters, "Udp6"),
+            udp_lite: UdpStats::from_counters(&counters, "UdpLite."),
+            udp_lite6: UdpStats::from_counters(&counters, "UdpLite6"),
+        }
+    }
+
+    /// Increase of every counter since `earlier`.
+    pub fn delta_since(&self, earlier: &ProtocolStats) -> ProtocolStats {
+        ProtocolStats {
+            ip: self.ip.delta_since(&earlier.ip),
+            ip_ext: self.ip_ext.delta_since(&earlier.ip_ext),
+            ip6: self.ip6.delta_since(&earlier.ip6),
+            icmp: self.icmp.delta_since(&earlier.icmp),
+            icmp6: self.icmp6.delta_since(&earlier.icmp6),
+            tcp: self.tcp.delta_since(&earlier.tcp),
+            tcp_ext: self.tcp_ext.delta_since(&earlier.tcp_ext),
+            tcp_curr_estab: self.tcp_curr_estab,
+            udp: self.udp.delta_since(&earlier.udp),
+            udp6: self.udp6.delta_since(&earlier.udp6),
+            udp_lite: self.udp_lite.delta_since(&earlier.udp_lite),
+            udp_lite6: self.udp_lite6.delta_since(&earlier.udp_lite6),
+        }
+    }
+
+    /// Rates since `earlier`, taken `interval` before this sample.
+    pub fn rates_since(&self, earlier: &ProtocolStats, interval: Duration)
+        -> ProtocolRates {
+        let secs = interval.as_secs_f64();
+        if secs <= 0.0 {
+            return ProtocolRates::default();
+        }
+
+        let d = self.delta_since(earlier);
+        let rate = |delta: Option<u64>| delta.map(|delta| delta as f64 / secs);
+
+        ProtocolRates {
+            interval_secs: secs,
+            tcp_in_segs_per_sec: rate(d.tcp.in_segs),
+            tcp_out_segs_per_sec: rate(d.tcp.out_segs),
+            tcp_retransmits_per_sec: rate(d.tcp.retrans_segs),
+            listen_overflows_per_sec: rate(d.tcp_ext.listen_overflows),
+            listen_drops_per_sec: rate(d.tcp_ext.listen_drops),
+            udp_in_datagrams_per_sec:
+                rate(sum(d.udp.in_datagrams, d.udp6.in_datagrams)),
+            udp_out_datagrams_per_sec:
+                rate(sum(d.udp.out_datagrams, d.udp6.out_datagrams)),
+            udp_rcvbuf_errors_per_sec:
+                rate(sum(d.udp.rcvbuf_errors, d.udp6.rcvbuf_errors)),
+            ip_reasm_reqds_per_sec:
+                rate(sum(d.ip.reasm_reqds, d.ip6.reasm_reqds)),
+            ip_reasm_fails_per_sec:
+                rate(sum(d.ip.reasm_fails, d.ip6.reasm_fails)),
+            ip_frag_oks_per_sec: rate(sum(d.ip.frag_oks, d.ip6.frag_oks)),
+            ip_frag_fails_per_sec:
+                rate(sum(d.ip.frag_fails, d.ip6.frag_fails)),
+            ip_frag_creates_per_sec:
+                rate(sum(d.ip.frag_creates, d.ip6.frag_creates)),
+        }
+    }
+
+    /// Reads the protocol counters twice, `interval` apart, and returns
+    /// the TCP, UDP and IP fragment rates over the time that actually
+    /// passed.
+    pub fn sample_rates(interval: Duration) -> ProtocolRates {
+        let earlier = ProtocolStats::new();
+        let start = Instant::now();
+        thread::sleep(interval);
+        let later = ProtocolStats::new();
+
+        later.rates_since(&earlier, start.elapsed())
+    }
+}
+
+impl SockStat {
+    /// Reads `/proc/net/sockstat` and `/proc/net/sockstat6`.
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/proc/net"))
+    }
+
+    /// Reads `sockstat` and `sockstat6` from `dir`.
+    pub fn read_from(dir: &Path) -> Self {
+        let read = |name: &str| {
+            fs::read_to_string(dir.join(name)).unwrap_or_default()
+        };
+
+        Self::parse(&read("sockstat"), &read("sockstat6"))
+    }
+
+    /// Parse the text of `/proc/net/sockstat` and `/proc/net/sockstat6`.
+    pub fn parse(sockstat: &str, sockstat6: &str) -> Self {
+        // Lines look like `TCP: inuse 10 orphan 0 tw 0 alloc 10 mem 0`.
+        let mut values: BTreeMap<String, u64> = BTreeMap::new();
+        for line in sockstat.lines().chain(sockstat6.lines()) {
+            let Some((group, pairs)) = line.split_once(':') else {
+                continue;
+            };
+            let words: Vec<&str> = pairs.split_whitespace().collect();
+            for pair in words.chunks_exact(2) {
+                if let Ok(value) = pair[1].parse() {
+                    values.insert(format!("{}.{}", group, pair[0]), value);
+                }
+            }
+        }
+
+        let get = |key: &str| values.get(key).copied().unwrap_or(0);
+
+        SockStat {
+            sockets_used: get("sockets.used"),
+            tcp_inuse: get("TCP.inuse"),
+            tcp_orphan: get("TCP.orphan"),
+            tcp_time_wait: get("TCP.tw"),
+            tcp_alloc: get("TCP.alloc"),
+            tcp_mem: get("TCP.mem"),
+            udp_inuse: get("UDP.inuse"),
+            udp_mem: get("UDP.mem"),
+            udp_lite_inuse: get("UDPLITE.inuse"),
+            raw_inuse: get("RAW.inuse"),
+            frag_inuse: get("FRAG.inuse"),
+            frag_memory: get("FRAG.memory"),
+            tcp6_inuse: get("TCP6.inuse"),
+            udp6_inuse: get("UDP6.inuse"),
+            udp_lite6_inuse: get("UDPLITE6.inuse"),
+            raw6_inuse: get("RAW6.inuse"),
+            frag6_inuse: get("FRAG6.inuse"),
+            frag6_memory: get("FRAG6.memory"),
+        }
+    }
+}
+
+/// Increase from `before` to `now`, or `None` if either is missing.
+fn delta(now: Option<u64>, before: Option<u64>) -> Option<u64> {
+    Some(now?.saturating_sub(before?))
+}
+
+/// Adds up the IPv4 and IPv6 variants of a counter, either of which may
+/// be missing, e.g. with IPv6 disabled.
+fn sum(v4: Option<u64>, v6: Option<u64>) -> Option<u64> {
+    match (v4, v6) {
+        (Some(v4), Some(v6)) => Some(v4 + v6),
+        (v4, v6) => v4.or(v6),
+    }
+}
+
+/// Parse the header/value line pairs of `/proc/net/snmp` and
+/// `/proc/net/netstat` into `Group.Name` keys. Negative values, such as
+/// `Tcp: MaxConn -1`, are skipped.
+fn parse_grouped(content: &str) -> BTreeMap<String, u64> {
+    let mut counters = BTreeMap::new();
+    let mut lines = content.lines();
+
+    while let (Some(names), Some(values)) = (lines.next(), lines.next()) {
+        let (Some((group, names)), Some((_, values))) =
+            (names.split_once(':'), values.split_once(':')) else {
+            continue;
+        };
+
+        for (name, value) in names.split_whitespace()
+            .zip(values.split_whitespace()) {
+            if let Ok(value) = value.parse() {
+                counters.insert(format!("{}.{}", group, name), value);
+            }
+        }
+    }
+
+    counters
+}
+
+/// Parse the `Name value` lines of `/proc/net/snmp6`.
+fn parse_flat(content: &str) -> BTreeMap<String, u64> {
+    content.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            Some((name.to_string(), fields.next()?.parse().ok()?))
+        })
+        .collect()
+}
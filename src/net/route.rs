@@ -0,0 +1,616 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::IpAddr;
+use nix::sys::socket::SockProtocol;
+use serde::{Serialize, Deserialize};
+
+use super::netlink::{self, NetlinkSocket};
+
+// Message types, attributes and flags from <linux/rtnetlink.h>,
+// <linux/if_addr.h> and <linux/fib_rules.h>.
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_GETADDR: u16 = 22;
+const RTM_NEWROUTE: u16 = 24;
+const RTM_GETROUTE: u16 = 26;
+const RTM_NEWRULE: u16 = 32;
+const RTM_GETRULE: u16 = 34;
+
+const IFLA_IFNAME: u16 = 3;
+
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_LABEL: u16 = 3;
+const IFA_BROADCAST: u16 = 4;
+const IFA_CACHEINFO: u16 = 6;
+const IFA_FLAGS: u16 = 8;
+
+const ADDRESS_FLAGS: [(u32, &str); 12] = [
+    (0x01, "secondary"),
+    (0x02, "nodad"),
+    (0x04, "optimistic"),
+    (0x08, "dadfailed"),
+    (0x10, "home"),
+    (0x20, "deprecated"),
+    (0x40, "tentative"),
+    (0x80, "permanent"),
+    (0x100, "mngtmpaddr"),
+    (0x200, "noprefixroute"),
+    (0x400, "autojoin"),
+    (0x800, "stable-privacy"),
+];
+
+const RTA_DST: u16 = 1;
+const RTA_SRC: u16 = 2;
+const RTA_IIF: u16 = 3;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_PREFSRC: u16 = 7;
+const RTA_METRICS: u16 = 8;
+const RTA_MULTIPATH: u16 = 9;
+const RTA_TABLE: u16 = 15;
+const RTA_VIA: u16 = 18;
+const RTA_PREF: u16 = 20;
+
+const RTAX_LOCK: u16 = 1;
+const RTAX_CC_ALGO: u16 = 16;
+const ROUTE_METRICS: [&str; 18] = [
+    "unspec", "lock", "mtu", "window", "rtt", "rttvar", "ssthresh", "cwnd",
+    "advmss", "reordering", "hoplimit", "initcwnd", "features", "rto_min",
+    "initrwnd", "quickack", "congctl", "fastopen_no_cookie",
+];
+
+const FRA_DST: u16 = 1;
+const FRA_SRC: u16 = 2;
+const FRA_IIFNAME: u16 = 3;
+const FRA_GOTO: u16 = 4;
+const FRA_PRIORITY: u16 = 6;
+const FRA_FWMARK: u16 = 10;
+const FRA_TABLE: u16 = 15;
+const FRA_FWMASK: u16 = 16;
+const FRA_OIFNAME: u16 = 17;
+const FRA_L3MDEV: u16 = 19;
+const FRA_UID_RANGE: u16 = 20;
+const FRA_PROTOCOL: u16 = 21;
+const FRA_SUPPRESS_PREFIXLEN: u16 = 14;
+
+const FIB_RULE_INVERT: u32 = 0x2;
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpFamily {
+    V4,
+    V6,
+}
+
+/// How far an address or route is valid (`rt_scope_t`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Universe,
+    Site,
+    Link,
+    Host,
+    Nowhere,
+    Other(u8),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteType {
+    Unspec,
+    Unicast,
+    Local,
+    Broadcast,
+    Anycast,
+    Multicast,
+    Blackhole,
+    Unreachable,
+    Prohibit,
+    Throw,
+    Nat,
+    Other(u8),
+}
+
+/// What a policy rule does with matching packets.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    Table,
+    Goto,
+    Nop,
+    Blackhole,
+    Unreachable,
+    Prohibit,
+    Other(u8),
+}
+
+/// An address assigned to an interface (`ip address`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Address {
+    pub family: IpFamily,
+    pub ifindex: u32,
+    pub interface: Option<String>,
+    pub address: IpAddr,
+    /// The other end of a point-to-point link, when it differs from the
+    /// local address.
+    pub peer: Option<IpAddr>,
+    pub prefix_len: u8,
+    pub scope: Scope,
+    pub broadcast: Option<IpAddr>,
+    pub label: Option<String>,
+    /// `ip address` flag names, e.g. `tentative` or `deprecated`.
+    pub flags: Vec<String>,
+    /// Lifetimes in seconds; `u32::MAX` means forever.
+    pub valid_lifetime: Option<u32>,
+    pub preferred_lifetime: Option<u32>,
+}
+
+/// One path of a multipath route.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NextHop {
+    pub gateway: Option<IpAddr>,
+    pub ifindex: u32,
+    pub interface: Option<String>,
+    pub weight: u16,
+}
+
+/// A route from any routing table (`ip route show table all`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Route {
+    pub family: IpFamily,
+    pub table: u32,
+    pub route_type: RouteType,
+    /// `None` for a default route.
+    pub destination: Option<IpAddr>,
+    pub dst_len: u8,
+    pub source: Option<IpAddr>,
+    pub src_len: u8,
+    pub gateway: Option<IpAddr>,
+    pub ifindex: Option<u32>,
+    pub interface: Option<String>,
+    pub input_ifindex: Option<u32>,
+    pub prefsrc: Option<IpAddr>,
+    /// The route's metric.
+    pub priority: Option<u32>,
+    /// Who installed the route: `kernel`, `boot`, `static`, `dhcp`, ...
+    pub protocol: String,
+    pub scope: Scope,
+    pub tos: u8,
+    /// IPv6 router preference: 0 medium, 1 high, 3 low.
+    pub pref: Option<u8>,
+    /// Per-route TCP metrics such as `mtu`, `initcwnd` and `rto_min`.
+    pub metrics: BTreeMap<String, u32>,
+    pub congestion_control: Option<String>,
+    pub nexthops: Vec<NextHop>,
+}
+
+/// A policy routing rule (`ip rule`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub family: IpFamily,
+    pub priority: u32,
+    pub action: RuleAction,
+    pub table: u32,
+    pub invert: bool,
+    pub source: Option<IpAddr>,
+    pub src_len: u8,
+    pub destination: Option<IpAddr>,
+    pub dst_len: u8,
+    pub tos: u8,
+    pub iif: Option<String>,
+    pub oif: Option<String>,
+    pub fwmark: Option<u32>,
+    pub fwmask: Option<u32>,
+    pub goto: Option<u32>,
+    /// Route lookups through this VRF's table.
+    pub l3mdev: bool,
+    pub uid_range: Option<(u32, u32)>,
+    pub suppress_prefixlen: Option<i32>,
+    pub protocol: Option<String>,
+}
+
+impl IpFamily {
+    fn from_af(family: u8) -> Option<Self> {
+        match family {
+            AF_INET => Some(Self::V4),
+            AF_INET6 => Some(Self::V6),
+            _ => None,
+        }
+    }
+}
+
+impl Scope {
+    pub fn from_raw(scope: u8) -> Self {
+        match scope {
+            0 => Self::Universe,
+            200 => Self::Site,
+            253 => Self::Link,
+            254 => Self::Host,
+            255 => Self::Nowhere,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl RouteType {
+    pub fn from_raw(route_type: u8) -> Self {
+        match route_type {
+            0 => Self::Unspec,
+            1 => Self::Unicast,
+            2 => Self::Local,
+            3 => Self::Broadcast,
+            4 => Self::Anycast,
+            5 => Self::Multicast,
+            6 => Self::Blackhole,
+            7 => Self::Unreachable,
+            8 => Self::Prohibit,
+            9 => Self::Throw,
+            10 => Self::Nat,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl RuleAction {
+    pub fn from_raw(action: u8) -> Self {
+        match action {
+            1 => Self::Table,
+            2 => Self::Goto,
+            3 => Self::Nop,
+            6 => Self::Blackhole,
+            7 => Self::Unreachable,
+            8 => Self::Prohibit,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl Address {
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Whether duplicate address detection has not finished yet.
+    pub fn is_tentative(&self) -> bool {
+        self.has_flag("tentative")
+    }
+
+    /// Whether the preferred lifetime ran out, so the address is no longer
+    /// used for new connections.
+    pub fn is_deprecated(&self) -> bool {
+        self.has_flag("deprecated")
+    }
+}
+
+impl Route {
+    pub fn is_default(&self) -> bool {
+        self.destination.is_none() && self.dst_len == 0
+    }
+}
+
+/// Name of a routing table as in `/etc/iproute2/rt_tables`.
+pub fn table_name(table: u32) -> String {
+    match table {
+        0 => "unspec".to_string(),
+        253 => "default".to_string(),
+        254 => "main".to_string(),
+        255 => "local".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Name of a route's originating protocol as in
+/// `/etc/iproute2/rt_protos`.
+pub fn protocol_name(protocol: u8) -> String {
+    match protocol {
+        0 => "unspec",
+        1 => "redirect",
+        2 => "kernel",
+        3 => "boot",
+        4 => "static",
+        8 => "gated",
+        9 => "ra",
+        10 => "mrt",
+        11 => "zebra",
+        12 => "bird",
+        13 => "dnrouted",
+        14 => "xorp",
+        15 => "ntk",
+        16 => "dhcp",
+        17 => "mrouted",
+        18 => "keepalived",
+        42 => "babel",
+        99 => "openr",
+        186 => "bgp",
+        187 => "isis",
+        188 => "ospf",
+        189 => "rip",
+        192 => "eigrp",
+        other => return other.to_string(),
+    }
+    .to_string()
+}
+
+/// Returns every IPv4 and IPv6 address on every interface.
+pub fn get_addresses() -> io::Result<Vec<Address>> {
+    let mut socket = NetlinkSocket::open(SockProtocol::NetlinkRoute)?;
+    let names = interface_names(&mut socket)?;
+
+    // struct ifaddrmsg, with every field left as a wildcard.
+    let replies = socket.dump(RTM_GETADDR, &[0u8; 8])?;
+
+    Ok(replies.iter()
+        .filter(|reply| reply.msg_type == RTM_NEWADDR)
+        .filter_map(|reply| parse_address(&reply.payload, &names))
+        .collect())
+}
+
+/// Returns the routes of every routing table, both address families.
+pub fn get_routes() -> io::Result<Vec<Route>> {
+    let mut socket = NetlinkSocket::open(SockProtocol::NetlinkRoute)?;
+    let names = interface_names(&mut socket)?;
+
+    // struct rtmsg, with every field left as a wildcard.
+    let replies = socket.dump(RTM_GETROUTE, &[0u8; 12])?;
+
+    Ok(replies.iter()
+        .filter(|reply| reply.msg_type == RTM_NEWROUTE)
+        .filter_map(|reply| parse_route(&reply.payload, &names))
+        .collect())
+}
+
+/// Returns the policy routing rules of both address families, in the
+/// order the kernel evaluates them.
+pub fn get_rules() -> io::Result<Vec<Rule>> {
+    let mut socket = NetlinkSocket::open(SockProtocol::NetlinkRoute)?;
+
+    // struct fib_rule_hdr, with every field left as a wildcard.
+    let replies = socket.dump(RTM_GETRULE, &[0u8; 12])?;
+
+    Ok(replies.iter()
+        .filter(|reply| reply.msg_type == RTM_NEWRULE)
+        .filter_map(|reply| parse_rule(&reply.payload))
+        .collect())
+}
+
+/// Map interface indexes to names with a link dump.
+fn interface_names(socket: &mut NetlinkSocket)
+    -> io::Result<HashMap<u32, String>> {
+    // struct ifinfomsg, with every field left as a wildcard.
+    let replies = socket.dump(RTM_GETLINK, &[0u8; 16])?;
+
+    Ok(replies.iter()
+        .filter(|reply| {
+            reply.msg_type == RTM_NEWLINK && reply.payload.len() >= 16
+        })
+        .filter_map(|reply| {
+            let ifindex = netlink::u32_at(&reply.payload, 4);
+            let name = netlink::attributes(&reply.payload[16..])
+                .find(|(kind, _)| *kind == IFLA_IFNAME)
+                .map(|(_, value)| netlink::string(value))?;
+            Some((ifindex, name))
+        })
+        .collect())
+}
+
+/// Parse the payload of an `RTM_NEWADDR` message, naming the interface
+/// from `names`.
+pub fn parse_address(payload: &[u8], names: &HashMap<u32, String>)
+    -> Option<Address> {
+    let header = payload.get(..8)?;
+    let family = IpFamily::from_af(header[0])?;
+    let ifindex = netlink::u32_at(header, 4);
+
+    let mut address = None;
+    let mut local = None;
+    let mut broadcast = None;
+    let mut label = None;
+    let mut flags = header[2] as u32;
+    let mut lifetimes = None;
+
+    for (kind, value) in netlink::attributes(&payload[8..]) {
+        match kind {
+            IFA_ADDRESS => address = netlink::ip_addr(value),
+            IFA_LOCAL => local = netlink::ip_addr(value),
+            IFA_BROADCAST => broadcast = netlink::ip_addr(value),
+            IFA_LABEL => label = Some(netlink::string(value)),
+            IFA_FLAGS => flags = netlink::u32_at(value, 0),
+            IFA_CACHEINFO if value.len() >= 8 => {
+                lifetimes = Some((
+                    netlink::u32_at(value, 4), netlink::u32_at(value, 0)
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    // IFA_LOCAL is the interface's own address; IFA_ADDRESS is the same
+    // except on point-to-point links, where it is the peer.
+    let (address, peer) = match (local, address) {
+        (Some(local), Some(address)) if local != address => {
+            (local, Some(address))
+        }
+        (Some(local), _) => (local, None),
+        (None, Some(address)) => (address, None),
+        (None, None) => return None,
+    };
+
+    Some(Address {
+        family,
+        ifindex,
+        interface: names.get(&ifindex).cloned(),
+        address,
+        peer,
+        prefix_len: header[1],
+        scope: Scope::from_raw(header[3]),
+        broadcast,
+        label,
+        flags: ADDRESS_FLAGS.iter()
+            .filter(|(bit, _)| flags & bit != 0)
+            .map(|(_, name)| name.to_string())
+            .collect(),
+        valid_lifetime: lifetimes.map(|(valid, _)| valid),
+        preferred_lifetime: lifetimes.map(|(_, preferred)| preferred),
+    })
+}
+
+/// Parse the payload of an `RTM_NEWROUTE` message, naming interfaces
+/// from `names`.
+pub fn parse_route(payload: &[u8], names: &HashMap<u32, String>)
+    -> Option<Route> {
+    let header = payload.get(..12)?;
+    let family = IpFamily::from_af(header[0])?;
+
+    let mut route = Route {
+        family,
+        table: header[4] as u32,
+        route_type: RouteType::from_raw(header[7]),
+        destination: None,
+        dst_len: header[1],
+        source: None,
+        src_len: header[2],
+        gateway: None,
+        ifindex: None,
+        interface: None,
+        input_ifindex: None,
+        prefsrc: None,
+        priority: None,
+        protocol: protocol_name(header[5]),
+        scope: Scope::from_raw(header[6]),
+        tos: header[3],
+        pref: None,
+        metrics: BTreeMap::new(),
+        congestion_control: None,
+        nexthops: Vec::new(),
+    };
+
+    for (kind, value) in netlink::attributes(&payload[12..]) {
+        match kind {
+            RTA_DST => route.destination = netlink::ip_addr(value),
+            RTA_SRC => route.source = netlink::ip_addr(value),
+            RTA_IIF => route.input_ifindex = Some(netlink::u32_at(value, 0)),
+            RTA_OIF => route.ifindex = Some(netlink::u32_at(value, 0)),
+            RTA_GATEWAY => route.gateway = netlink::ip_addr(value),
+            // struct rtvia: a 2-byte family, then the address.
+            RTA_VIA => route.gateway = netlink::ip_addr(value.get(2..)?),
+            RTA_PRIORITY => route.priority = Some(netlink::u32_at(value, 0)),
+            RTA_PREFSRC => route.prefsrc = netlink::ip_addr(value),
+            RTA_TABLE => route.table = netlink::u32_at(value, 0),
+            RTA_PREF => route.pref = value.first().copied(),
+            RTA_METRICS => {
+                for (metric, value) in netlink::attributes(value) {
+                    if metric == RTAX_CC_ALGO {
+                        route.congestion_control =
+                            Some(netlink::string(value));
+                    } else if metric != RTAX_LOCK {
+                        let name = ROUTE_METRICS.get(metric as usize)
+                            .map(|name| name.to_string())
+                            .unwrap_or_else(|| format!("metric{}", metric));
+                        route.metrics.insert(name, netlink::u32_at(value, 0));
+                    }
+                }
+            }
+            RTA_MULTIPATH => route.nexthops = parse_multipath(value, names),
+            _ => {}
+        }
+    }
+
+    route.interface = route.ifindex
+        .and_then(|ifindex| names.get(&ifindex).cloned());
+
+    Some(route)
+}
+
+/// Parse the `struct rtnexthop` entries of `RTA_MULTIPATH`.
+fn parse_multipath(mut data: &[u8], names: &HashMap<u32, String>)
+    -> Vec<NextHop> {
+    const RTNH_LEN: usize = 8;
+    let mut hops = Vec::new();
+
+    while data.len() >= RTNH_LEN {
+        let len = netlink::u16_at(data, 0) as usize;
+        if len < RTNH_LEN || len > data.len() {
+            break;
+        }
+
+        let ifindex = netlink::u32_at(data, 4);
+        let gateway = netlink::attributes(&data[RTNH_LEN..len])
+            .find_map(|(kind, value)| match kind {
+                RTA_GATEWAY => netlink::ip_addr(value),
+                RTA_VIA => netlink::ip_addr(value.get(2..)?),
+                _ => None,
+            });
+
+        hops.push(NextHop {
+            gateway,
+            ifindex,
+            interface: names.get(&ifindex).cloned(),
+            weight: data[3] as u16 + 1,
+        });
+        data = &data[netlink::align(len).min(data.len())..];
+    }
+
+    hops
+}
+
+/// Parse the payload of an `RTM_NEWRULE` message.
+pub fn parse_rule(payload: &[u8]) -> Option<Rule> {
+    let header = payload.get(..12)?;
+    let family = IpFamily::from_af(header[0])?;
+
+    let mut rule = Rule {
+        family,
+        priority: 0,
+        action: RuleAction::from_raw(header[7]),
+        table: header[4] as u32,
+        invert: netlink::u32_at(header, 8) & FIB_RULE_INVERT != 0,
+        source: None,
+        src_len: header[2],
+        destination: None,
+        dst_len: header[1],
+        tos: header[3],
+        iif: None,
+        oif: None,
+        fwmark: None,
+        fwmask: None,
+        goto: None,
+        l3mdev: false,
+        uid_range: None,
+        suppress_prefixlen: None,
+        protocol: None,
+    };
+
+    for (kind, value) in netlink::attributes(&payload[12..]) {
+        match kind {
+            FRA_DST => rule.destination = netlink::ip_addr(value),
+            FRA_SRC => rule.source = netlink::ip_addr(value),
+            FRA_IIFNAME => rule.iif = Some(netlink::string(value)),
+            FRA_OIFNAME => rule.oif = Some(netlink::string(value)),
+            FRA_GOTO => rule.goto = Some(netlink::u32_at(value, 0)),
+            FRA_PRIORITY => rule.priority = netlink::u32_at(value, 0),
+            FRA_FWMARK => rule.fwmark = Some(netlink::u32_at(value, 0)),
+            FRA_FWMASK => rule.fwmask = Some(netlink::u32_at(value, 0)),
+            FRA_TABLE => rule.table = netlink::u32_at(value, 0),
+            FRA_L3MDEV => rule.l3mdev = value.first() == Some(&1),
+            FRA_UID_RANGE => {
+                rule.uid_range = Some((
+                    netlink::u32_at(value, 0), netlink::u32_at(value, 4)
+                ));
+            }
+            FRA_SUPPRESS_PREFIXLEN => {
+                let prefixlen = netlink::u32_at(value, 0) as i32;
+                rule.suppress_prefixlen = Some(prefixlen)
+                    .filter(|prefixlen| *prefixlen >= 0);
+            }
+            FRA_PROTOCOL => {
+                rule.protocol = value.first()
+                    .map(|protocol| protocol_name(*protocol));
+            }
+            _ => {}
+        }
+    }
+
+    Some(rule)
+}
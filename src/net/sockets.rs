@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::Path;
+use nix::sys::socket::SockProtocol;
+use serde::{Serialize, Deserialize};
+
+use super::netlink::{self, NetlinkSocket};
+
+const PROC_NET: &str = "/proc/net";
+
+/// `SOCK_DIAG_BY_FAMILY` from `<linux/sock_diag.h>`.
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+
+/// `INET_DIAG_INFO` from `<linux/inet_diag.h>`: the attribute carrying
+/// `struct tcp_info`, requested with bit `INET_DIAG_INFO - 1`.
+const INET_DIAG_INFO: u16 = 2;
+
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+const IPPROTO_TCP: u8 = 6;
+const IPPROTO_UDP: u8 = 17;
+
+/// `__SO_ACCEPTCON` in the flags of `/proc/net/unix`: a listening socket.
+const SO_ACCEPTCON: u32 = 0x10000;
+
+/// The `/proc/net` table a socket was read from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketProtocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Raw,
+    Raw6,
+}
+
+/// Socket state, using the TCP state names. UDP and raw sockets are
+/// `Established` when connected and `Close` otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+    Unknown(u8),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixSocketType {
+    Stream,
+    Dgram,
+    SeqPacket,
+    Other(u16),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixSocketState {
+    Unconnected,
+    Connecting,
+    Connected,
+    Disconnecting,
+    Other(u8),
+}
+
+/// Kernel TCP statistics of one connection (`struct tcp_info`). Times are
+/// in microseconds. Fields newer than Linux 4.x are `None` on kernels that
+/// do not report them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TcpInfo {
+    pub ca_state: u8,
+    /// Retransmits of the current unacknowledged segment.
+    pub retransmits: u8,
+    pub probes: u8,
+    pub backoff: u8,
+    pub rto: u32,
+    pub ato: u32,
+    pub snd_mss: u32,
+    pub rcv_mss: u32,
+    pub unacked: u32,
+    pub sacked: u32,
+    pub lost: u32,
+    pub retrans: u32,
+    pub pmtu: u32,
+    pub rcv_ssthresh: u32,
+    pub rtt: u32,
+    pub rttvar: u32,
+    pub snd_ssthresh: u32,
+    pub snd_cwnd: u32,
+    pub advmss: u32,
+    pub reordering: u32,
+    pub rcv_rtt: u32,
+    pub rcv_space: u32,
+    pub total_retrans: u32,
+    /// Bytes per second.
+    pub pacing_rate: Option<u64>,
+    pub bytes_acked: Option<u64>,
+    pub bytes_received: Option<u64>,
+    pub segs_out: Option<u32>,
+    pub segs_in: Option<u32>,
+    pub notsent_bytes: Option<u32>,
+    pub min_rtt: Option<u32>,
+    /// Bytes per second.
+    pub delivery_rate: Option<u64>,
+    pub bytes_sent: Option<u64>,
+    pub bytes_retrans: Option<u64>,
+}
+
+/// An IPv4 or IPv6 socket.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InetSocket {
+    pub protocol: SocketProtocol,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: TcpState,
+    /// Bytes sent but not yet acknowledged.
+    pub tx_queue: u32,
+    /// Bytes waiting to be read, or connections waiting to be accepted on a
+    /// listener.
+    pub rx_queue: u32,
+    pub uid: u32,
+    pub inode: u64,
+    pub retransmits: u32,
+    /// Datagrams dropped for this socket (UDP and raw only).
+    pub drops: Option<u64>,
+    /// Only filled in by the sock_diag readers.
+    pub tcp_info: Option<TcpInfo>,
+}
+
+/// A Unix domain socket from `/proc/net/unix`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnixSocket {
+    pub path: Option<String>,
+    /// Bound in the abstract namespace; `path` then starts with `@`.
+    pub is_abstract: bool,
+    pub socket_type: UnixSocketType,
+    pub state: UnixSocketState,
+    pub listening: bool,
+    pub ref_count: u32,
+    pub inode: u64,
+}
+
+/// A process holding a socket open.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SocketOwner {
+    pub pid: u32,
+    pub fd: u32,
+    pub comm: Option<String>,
+}
+
+/// Which processes hold each socket, by socket inode.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SocketOwners {
+    pub by_inode: HashMap<u64, Vec<SocketOwner>>,
+}
+
+impl SocketProtocol {
+    fn proc_file(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Tcp6 => "tcp6",
+            Self::Udp => "udp",
+            Self::Udp6 => "udp6",
+            Self::Raw => "raw",
+            Self::Raw6 => "raw6",
+        }
+    }
+
+    /// Address family and IP protocol for sock_diag, which covers TCP and
+    /// UDP only.
+    fn diag_ids(self) -> Option<(u8, u8)> {
+        match self {
+            Self::Tcp => Some((AF_INET, IPPROTO_TCP)),
+            Self::Tcp6 => Some((AF_INET6, IPPROTO_TCP)),
+            Self::Udp => Some((AF_INET, IPPROTO_UDP)),
+            Self::Udp6 => Some((AF_INET6, IPPROTO_UDP)),
+            Self::Raw | Self::Raw6 => None,
+        }
+    }
+}
+
+impl TcpState {
+    pub fn from_raw(state: u8) -> Self {
+        match state {
+            1 => Self::Established,
+            2 => Self::SynSent,
+            3 => Self::SynRecv,
+            4 => Self::FinWait1,
+            5 => Self::FinWait2,
+            6 => Self::TimeWait,
+            7 => Self::Close,
+            8 => Self::CloseWait,
+            9 => Self::LastAck,
+            10 => Self::Listen,
+            11 => Self::Closing,
+            12 => Self::NewSynRecv,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl SocketOwners {
+    /// Scan `/proc/*/fd` for socket links. Processes that can't be read
+    /// (other users' without privileges, or ones that exited meanwhile)
+    /// are skipped.
+    pub fn scan() -> Self {
+        let mut by_inode: HashMap<u64, Vec<SocketOwner>> = HashMap::new();
+
+        let Ok(processes) = fs::read_dir("/proc") else {
+            return SocketOwners { by_inode };
+        };
+
+        for process in processes.flatten() {
+            let Some(pid) = process.file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u32>().ok()) else {
+                continue;
+            };
+            let Ok(fds) = fs::read_dir(process.path().join("fd")) else {
+                continue;
+            };
+            let comm = fs::read_to_string(process.path().join("comm"))
+                .ok()
+                .map(|comm| comm.trim_end().to_string());
+
+            for fd in fds.flatten() {
+                let Ok(target) = fs::read_link(fd.path()) else {
+                    continue;
+                };
+                let Some(inode) = target.to_str()
+                    .and_then(|target| target.strip_prefix("socket:["))
+                    .and_then(|inode| inode.strip_suffix(']'))
+                    .and_then(|inode| inode.parse().ok()) else {
+                    continue;
+                };
+                let Some(fd) = fd.file_name()
+                    .to_str()
+                    .and_then(|fd| fd.parse().ok()) else {
+                    continue;
+                };
+
+                by_inode.entry(inode)
+                    .or_default()
+                    .push(SocketOwner { pid, fd, comm: comm.clone() });
+            }
+        }
+
+        SocketOwners { by_inode }
+    }
+
+    pub fn get(&self, inode: u64) -> &[SocketOwner] {
+        self.by_inode.get(&inode).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Returns the sockets in one `/proc/net` table.
+pub fn get_inet_sockets(protocol: SocketProtocol) -> Vec<InetSocket> {
+    read_inet_sockets(Path::new(PROC_NET), protocol)
+}
+
+/// Returns the sockets of every TCP, UDP and raw table.
+pub fn get_all_inet_sockets() -> Vec<InetSocket> {
+    read_all_inet_sockets(Path::new(PROC_NET))
+}
+
+/// Returns every Unix domain socket.
+pub fn get_unix_sockets() -> Vec<UnixSocket> {
+    read_unix_sockets(Path::new(PROC_NET))
+}
+
+/// Reads one table in a `/proc/net` directory.
+pub fn read_inet_sockets(proc_net: &Path, protocol: SocketProtocol)
+    -> Vec<InetSocket> {
+    fs::read_to_string(proc_net.join(protocol.proc_file()))
+        .map(|content| parse_inet_sockets(&content, protocol))
+        .unwrap_or_default()
+}
+
+/// Reads every TCP, UDP and raw table in a `/proc/net` directory.
+pub fn read_all_inet_sockets(proc_net: &Path) -> Vec<InetSocket> {
+    [
+        SocketProtocol::Tcp,
+        SocketProtocol::Tcp6,
+        SocketProtocol::Udp,
+        SocketProtocol::Udp6,
+        SocketProtocol::Raw,
+        SocketProtocol::Raw6,
+    ]
+    .into_iter()
+    .flat_map(|protocol| read_inet_sockets(proc_net, protocol))
+    .collect()
+}
+
+/// Reads `unix` in a `/proc/net` directory.
+pub fn read_unix_sockets(proc_net: &Path) -> Vec<UnixSocket> {
+    fs::read_to_string(proc_net.join("unix"))
+        .map(|content| parse_unix_sockets(&content))
+        .unwrap_or_default()
+}
+
+/// Parse an address as the kernel prints it in `/proc/net`: the address in
+/// 32-bit words of hex in host byte order, then the port.
+fn parse_proc_addr(field: &str) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut octets = Vec::with_capacity(16);
+    for start in (0..addr.len()).step_by(8) {
+        let word = u32::from_str_radix(addr.get(start..start + 8)?, 16).ok()?;
+        octets.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match octets.len() {
+        4 => IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(octets).ok()?)),
+        16 => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(octets).ok()?)),
+        _ => return None,
+    };
+
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Parse the text of `/proc/net/{tcp,tcp6,udp,udp6,raw,raw6}`.
+pub fn parse_inet_sockets(content: &str, protocol: SocketProtocol)
+    -> Vec<InetSocket> {
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+
+            let (tx_queue, rx_queue) = fields[4].split_once(':')?;
+            let has_drops = !matches!(
+                protocol, SocketProtocol::Tcp | SocketProtocol::Tcp6
+            );
+
+            Some(InetSocket {
+                protocol,
+                local: parse_proc_addr(fields[1])?,
+                remote: parse_proc_addr(fields[2])?,
+                state: TcpState::from_raw(
+                    u8::from_str_radix(fields[3], 16).ok()?
+                ),
+                tx_queue: u32::from_str_radix(tx_queue, 16).ok()?,
+                rx_queue: u32::from_str_radix(rx_queue, 16).ok()?,
+                retransmits: u32::from_str_radix(fields[6], 16).unwrap_or(0),
+                uid: fields[7].parse().ok()?,
+                inode: fields[9].parse().ok()?,
+                drops: fields.get(12)
+                    .filter(|_| has_drops)
+                    .and_then(|drops| drops.parse().ok()),
+                tcp_info: None,
+            })
+        })
+        .collect()
+}
+
+/// Parse the text of `/proc/net/unix`.
+pub fn parse_unix_sockets(content: &str) -> Vec<UnixSocket> {
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 7 {
+                return None;
+            }
+
+            let flags = u32::from_str_radix(fields[3], 16).ok()?;
+            // Everything after the inode is the path, which may hold spaces.
+            let path = (fields.len() > 7).then(|| fields[7..].join(" "));
+
+            Some(UnixSocket {
+                is_abstract: path.as_deref()
+                    .is_some_and(|path| path.starts_with('@')),
+                path,
+                socket_type: match u16::from_str_radix(fields[4], 16).ok()? {
+                    1 => UnixSocketType::Stream,
+                    2 => UnixSocketType::Dgram,
+                    5 => UnixSocketType::SeqPacket,
+                    other => UnixSocketType::Other(other),
+                },
+                state: match u8::from_str_radix(fields[5], 16).ok()? {
+                    1 => UnixSocketState::Unconnected,
+                    2 => UnixSocketState::Connecting,
+                    3 => UnixSocketState::Connected,
+                    4 => UnixSocketState::Disconnecting,
+                    other => UnixSocketState::Other(other),
+                },
+                listening: flags & SO_ACCEPTCON != 0,
+                ref_count: u32::from_str_radix(fields[1], 16).ok()?,
+                inode: fields[6].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Dump TCP or UDP sockets through `NETLINK_SOCK_DIAG`, which is much
+/// faster than `/proc/net` on hosts with many sockets and fills in
+/// [`InetSocket::tcp_info`] for TCP. Raw sockets are not supported.
+pub fn diag_inet_sockets(protocol: SocketProtocol)
+    -> io::Result<Vec<InetSocket>> {
+    let (family, ip_protocol) = protocol.diag_ids().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("sock_diag does not cover {:?} sockets", protocol),
+        )
+    })?;
+
+    // struct inet_diag_req_v2: family, protocol, extensions, padding, the
+    // state bitmask, then a wildcard struct inet_diag_sockid.
+    let mut request = vec![family, ip_protocol, 1 << (INET_DIAG_INFO - 1), 0];
+    request.extend_from_slice(&u32::MAX.to_ne_bytes());
+    request.resize(56, 0);
+
+    let mut socket = NetlinkSocket::open(SockProtocol::NetlinkSockDiag)?;
+    let replies = socket.dump(SOCK_DIAG_BY_FAMILY, &request)?;
+
+    Ok(replies.iter()
+        .filter(|reply| reply.msg_type == SOCK_DIAG_BY_FAMILY)
+        .filter_map(|reply| parse_diag_msg(&reply.payload, protocol))
+        .collect())
+}
+
+/// Parse a `struct inet_diag_msg` and its attributes.
+fn parse_diag_msg(payload: &[u8], protocol: SocketProtocol)
+    -> Option<InetSocket> {
+    const MSG_LEN: usize = 72;
+    let msg = payload.get(..MSG_LEN)?;
+
+    let address = |offset: usize, port_offset: usize| -> Option<SocketAddr> {
+        let port = u16::from_be_bytes([msg[port_offset], msg[port_offset + 1]]);
+        let ip = match msg[0] {
+            AF_INET => IpAddr::V4(Ipv4Addr::from(
+                <[u8; 4]>::try_from(&msg[offset..offset + 4]).ok()?
+            )),
+            AF_INET6 => IpAddr::V6(Ipv6Addr::from(
+                <[u8; 16]>::try_from(&msg[offset..offset + 16]).ok()?
+            )),
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, port))
+    };
+
+    let tcp_info = netlink::attributes(&payload[MSG_LEN..])
+        .find(|(kind, _)| *kind == INET_DIAG_INFO)
+        .and_then(|(_, value)| parse_tcp_info(value));
+
+    Some(InetSocket {
+        protocol,
+        local: address(8, 4)?,
+        remote: address(24, 6)?,
+        state: TcpState::from_raw(msg[1]),
+        rx_queue: netlink::u32_at(msg, 56),
+        tx_queue: netlink::u32_at(msg, 60),
+        uid: netlink::u32_at(msg, 64),
+        inode: netlink::u32_at(msg, 68) as u64,
+        retransmits: msg[3] as u32,
+        drops: None,
+        tcp_info,
+    })
+}
+
+/// Parse `struct tcp_info`, which has grown over kernel versions; fields
+/// past the end of what this kernel sent stay `None`.
+fn parse_tcp_info(data: &[u8]) -> Option<TcpInfo> {
+    if data.len() < 104 {
+        return None;
+    }
+
+    let u32_at = |offset: usize| netlink::u32_at(data, offset);
+    let opt_u32 = |offset: usize| {
+        (data.len() >= offset + 4).then(|| u32_at(offset))
+    };
+    let opt_u64 = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|bytes| u64::from_ne_bytes(bytes.try_into().unwrap()))
+    };
+
+    Some(TcpInfo {
+        ca_state: data[1],
+        retransmits: data[2],
+        probes: data[3],
+        backoff: data[4],
+        rto: u32_at(8),
+        ato: u32_at(12),
+        snd_mss: u32_at(16),
+        rcv_mss: u32_at(20),
+        unacked: u32_at(24),
+        sacked: u32_at(28),
+        lost: u32_at(32),
+        retrans: u32_at(36),
+        pmtu: u32_at(60),
+        rcv_ssthresh: u32_at(64),
+        rtt: u32_at(68),
+        rttvar: u32_at(72),
+        snd_ssthresh: u32_at(76),
+        snd_cwnd: u32_at(80),
+        advmss: u32_at(84),
+        reordering: u32_at(88),
+        rcv_rtt: u32_at(92),
+        rcv_space: u32_at(96),
+        total_retrans: u32_at(100),
+        pacing_rate: opt_u64(104),
+        bytes_acked: opt_u64(120),
+        bytes_received: opt_u64(128),
+        segs_out: opt_u32(136),
+        segs_in: opt_u32(140),
+        notsent_bytes: opt_u32(144),
+        min_rtt: opt_u32(148),
+        delivery_rate: opt_u64(160),
+        bytes_sent: opt_u64(200),
+        bytes_retrans: opt_u64(208),
+    })
+}
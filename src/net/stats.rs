@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+use crate::units;
+
+/// Generates `InterfaceStats` with one counter per file in
+/// `/sys/class/net/<name>/statistics`.
+macro_rules! interface_counters {
+    ($($field:ident),* $(,)?) => {
+        /// Traffic and error counters of one interface, named as in
+        /// `/sys/class/net/<name>/statistics`.
+        #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+        pub struct InterfaceStats {
+            $(pub $field: u64,)*
+        }
+
+        impl InterfaceStats {
+            fn read_dir(dir: &Path) -> Self {
+                InterfaceStats {
+                    $(
+                        $field: sysfs::read_u64(
+                            &dir.join(stringify!($field))
+                        ).unwrap_or(0),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+interface_counters! {
+    rx_bytes,
+    rx_packets,
+    rx_errors,
+    rx_dropped,
+    rx_missed_errors,
+    rx_fifo_errors,
+    rx_frame_errors,
+    rx_length_errors,
+    rx_over_errors,
+    rx_crc_errors,
+    rx_compressed,
+    rx_nohandler,
+    multicast,
+    tx_bytes,
+    tx_packets,
+    tx_errors,
+    tx_dropped,
+    tx_fifo_errors,
+    tx_carrier_errors,
+    tx_aborted_errors,
+    tx_window_errors,
+    tx_heartbeat_errors,
+    tx_compressed,
+    collisions,
+}
+
+/// Per-second rates of one interface between two samples. Throughput is
+/// in bits per second, ready for [`units::human_readable_bitrate`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct InterfaceRates {
+    pub interval_secs: f64,
+    pub rx_bits_per_sec: u64,
+    pub tx_bits_per_sec: u64,
+    pub rx_packets_per_sec: f64,
+    pub tx_packets_per_sec: f64,
+    pub rx_errors_per_sec: f64,
+    pub tx_errors_per_sec: f64,
+    pub rx_dropped_per_sec: f64,
+    pub tx_dropped_per_sec: f64,
+}
+
+impl InterfaceStats {
+    /// Reads `/sys/class/net/<name>/statistics`.
+    pub fn from_sysfs(name: &str) -> io::Result<Self> {
+        Self::from_sysfs_in(Path::new("/sys/class/net"), name)
+    }
+
+    /// Reads `<name>/statistics` under `sys_class_net`.
+    pub fn from_sysfs_in(sys_class_net: &Path, name: &str)
+        -> io::Result<Self> {
+        let dir = sys_class_net.join(name).join("statistics");
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound, format!("No interface {}", name)
+            ));
+        }
+
+        Ok(Self::read_dir(&dir))
+    }
+
+    /// Rates since `earlier`, taken `interval` before this sample. A
+    /// counter that went backwards, as when the interface was recreated,
+    /// counts as 0.
+    pub fn rates_since(&self, earlier: &InterfaceStats, interval: Duration)
+        -> InterfaceRates {
+        let secs = interval.as_secs_f64();
+        if secs <= 0.0 {
+            return InterfaceRates::default();
+        }
+
+        let rate = |now: u64, before: u64| {
+            now.saturating_sub(before) as f64 / secs
+        };
+        let bits = |now: u64, before: u64| (rate(now, before) * 8.0) as u64;
+
+        InterfaceRates {
+            interval_secs: secs,
+            rx_bits_per_sec: bits(self.rx_bytes, earlier.rx_bytes),
+            tx_bits_per_sec: bits(self.tx_bytes, earlier.tx_bytes),
+            rx_packets_per_sec: rate(self.rx_packets, earlier.rx_packets),
+            tx_packets_per_sec: rate(self.tx_packets, earlier.tx_packets),
+            rx_errors_per_sec: rate(self.rx_errors, earlier.rx_errors),
+            tx_errors_per_sec: rate(self.tx_errors, earlier.tx_errors),
+            rx_dropped_per_sec: rate(self.rx_dropped, earlier.rx_dropped),
+            tx_dropped_per_sec: rate(self.tx_dropped, earlier.tx_dropped),
+        }
+    }
+}
+
+impl InterfaceRates {
+    pub fn rx_bitrate(&self) -> String {
+        units::human_readable_bitrate(self.rx_bits_per_sec)
+    }
+
+    pub fn tx_bitrate(&self) -> String {
+        units::human_readable_bitrate(self.tx_bits_per_sec)
+    }
+}
+
+/// Returns the counters of every interface from `/proc/net/dev`.
+pub fn get_proc_net_dev() -> BTreeMap<String, InterfaceStats> {
+    fs::read_to_string("/proc/net/dev")
+        .map(|content| parse_proc_net_dev(&content))
+        .unwrap_or_default()
+}
+
+/// Parse the text of `/proc/net/dev`.
+///
+/// The kernel folds several counters together in this file: `drop`
+/// includes missed packets, `frame` all receive length, overrun, CRC and
+/// frame errors, and `carrier` all transmit carrier, aborted, window and
+/// heartbeat errors. They are stored in `rx_dropped`, `rx_frame_errors`
+/// and `tx_carrier_errors` respectively, leaving the finer counters at 0.
+pub fn parse_proc_net_dev(content: &str) -> BTreeMap<String, InterfaceStats> {
+    content.lines()
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let values: Vec<u64> = counters.split_whitespace()
+                .map(|value| value.parse().ok())
+                .collect::<Option<_>>()?;
+            if values.len() < 16 {
+                return None;
+            }
+
+            let stats = InterfaceStats {
+                rx_bytes: values[0],
+                rx_packets: values[1],
+                rx_errors: values[2],
+                rx_dropped: values[3],
+                rx_fifo_errors: values[4],
+                rx_frame_errors: values[5],
+                rx_compressed: values[6],
+                multicast: values[7],
+                tx_bytes: values[8],
+                tx_packets: values[9],
+                tx_errors: values[10],
+                tx_dropped: values[11],
+                tx_fifo_errors: values[12],
+                collisions: values[13],
+                tx_carrier_errors: values[14],
+                tx_compressed: values[15],
+                ..Default::default()
+            };
+
+            Some((name.trim().to_string(), stats))
+        })
+        .collect()
+}
+
+/// Rates of every interface present in both samples.
+pub fn rates_between(
+    earlier: &BTreeMap<String, InterfaceStats>,
+    later: &BTreeMap<String, InterfaceStats>,
+    interval: Duration,
+) -> BTreeMap<String, InterfaceRates> {
+    later.iter()
+        .filter_map(|(name, now)| {
+            let before = earlier.get(name)?;
+            Some((name.clone(), now.rates_since(before, interval)))
+        })
+        .collect()
+}
+
+/// Measures the throughput and error rates of every interface over
+/// `interval`. Interfaces that appear or vanish meanwhile are left out.
+pub fn sample_rates(interval: Duration) -> BTreeMap<String, InterfaceRates> {
+    let earlier = get_proc_net_dev();
+    let start = Instant::now();
+    thread::sleep(interval);
+    let later = get_proc_net_dev();
+
+    rates_between(&earlier, &later, start.elapsed())
+}
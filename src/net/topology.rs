@@ -0,0 +1,530 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::sysfs;
+use super::{Duplex, Interface};
+
+/// Bonding mode, from the `Bonding Mode:` line of `/proc/net/bonding/*`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum BondMode {
+    BalanceRr,
+    ActiveBackup,
+    BalanceXor,
+    Broadcast,
+    /// IEEE 802.3ad dynamic link aggregation (LACP).
+    Lacp,
+    BalanceTlb,
+    BalanceAlb,
+    Other(String),
+}
+
+/// A slave of a bond.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BondSlave {
+    pub name: String,
+    /// MII link status is up.
+    pub mii_up: bool,
+    /// In Mbit/s.
+    pub speed: Option<u32>,
+    pub duplex: Option<Duplex>,
+    /// Times the slave's link went down since it was enslaved.
+    pub link_failure_count: u64,
+    pub permanent_mac: Option<String>,
+    pub queue_id: Option<u32>,
+    /// The 802.3ad aggregator the slave belongs to.
+    pub aggregator_id: Option<u32>,
+}
+
+/// A bonding device from `/proc/net/bonding`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bond {
+    pub name: String,
+    pub mode: BondMode,
+    pub transmit_hash_policy: Option<String>,
+    pub primary_slave: Option<String>,
+    /// Only reported in active-backup, TLB and ALB modes.
+    pub active_slave: Option<String>,
+    pub mii_up: bool,
+    pub mii_polling_interval_ms: Option<u32>,
+    pub up_delay_ms: Option<u32>,
+    pub down_delay_ms: Option<u32>,
+    pub lacp_rate: Option<String>,
+    pub slaves: Vec<BondSlave>,
+}
+
+/// Which spanning tree implementation a bridge runs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StpMode {
+    Disabled,
+    /// The kernel's own STP.
+    Kernel,
+    /// A user space daemon such as mstpd.
+    User,
+    Other(u8),
+}
+
+/// STP state of a bridge port.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortState {
+    Disabled,
+    Listening,
+    Learning,
+    Forwarding,
+    Blocking,
+    Other(u8),
+}
+
+/// A port of a bridge, from `/sys/class/net/<bridge>/brif/<port>`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BridgePort {
+    pub name: String,
+    pub port_no: Option<u32>,
+    pub state: PortState,
+    pub path_cost: Option<u32>,
+    pub priority: Option<u32>,
+    pub hairpin_mode: bool,
+    pub learning: Option<bool>,
+}
+
+/// A bridge from `/sys/class/net/<name>/bridge`. Times are in seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bridge {
+    pub name: String,
+    pub stp: StpMode,
+    /// Priority and address, e.g. `8000.d207e926fde4`.
+    pub bridge_id: Option<String>,
+    pub root_id: Option<String>,
+    pub root_port: Option<u32>,
+    pub root_path_cost: Option<u32>,
+    pub forward_delay: Option<f64>,
+    pub hello_time: Option<f64>,
+    pub max_age: Option<f64>,
+    pub ageing_time: Option<f64>,
+    /// `None` on kernels built without bridge VLAN filtering.
+    pub vlan_filtering: Option<bool>,
+    pub ports: Vec<BridgePort>,
+}
+
+/// An 802.1Q VLAN from `/proc/net/vlan/config`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Vlan {
+    pub name: String,
+    pub id: u16,
+    pub parent: String,
+}
+
+/// How the upper interface of a [`TopologyEdge`] uses the lower one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    BondSlave,
+    BridgePort,
+    VlanParent,
+}
+
+/// `upper` is built on `lower`, e.g. a bond on one of its slaves.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TopologyEdge {
+    pub upper: String,
+    pub lower: String,
+    pub relation: Relation,
+}
+
+/// The bonds, bridges and VLANs stacked on the host's interfaces.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Topology {
+    pub bonds: Vec<Bond>,
+    pub bridges: Vec<Bridge>,
+    pub vlans: Vec<Vlan>,
+}
+
+/// An interface and its place in the [`Topology`]: the bond, bridge or
+/// VLAN it is, if any, and its edges to the interfaces around it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InterfaceNode {
+    pub interface: Interface,
+    pub bond: Option<Bond>,
+    pub bridge: Option<Bridge>,
+    pub vlan: Option<Vlan>,
+    /// Edges to the interfaces this one is built on.
+    pub lower: Vec<TopologyEdge>,
+    /// Edges to the interfaces built on this one.
+    pub upper: Vec<TopologyEdge>,
+}
+
+impl BondMode {
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        match value {
+            "load balancing (round-robin)" => Self::BalanceRr,
+            "fault-tolerance (active-backup)" => Self::ActiveBackup,
+            "load balancing (xor)" => Self::BalanceXor,
+            "fault-tolerance (broadcast)" => Self::Broadcast,
+            "IEEE 802.3ad Dynamic link aggregation" => Self::Lacp,
+            "transmit load balancing" => Self::BalanceTlb,
+            "adaptive load balancing" => Self::BalanceAlb,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl Bond {
+    /// Parse the text of `/proc/net/bonding/<name>`.
+    pub fn parse(name: &str, content: &str) -> Self {
+        let mut bond = Bond {
+            name: name.to_string(),
+            mode: BondMode::Other(String::new()),
+            transmit_hash_policy: None,
+            primary_slave: None,
+            active_slave: None,
+            mii_up: false,
+            mii_polling_interval_ms: None,
+            up_delay_ms: None,
+            down_delay_ms: None,
+            lacp_rate: None,
+            slaves: Vec::new(),
+        };
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            // Drops annotations like the `(1)` in `layer3+4 (1)`.
+            let named = || {
+                value.split_whitespace()
+                    .next()
+                    .filter(|word| *word != "None")
+                    .map(String::from)
+            };
+
+            if key == "Slave Interface" {
+                bond.slaves.push(BondSlave {
+                    name: value.to_string(),
+                    mii_up: false,
+                    speed: None,
+                    duplex: None,
+                    link_failure_count: 0,
+                    permanent_mac: None,
+                    queue_id: None,
+                    aggregator_id: None,
+                });
+                continue;
+            }
+
+            // Everything after the first slave line describes that slave.
+            if let Some(slave) = bond.slaves.last_mut() {
+                match key {
+                    "MII Status" => slave.mii_up = value == "up",
+                    "Speed" => {
+                        slave.speed = value.strip_suffix(" Mbps")
+                            .and_then(|speed| speed.parse().ok());
+                    }
+                    "Duplex" => slave.duplex = Duplex::parse(value),
+                    "Link Failure Count" => {
+                        slave.link_failure_count = value.parse().unwrap_or(0);
+                    }
+                    "Permanent HW addr" => slave.permanent_mac = named(),
+                    "Slave queue ID" => slave.queue_id = value.parse().ok(),
+                    "Aggregator ID" => {
+                        slave.aggregator_id = value.parse().ok();
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            match key {
+                "Bonding Mode" => bond.mode = BondMode::parse(value),
+                "Transmit Hash Policy" => {
+                    bond.transmit_hash_policy = named();
+                }
+                "Primary Slave" => bond.primary_slave = named(),
+                "Currently Active Slave" => bond.active_slave = named(),
+                "MII Status" => bond.mii_up = value == "up",
+                "MII Polling Interval (ms)" => {
+                    bond.mii_polling_interval_ms = value.parse().ok();
+                }
+                "Up Delay (ms)" => bond.up_delay_ms = value.parse().ok(),
+                "Down Delay (ms)" => bond.down_delay_ms = value.parse().ok(),
+                "LACP rate" => bond.lacp_rate = named(),
+                _ => {}
+            }
+        }
+
+        bond
+    }
+
+    /// Slaves whose MII status is down.
+    pub fn failed_slaves(&self) -> Vec<&BondSlave> {
+        self.slaves.iter().filter(|slave| !slave.mii_up).collect()
+    }
+}
+
+impl StpMode {
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            0 => Self::Disabled,
+            1 => Self::Kernel,
+            2 => Self::User,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl PortState {
+    pub fn from_raw(value: u8) -> Self {
+        match value {
+            0 => Self::Disabled,
+            1 => Self::Listening,
+            2 => Self::Learning,
+            3 => Self::Forwarding,
+            4 => Self::Blocking,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl Bridge {
+    /// Reads the bridge `name` from a `/sys/class/net` directory, returning
+    /// `None` if it is not a bridge.
+    pub fn read_from(sys_class_net: &Path, name: &str) -> Option<Self> {
+        let dir = sys_class_net.join(name);
+        let bridge_dir = dir.join("bridge");
+        if !bridge_dir.is_dir() {
+            return None;
+        }
+
+        let number = |file: &str| sysfs::read_u64(&bridge_dir.join(file));
+        let string = |file: &str| sysfs::read_string(&bridge_dir.join(file));
+        // Timers are kept in hundredths of a second.
+        let secs = |file: &str| number(file).map(|value| value as f64 / 100.0);
+
+        let mut ports: Vec<BridgePort> = match fs::read_dir(dir.join("brif")) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| {
+                    let port = entry.file_name().to_string_lossy().to_string();
+                    read_port(&entry.path(), port)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        ports.sort_by_key(|port| port.port_no);
+
+        Some(Bridge {
+            name: name.to_string(),
+            stp: StpMode::from_raw(number("stp_state").unwrap_or(0) as u8),
+            bridge_id: string("bridge_id"),
+            root_id: string("root_id"),
+            root_port: number("root_port").map(|port| port as u32),
+            root_path_cost: number("root_path_cost").map(|cost| cost as u32),
+            forward_delay: secs("forward_delay"),
+            hello_time: secs("hello_time"),
+            max_age: secs("max_age"),
+            ageing_time: secs("ageing_time"),
+            vlan_filtering: number("vlan_filtering").map(|value| value == 1),
+            ports,
+        })
+    }
+
+    /// Whether this bridge is the root of its spanning tree.
+    pub fn is_root(&self) -> bool {
+        self.bridge_id.is_some() && self.bridge_id == self.root_id
+    }
+
+    pub fn port(&self, name: &str) -> Option<&BridgePort> {
+        self.ports.iter().find(|port| port.name == name)
+    }
+}
+
+impl Topology {
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/proc/net"), Path::new("/sys/class/net"))
+    }
+
+    /// Reads bonds and VLANs from a `/proc/net` directory and bridges from
+    /// a `/sys/class/net` directory.
+    pub fn read_from(proc_net: &Path, sys_class_net: &Path) -> Self {
+        let bonds = sorted_entries(&proc_net.join("bonding"))
+            .into_iter()
+            .filter_map(|name| {
+                let path = proc_net.join("bonding").join(&name);
+                let content = fs::read_to_string(path).ok()?;
+                Some(Bond::parse(&name, &content))
+            })
+            .collect();
+
+        let bridges = sorted_entries(sys_class_net)
+            .into_iter()
+            .filter_map(|name| Bridge::read_from(sys_class_net, &name))
+            .collect();
+
+        let vlans = fs::read_to_string(proc_net.join("vlan/config"))
+            .map(|content| parse_vlan_config(&content))
+            .unwrap_or_default();
+
+        Topology { bonds, bridges, vlans }
+    }
+
+    pub fn bond(&self, name: &str) -> Option<&Bond> {
+        self.bonds.iter().find(|bond| bond.name == name)
+    }
+
+    pub fn bridge(&self, name: &str) -> Option<&Bridge> {
+        self.bridges.iter().find(|bridge| bridge.name == name)
+    }
+
+    pub fn vlan(&self, name: &str) -> Option<&Vlan> {
+        self.vlans.iter().find(|vlan| vlan.name == name)
+    }
+
+    /// Place each of `interfaces` in the graph, keeping their order.
+    pub fn attach(&self, interfaces: Vec<Interface>) -> Vec<InterfaceNode> {
+        let edges = self.edges();
+
+        interfaces.into_iter()
+            .map(|interface| {
+                let name = interface.name.as_str();
+                let matching = |end: fn(&TopologyEdge) -> &str| edges.iter()
+                    .filter(|edge| end(edge) == name)
+                    .cloned()
+                    .collect();
+
+                InterfaceNode {
+                    bond: self.bond(name).cloned(),
+                    bridge: self.bridge(name).cloned(),
+                    vlan: self.vlan(name).cloned(),
+                    lower: matching(|edge| &edge.upper),
+                    upper: matching(|edge| &edge.lower),
+                    interface,
+                }
+            })
+            .collect()
+    }
+
+    /// Every edge of the graph, bonds first, then bridges, then VLANs.
+    pub fn edges(&self) -> Vec<TopologyEdge> {
+        let edge = |upper: &str, lower: &str, relation| TopologyEdge {
+            upper: upper.to_string(),
+            lower: lower.to_string(),
+            relation,
+        };
+
+        let bonds = self.bonds.iter().flat_map(|bond| {
+            bond.slaves.iter().map(|slave| {
+                edge(&bond.name, &slave.name, Relation::BondSlave)
+            })
+        });
+        let bridges = self.bridges.iter().flat_map(|bridge| {
+            bridge.ports.iter().map(|port| {
+                edge(&bridge.name, &port.name, Relation::BridgePort)
+            })
+        });
+        let vlans = self.vlans.iter()
+            .map(|vlan| edge(&vlan.name, &vlan.parent, Relation::VlanParent));
+
+        bonds.chain(bridges).chain(vlans).collect()
+    }
+
+    /// The interfaces `name` is built on.
+    pub fn lower(&self, name: &str) -> Vec<TopologyEdge> {
+        self.edges().into_iter().filter(|edge| edge.upper == name).collect()
+    }
+
+    /// The interfaces built on `name`.
+    pub fn upper(&self, name: &str) -> Vec<TopologyEdge> {
+        self.edges().into_iter().filter(|edge| edge.lower == name).collect()
+    }
+
+    /// The interfaces at the bottom of the stack under `name`, such as the
+    /// NICs behind a bridge on a VLAN of a bond. An interface with nothing
+    /// under it is its own bottom.
+    pub fn bottom(&self, name: &str) -> BTreeSet<String> {
+        let edges = self.edges();
+        let mut bottom = BTreeSet::new();
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![name.to_string()];
+
+        while let Some(current) = pending.pop() {
+            if !seen.insert(current.clone()) {
+                continue;
+            }
+
+            let lower: Vec<&TopologyEdge> = edges.iter()
+                .filter(|edge| edge.upper == current)
+                .collect();
+            if lower.is_empty() {
+                bottom.insert(current);
+            } else {
+                pending.extend(lower.iter().map(|edge| edge.lower.clone()));
+            }
+        }
+
+        bottom
+    }
+}
+
+/// Every interface in `/sys/class/net`, ordered by index, placed in the
+/// host's topology.
+pub fn get_interface_graph() -> Vec<InterfaceNode> {
+    Topology::new().attach(super::get_interfaces())
+}
+
+/// Like [`get_interface_graph`], reading from a `/proc/net` and a
+/// `/sys/class/net` directory.
+pub fn read_interface_graph(
+    proc_net: &Path, sys_class_net: &Path
+) -> Vec<InterfaceNode> {
+    Topology::read_from(proc_net, sys_class_net)
+        .attach(super::read_interfaces(sys_class_net))
+}
+
+/// Parse the text of `/proc/net/vlan/config`.
+pub fn parse_vlan_config(content: &str) -> Vec<Vlan> {
+    content.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('|').map(str::trim);
+            let name = fields.next()?;
+            let id = fields.next()?.parse().ok()?;
+            let parent = fields.next()?;
+
+            Some(Vlan {
+                name: name.to_string(),
+                id,
+                parent: parent.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn read_port(dir: &Path, name: String) -> BridgePort {
+    let number = |file: &str| sysfs::read_u64(&dir.join(file));
+
+    BridgePort {
+        name,
+        port_no: sysfs::read_string(&dir.join("port_no"))
+            .and_then(|hex| {
+                u32::from_str_radix(hex.trim_start_matches("0x"), 16).ok()
+            }),
+        state: PortState::from_raw(number("state").unwrap_or(0) as u8),
+        path_cost: number("path_cost").map(|cost| cost as u32),
+        priority: number("priority").map(|priority| priority as u32),
+        hairpin_mode: number("hairpin_mode") == Some(1),
+        learning: number("learning").map(|learning| learning == 1),
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    names.sort();
+    names
+}
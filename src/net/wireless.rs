@@ -0,0 +1,459 @@
+use std::fs;
+use std::io;
+use nix::sys::socket::SockProtocol;
+use serde::{Serialize, Deserialize};
+
+use crate::units;
+use super::netlink::{self, NetlinkSocket, GENL_HDRLEN, NLM_F_DUMP};
+use super::{Interface, InterfaceKind};
+
+// Commands and attributes from <linux/nl80211.h>.
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_NEW_INTERFACE: u8 = 7;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_CMD_NEW_STATION: u8 = 19;
+
+const NL80211_ATTR_WIPHY: u16 = 1;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_IFNAME: u16 = 4;
+const NL80211_ATTR_IFTYPE: u16 = 5;
+const NL80211_ATTR_MAC: u16 = 6;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_ATTR_WIPHY_TX_POWER_LEVEL: u16 = 98;
+const NL80211_ATTR_CHANNEL_WIDTH: u16 = 159;
+const NL80211_ATTR_CENTER_FREQ1: u16 = 160;
+
+const NL80211_STA_INFO_INACTIVE_TIME: u16 = 1;
+const NL80211_STA_INFO_RX_BYTES: u16 = 2;
+const NL80211_STA_INFO_TX_BYTES: u16 = 3;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+const NL80211_STA_INFO_TX_BITRATE: u16 = 8;
+const NL80211_STA_INFO_RX_PACKETS: u16 = 9;
+const NL80211_STA_INFO_TX_PACKETS: u16 = 10;
+const NL80211_STA_INFO_TX_RETRIES: u16 = 11;
+const NL80211_STA_INFO_TX_FAILED: u16 = 12;
+const NL80211_STA_INFO_SIGNAL_AVG: u16 = 13;
+const NL80211_STA_INFO_RX_BITRATE: u16 = 14;
+const NL80211_STA_INFO_CONNECTED_TIME: u16 = 16;
+const NL80211_STA_INFO_BEACON_LOSS: u16 = 18;
+const NL80211_STA_INFO_RX_BYTES64: u16 = 23;
+const NL80211_STA_INFO_TX_BYTES64: u16 = 24;
+
+const NL80211_RATE_INFO_BITRATE: u16 = 1;
+const NL80211_RATE_INFO_BITRATE32: u16 = 5;
+
+/// nl80211 reports bitrates in units of 100 kbit/s.
+const BITRATE_UNIT: u64 = 100_000;
+
+/// Statistics of one interface from `/proc/net/wireless`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WirelessStats {
+    pub interface: String,
+    pub status: u16,
+    /// Link quality, on the driver's own scale (often out of 70).
+    pub link_quality: i32,
+    /// Signal level, in dBm on current drivers.
+    pub signal_level: i32,
+    /// `None` when the driver doesn't measure noise.
+    pub noise_level: Option<i32>,
+    /// Packets discarded for a foreign network id or SSID.
+    pub discarded_nwid: u64,
+    /// Packets that could not be decrypted.
+    pub discarded_crypt: u64,
+    pub discarded_frag: u64,
+    /// Packets dropped after too many retries.
+    pub discarded_retry: u64,
+    pub discarded_misc: u64,
+    pub missed_beacons: u64,
+}
+
+/// nl80211 interface type.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WirelessMode {
+    Adhoc,
+    Station,
+    AccessPoint,
+    ApVlan,
+    Wds,
+    Monitor,
+    MeshPoint,
+    P2pClient,
+    P2pGo,
+    P2pDevice,
+    Ocb,
+    Nan,
+    Other(u32),
+}
+
+/// A peer of a wireless interface: the access point of a station, or a
+/// client of an access point.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct WirelessStation {
+    pub mac: String,
+    pub signal_dbm: Option<i8>,
+    pub signal_avg_dbm: Option<i8>,
+    pub tx_bits_per_sec: Option<u64>,
+    pub rx_bits_per_sec: Option<u64>,
+    pub rx_bytes: Option<u64>,
+    pub tx_bytes: Option<u64>,
+    pub rx_packets: Option<u32>,
+    pub tx_packets: Option<u32>,
+    pub tx_retries: Option<u32>,
+    pub tx_failed: Option<u32>,
+    pub beacon_loss: Option<u32>,
+    pub inactive_ms: Option<u32>,
+    pub connected_secs: Option<u32>,
+}
+
+/// The state of a wireless interface, from nl80211.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WirelessLink {
+    pub interface: String,
+    pub ifindex: u32,
+    /// Index of the radio, as in `phy<n>`.
+    pub wiphy: Option<u32>,
+    pub mode: Option<WirelessMode>,
+    pub mac: Option<String>,
+    /// Network name, while associated or operating a network.
+    pub ssid: Option<String>,
+    /// The access point in station mode, the interface itself in access
+    /// point mode.
+    pub bssid: Option<String>,
+    /// In MHz.
+    pub frequency: Option<u32>,
+    pub channel: Option<u32>,
+    /// In MHz.
+    pub channel_width: Option<u32>,
+    /// Center of the whole channel for widths above 20 MHz.
+    pub center_frequency: Option<u32>,
+    pub tx_power_dbm: Option<f64>,
+    pub stations: Vec<WirelessStation>,
+}
+
+impl WirelessMode {
+    pub fn from_raw(value: u32) -> Self {
+        match value {
+            1 => Self::Adhoc,
+            2 => Self::Station,
+            3 => Self::AccessPoint,
+            4 => Self::ApVlan,
+            5 => Self::Wds,
+            6 => Self::Monitor,
+            7 => Self::MeshPoint,
+            8 => Self::P2pClient,
+            9 => Self::P2pGo,
+            10 => Self::P2pDevice,
+            11 => Self::Ocb,
+            12 => Self::Nan,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl WirelessStation {
+    pub fn tx_bitrate(&self) -> Option<String> {
+        self.tx_bits_per_sec.map(units::human_readable_bitrate)
+    }
+
+    pub fn rx_bitrate(&self) -> Option<String> {
+        self.rx_bits_per_sec.map(units::human_readable_bitrate)
+    }
+}
+
+impl WirelessLink {
+    /// The access point's entry in station mode, where it is the only
+    /// station.
+    pub fn access_point(&self) -> Option<&WirelessStation> {
+        match self.mode {
+            Some(WirelessMode::Station | WirelessMode::P2pClient) => {
+                self.stations.first()
+            }
+            _ => None,
+        }
+    }
+
+    /// Signal strength of the access point in station mode.
+    pub fn signal_dbm(&self) -> Option<i8> {
+        self.access_point().and_then(|station| station.signal_dbm)
+    }
+
+    /// Transmit bitrate towards the access point in station mode.
+    pub fn tx_bitrate(&self) -> Option<String> {
+        self.access_point().and_then(WirelessStation::tx_bitrate)
+    }
+}
+
+/// Returns the statistics in `/proc/net/wireless`, which only lists
+/// interfaces whose driver supports wireless extensions.
+pub fn get_wireless_stats() -> Vec<WirelessStats> {
+    fs::read_to_string("/proc/net/wireless")
+        .map(|content| parse_proc_net_wireless(&content))
+        .unwrap_or_default()
+}
+
+/// Parse the text of `/proc/net/wireless`.
+pub fn parse_proc_net_wireless(content: &str) -> Vec<WirelessStats> {
+    content.lines()
+        .filter_map(|line| {
+            let (name, values) = line.split_once(':')?;
+            let values: Vec<&str> = values.split_whitespace()
+                // A trailing dot marks a value updated since the last read.
+                .map(|value| value.trim_end_matches('.'))
+                .collect();
+            if values.len() < 10 {
+                return None;
+            }
+
+            let count = |index: usize| values[index].parse().unwrap_or(0);
+            let noise: i32 = values[3].parse().ok()?;
+
+            Some(WirelessStats {
+                interface: name.trim().to_string(),
+                status: u16::from_str_radix(values[0], 16).ok()?,
+                link_quality: values[1].parse().ok()?,
+                signal_level: values[2].parse().ok()?,
+                noise_level: (noise != -256).then_some(noise),
+                discarded_nwid: count(4),
+                discarded_crypt: count(5),
+                discarded_frag: count(6),
+                discarded_retry: count(7),
+                discarded_misc: count(8),
+                missed_beacons: count(9),
+            })
+        })
+        .collect()
+}
+
+/// The channel number of a frequency in MHz, following the kernel's
+/// `ieee80211_freq_khz_to_channel`.
+pub fn frequency_to_channel(frequency: u32) -> Option<u32> {
+    match frequency {
+        2484 => Some(14),
+        2412..=2472 => Some((frequency - 2407) / 5),
+        4910..=4980 => Some((frequency - 4000) / 5),
+        5955..=7115 => Some((frequency - 5950) / 5),
+        5935 => Some(2),
+        5000..=5924 => Some((frequency - 5000) / 5),
+        58320..=70200 => Some((frequency - 56160) / 2160),
+        _ => None,
+    }
+}
+
+/// Queries nl80211 for every interface that [`super::get_interfaces`]
+/// finds to be wireless. Without any, nl80211 is not asked at all.
+pub fn get_wireless_links() -> io::Result<Vec<WirelessLink>> {
+    let interfaces: Vec<Interface> = super::get_interfaces()
+        .into_iter()
+        .filter(|interface| interface.kind == InterfaceKind::Wireless)
+        .collect();
+    if interfaces.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut nl80211 = Nl80211::open()?;
+    interfaces.iter()
+        .map(|interface| nl80211.link(interface.ifindex))
+        .collect()
+}
+
+/// Queries nl80211 for one interface.
+pub fn get_wireless_link(name: &str) -> io::Result<WirelessLink> {
+    let interface = Interface::new(name)?;
+    Nl80211::open()?.link(interface.ifindex)
+}
+
+struct Nl80211 {
+    socket: NetlinkSocket,
+    family: u16,
+}
+
+impl Nl80211 {
+    fn open() -> io::Result<Self> {
+        let mut socket = NetlinkSocket::open(SockProtocol::NetlinkGeneric)?;
+        let family = socket.genl_family("nl80211")?;
+        Ok(Nl80211 { socket, family })
+    }
+
+    fn link(&mut self, ifindex: u32) -> io::Result<WirelessLink> {
+        let index = ifindex.to_ne_bytes();
+        let attrs = [(NL80211_ATTR_IFINDEX, &index[..])];
+
+        let request = netlink::genl_message(NL80211_CMD_GET_INTERFACE, &attrs);
+        let reply = self.socket.request(self.family, 0, &request)?
+            .into_iter()
+            .find(|reply| {
+                reply.payload.first() == Some(&NL80211_CMD_NEW_INTERFACE)
+            })
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("nl80211 does not know interface {}", ifindex),
+            ))?;
+        let mut link = parse_interface(&reply.payload[GENL_HDRLEN..], ifindex);
+
+        let request = netlink::genl_message(NL80211_CMD_GET_STATION, &attrs);
+        link.stations = self.socket.request(self.family, NLM_F_DUMP, &request)?
+            .iter()
+            .filter(|reply| {
+                reply.payload.first() == Some(&NL80211_CMD_NEW_STATION)
+            })
+            .filter_map(|reply| parse_station(&reply.payload[GENL_HDRLEN..]))
+            .collect();
+
+        link.bssid = match link.mode {
+            Some(WirelessMode::AccessPoint | WirelessMode::P2pGo) => {
+                link.mac.clone()
+            }
+            _ => link.access_point().map(|station| station.mac.clone()),
+        };
+
+        Ok(link)
+    }
+}
+
+fn parse_interface(data: &[u8], ifindex: u32) -> WirelessLink {
+    let mut link = WirelessLink {
+        interface: String::new(),
+        ifindex,
+        wiphy: None,
+        mode: None,
+        mac: None,
+        ssid: None,
+        bssid: None,
+        frequency: None,
+        channel: None,
+        channel_width: None,
+        center_frequency: None,
+        tx_power_dbm: None,
+        stations: Vec::new(),
+    };
+
+    for (kind, value) in netlink::attributes(data) {
+        let number = netlink::u32_at(value, 0);
+        match kind {
+            NL80211_ATTR_IFNAME => link.interface = netlink::string(value),
+            NL80211_ATTR_WIPHY => link.wiphy = Some(number),
+            NL80211_ATTR_IFTYPE => {
+                link.mode = Some(WirelessMode::from_raw(number));
+            }
+            NL80211_ATTR_MAC => link.mac = Some(format_mac(value)),
+            NL80211_ATTR_SSID => {
+                link.ssid = Some(String::from_utf8_lossy(value).to_string());
+            }
+            NL80211_ATTR_WIPHY_FREQ => {
+                link.frequency = Some(number);
+                link.channel = frequency_to_channel(number);
+            }
+            NL80211_ATTR_CHANNEL_WIDTH => {
+                link.channel_width = channel_width_mhz(number);
+            }
+            NL80211_ATTR_CENTER_FREQ1 => link.center_frequency = Some(number),
+            // In mBm, hundredths of a dBm.
+            NL80211_ATTR_WIPHY_TX_POWER_LEVEL => {
+                link.tx_power_dbm = Some(number as i32 as f64 / 100.0);
+            }
+            _ => {}
+        }
+    }
+
+    link
+}
+
+fn parse_station(data: &[u8]) -> Option<WirelessStation> {
+    let mut station = WirelessStation::default();
+
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NL80211_ATTR_MAC => station.mac = format_mac(value),
+            NL80211_ATTR_STA_INFO => parse_station_info(value, &mut station),
+            _ => {}
+        }
+    }
+
+    (!station.mac.is_empty()).then_some(station)
+}
+
+fn parse_station_info(data: &[u8], station: &mut WirelessStation) {
+    for (kind, value) in netlink::attributes(data) {
+        let number = Some(netlink::u32_at(value, 0));
+        match kind {
+            NL80211_STA_INFO_INACTIVE_TIME => station.inactive_ms = number,
+            NL80211_STA_INFO_RX_BYTES if station.rx_bytes.is_none() => {
+                station.rx_bytes = number.map(u64::from);
+            }
+            NL80211_STA_INFO_TX_BYTES if station.tx_bytes.is_none() => {
+                station.tx_bytes = number.map(u64::from);
+            }
+            NL80211_STA_INFO_RX_BYTES64 => {
+                station.rx_bytes = Some(netlink::u64_at(value, 0));
+            }
+            NL80211_STA_INFO_TX_BYTES64 => {
+                station.tx_bytes = Some(netlink::u64_at(value, 0));
+            }
+            NL80211_STA_INFO_SIGNAL => {
+                station.signal_dbm = value.first().map(|dbm| *dbm as i8);
+            }
+            NL80211_STA_INFO_SIGNAL_AVG => {
+                station.signal_avg_dbm = value.first().map(|dbm| *dbm as i8);
+            }
+            NL80211_STA_INFO_TX_BITRATE => {
+                station.tx_bits_per_sec = parse_bitrate(value);
+            }
+            NL80211_STA_INFO_RX_BITRATE => {
+                station.rx_bits_per_sec = parse_bitrate(value);
+            }
+            NL80211_STA_INFO_RX_PACKETS => station.rx_packets = number,
+            NL80211_STA_INFO_TX_PACKETS => station.tx_packets = number,
+            NL80211_STA_INFO_TX_RETRIES => station.tx_retries = number,
+            NL80211_STA_INFO_TX_FAILED => station.tx_failed = number,
+            NL80211_STA_INFO_BEACON_LOSS => station.beacon_loss = number,
+            NL80211_STA_INFO_CONNECTED_TIME => station.connected_secs = number,
+            _ => {}
+        }
+    }
+}
+
+/// A nested `nl80211_rate_info`, preferring the 32-bit rate that newer
+/// kernels add for rates above 6.5 Gbit/s.
+fn parse_bitrate(data: &[u8]) -> Option<u64> {
+    let mut rate = None;
+    for (kind, value) in netlink::attributes(data) {
+        match kind {
+            NL80211_RATE_INFO_BITRATE32 => {
+                return Some(netlink::u32_at(value, 0) as u64 * BITRATE_UNIT);
+            }
+            NL80211_RATE_INFO_BITRATE => {
+                rate = Some(netlink::u16_at(value, 0) as u64 * BITRATE_UNIT);
+            }
+            _ => {}
+        }
+    }
+    rate
+}
+
+/// `enum nl80211_chan_width` in MHz.
+fn channel_width_mhz(width: u32) -> Option<u32> {
+    match width {
+        0 | 1 => Some(20),
+        2 => Some(40),
+        3 | 4 => Some(80),
+        5 => Some(160),
+        6 => Some(5),
+        7 => Some(10),
+        8 => Some(1),
+        9 => Some(2),
+        10 => Some(4),
+        11 => Some(8),
+        12 => Some(16),
+        13 => Some(320),
+        _ => None,
+    }
+}
+
+fn format_mac(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
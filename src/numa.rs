@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+use crate::memory::Meminfo;
+use crate::sysfs;
+
+/// Allocation counters of one node from `numastat`, in pages.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NumaStat {
+    /// Allocations intended for and served by this node.
+    pub numa_hit: u64,
+    /// Allocations served by this node although another node was preferred.
+    pub numa_miss: u64,
+    /// Allocations intended for this node but served by another one.
+    pub numa_foreign: u64,
+    pub interleave_hit: u64,
+    pub local_node: u64,
+    pub other_node: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NumaNode {
+    pub id: u32,
+    pub cpus: Vec<u32>,
+    pub has_cpu: bool,
+    pub has_memory: bool,
+    pub has_normal_memory: bool,
+    /// Set for nodes with devices that initiate memory requests but no
+    /// CPUs, such as accelerators.
+    pub has_generic_initiator: bool,
+    pub meminfo: Meminfo,
+    pub numastat: NumaStat,
+    /// Relative access cost from this node to each online node, where
+    /// `10` is local access.
+    pub distances: BTreeMap<u32, u32>,
+}
+
+/// Nodes from `/sys/devices/system/node`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct NumaTopology {
+    pub possible: Vec<u32>,
+    pub online: Vec<u32>,
+    pub nodes: Vec<NumaNode>,
+}
+
+impl NumaStat {
+    pub fn parse(content: &str) -> Self {
+        let mut stat = NumaStat::default();
+
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Ok(value) = value.trim().parse() else {
+                continue;
+            };
+
+            match key {
+                "numa_hit" => stat.numa_hit = value,
+                "numa_miss" => stat.numa_miss = value,
+                "numa_foreign" => stat.numa_foreign = value,
+                "interleave_hit" => stat.interleave_hit = value,
+                "local_node" => stat.local_node = value,
+                "other_node" => stat.other_node = value,
+                _ => {}
+            }
+        }
+
+        stat
+    }
+}
+
+impl NumaNode {
+    /// Whether the node has memory but no CPUs, as with CXL expanders or
+    /// persistent memory onlined as system RAM.
+    pub fn is_memory_only(&self) -> bool {
+        self.has_memory && !self.has_cpu
+    }
+
+    /// Share of allocations on this node that were not served where they
+    /// were wanted, or `None` before any allocation.
+    pub fn miss_ratio(&self) -> Option<f64> {
+        let total = self.numastat.numa_hit + self.numastat.numa_miss;
+        if total == 0 {
+            return None;
+        }
+        Some(self.numastat.numa_miss as f64 / total as f64)
+    }
+}
+
+impl NumaTopology {
+    pub fn new() -> Self {
+        Self::read_from(Path::new("/sys/devices/system/node"))
+    }
+
+    /// Like [`NumaTopology::new`], reading the node directory at `root`.
+    /// Kernels without NUMA support have no such directory and yield an
+    /// empty topology.
+    pub fn read_from(root: &Path) -> Self {
+        let mask = |name: &str| {
+            sysfs::read_string(&root.join(name))
+                .map(|list| sysfs::parse_list(&list))
+                .unwrap_or_default()
+        };
+        let has_cpu = mask("has_cpu");
+        let has_memory = mask("has_memory");
+        let has_normal_memory = mask("has_normal_memory");
+        let has_generic_initiator = mask("has_generic_initiator");
+        let online = mask("online");
+
+        let mut ids: Vec<u32> = match fs::read_dir(root) {
+            Ok(entries) => entries
+                .flatten()
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    name.strip_prefix("node")?.parse().ok()
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        ids.sort();
+
+        let nodes = ids.into_iter()
+            .map(|id| {
+                let dir = root.join(format!("node{}", id));
+                let distances = sysfs::read_string(&dir.join("distance"))
+                    .map(|line| online.iter()
+                        .copied()
+                        .zip(line.split_whitespace()
+                            .filter_map(|d| d.parse().ok()))
+                        .collect())
+                    .unwrap_or_default();
+
+                NumaNode {
+                    id,
+                    cpus: sysfs::read_string(&dir.join("cpulist"))
+                        .map(|list| sysfs::parse_list(&list))
+                        .unwrap_or_default(),
+                    has_cpu: has_cpu.contains(&id),
+                    has_memory: has_memory.contains(&id),
+                    has_normal_memory: has_normal_memory.contains(&id),
+                    has_generic_initiator: has_generic_initiator.contains(&id),
+                    meminfo: fs::read_to_string(dir.join("meminfo"))
+                        .map(|content| parse_node_meminfo(&content))
+                        .unwrap_or_default(),
+                    numastat: fs::read_to_string(dir.join("numastat"))
+                        .map(|content| NumaStat::parse(&content))
+                        .unwrap_or_default(),
+                    distances,
+                }
+            })
+            .collect();
+
+        NumaTopology { possible: mask("possible"), online, nodes }
+    }
+
+    pub fn node(&self, id: u32) -> Option<&NumaNode> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    /// The node a CPU belongs to.
+    pub fn node_of_cpu(&self, cpu: u32) -> Option<u32> {
+        self.nodes.iter()
+            .find(|node| node.cpus.contains(&cpu))
+            .map(|node| node.id)
+    }
+
+    pub fn distance(&self, from: u32, to: u32) -> Option<u32> {
+        self.node(from)?.distances.get(&to).copied()
+    }
+
+    /// Distances between all nodes, indexed in the order of `nodes`.
+    /// Missing entries are `None`.
+    pub fn distance_matrix(&self) -> Vec<Vec<Option<u32>>> {
+        self.nodes.iter()
+            .map(|from| self.nodes.iter()
+                .map(|to| from.distances.get(&to.id).copied())
+                .collect())
+            .collect()
+    }
+
+    /// Other nodes ordered from nearest to farthest from `from`, the order
+    /// the kernel falls back in when `from` runs out of memory.
+    pub fn nearest_nodes(&self, from: u32) -> Vec<u32> {
+        let Some(node) = self.node(from) else {
+            return Vec::new();
+        };
+
+        let mut others: Vec<(u32, u32)> = node.distances.iter()
+            .filter(|(id, _)| **id != from)
+            .map(|(id, distance)| (*distance, *id))
+            .collect();
+        others.sort();
+        others.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Nodes with memory but no CPUs.
+    pub fn memory_only_nodes(&self) -> Vec<&NumaNode> {
+        self.nodes.iter().filter(|node| node.is_memory_only()).collect()
+    }
+}
+
+/// Parse a node's `meminfo`, whose lines carry a `Node <id>` prefix in
+/// front of the `/proc/meminfo` format.
+pub fn parse_node_meminfo(content: &str) -> Meminfo {
+    let stripped: Vec<&str> = content.lines()
+        .map(|line| {
+            line.strip_prefix("Node")
+                .map(|rest| rest.trim_start()
+                    .trim_start_matches(|c: char| c.is_ascii_digit())
+                    .trim_start())
+                .unwrap_or(line)
+        })
+        .collect();
+
+    Meminfo::parse(&stripped.join("\n"))
+}
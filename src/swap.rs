@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use nix::libc;
+use serde::{Serialize, Deserialize};
+
+use crate::disks;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SwapType {
+    Partition,
+    File,
+    Other(String),
+}
+
+/// One active swap area from `/proc/swaps`. Sizes are in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SwapDevice {
+    pub path: String,
+    pub swap_type: SwapType,
+    pub size: u64,
+    pub used: u64,
+    pub priority: i32,
+    /// Kernel name of the block device holding the swap area: the
+    /// partition itself, or the device a swap file lives on. Only filled
+    /// in by [`get_swap_devices`].
+    pub block_device: Option<String>,
+}
+
+/// Zswap settings from `/sys/module/zswap/parameters`, plus the pool
+/// statistics in `/sys/kernel/debug/zswap` when debugfs is readable.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Zswap {
+    pub enabled: bool,
+    pub compressor: Option<String>,
+    pub zpool: Option<String>,
+    pub max_pool_percent: Option<u32>,
+    pub accept_threshold_percent: Option<u32>,
+    pub shrinker_enabled: Option<bool>,
+    pub parameters: BTreeMap<String, String>,
+    pub stats: Option<BTreeMap<String, u64>>,
+}
+
+impl SwapDevice {
+    /// Parse one data line of `/proc/swaps`. This touches nothing but the
+    /// line, so `block_device` is left empty.
+    pub fn new(line: &str) -> Option<Self> {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 {
+            return None;
+        }
+
+        let path = unescape_octal(parts[0]);
+        let swap_type = match parts[1] {
+            "partition" => SwapType::Partition,
+            "file" => SwapType::File,
+            other => SwapType::Other(other.to_string()),
+        };
+        let size = parts[2].parse::<u64>().ok()? * 1024;
+        let used = parts[3].parse::<u64>().ok()? * 1024;
+        let priority = parts[4].parse::<i32>().ok()?;
+
+        Some(SwapDevice {
+            path,
+            swap_type,
+            size,
+            used,
+            priority,
+            block_device: None,
+        })
+    }
+
+    /// Looks up the block device holding this swap area on the running
+    /// system.
+    pub fn find_block_device(&self) -> Option<String> {
+        match self.swap_type {
+            SwapType::Partition => disks::resolve_device(&self.path),
+            _ => containing_block_device(&self.path),
+        }
+    }
+}
+
+impl Zswap {
+    /// Reads the zswap configuration, or `None` if the kernel lacks zswap.
+    pub fn new() -> Option<Self> {
+        let params_dir = Path::new("/sys/module/zswap/parameters");
+        let parameters: BTreeMap<String, String> = fs::read_dir(params_dir)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| {
+                let value = fs::read_to_string(entry.path()).ok()?;
+                Some((
+                    entry.file_name().to_string_lossy().to_string(),
+                    value.trim().to_string(),
+                ))
+            })
+            .collect();
+
+        let flag = |name: &str| {
+            parameters.get(name).map(|value| value == "Y" || value == "1")
+        };
+        let number = |name: &str| {
+            parameters.get(name).and_then(|value| value.parse().ok())
+        };
+
+        Some(Zswap {
+            enabled: flag("enabled").unwrap_or(false),
+            compressor: parameters.get("compressor").cloned(),
+            zpool: parameters.get("zpool").cloned(),
+            max_pool_percent: number("max_pool_percent"),
+            accept_threshold_percent: number("accept_threshold_percent"),
+            shrinker_enabled: flag("shrinker_enabled"),
+            stats: read_debugfs_stats(),
+            parameters,
+        })
+    }
+}
+
+/// Returns every active swap area by parsing `/proc/swaps`, along with
+/// the block device each one is on.
+pub fn get_swap_devices() -> Vec<SwapDevice> {
+    let content = fs::read_to_string("/proc/swaps").unwrap_or_default();
+
+    parse_swaps(&content)
+        .into_iter()
+        .map(|mut device| {
+            device.block_device = device.find_block_device();
+            device
+        })
+        .collect()
+}
+
+/// Parse the text of a `/proc/swaps` file, skipping its header line.
+pub fn parse_swaps(content: &str) -> Vec<SwapDevice> {
+    content.lines()
+        .skip(1)
+        .filter_map(SwapDevice::new)
+        .collect()
+}
+
+fn read_debugfs_stats() -> Option<BTreeMap<String, u64>> {
+    let stats = fs::read_dir("/sys/kernel/debug/zswap")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let value = fs::read_to_string(entry.path()).ok()?;
+            Some((
+                entry.file_name().to_string_lossy().to_string(),
+                value.trim().parse().ok()?,
+            ))
+        })
+        .collect();
+
+    Some(stats)
+}
+
+/// Kernel name of the block device a file is stored on.
+fn containing_block_device(path: &str) -> Option<String> {
+    let dev = fs::metadata(path).ok()?.dev();
+    let sys_path = format!(
+        "/sys/dev/block/{}:{}", libc::major(dev), libc::minor(dev)
+    );
+
+    fs::canonicalize(sys_path)
+        .ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Undo the `\040`-style octal escaping the kernel applies to paths in
+/// `/proc/swaps` and `/proc/mounts`.
+pub fn unescape_octal(field: &str) -> String {
+    let mut bytes = Vec::with_capacity(field.len());
+    let raw = field.as_bytes();
+    let mut i = 0;
+
+    while i < raw.len() {
+        if raw[i] == b'\\' && i + 4 <= raw.len()
+            && let Ok(octal) = std::str::from_utf8(&raw[i + 1..i + 4])
+            && let Ok(byte) = u8::from_str_radix(octal, 8) {
+            bytes.push(byte);
+            i += 4;
+        } else {
+            bytes.push(raw[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).to_string()
+}
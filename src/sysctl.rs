@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use serde::{Serialize, Deserialize};
+
+const PROC_SYS: &str = "/proc/sys";
+
+/// Configuration directories in the order sysctl.d(5) gives them
+/// precedence: a file in an earlier directory masks a file of the same
+/// name in a later one.
+const CONFIG_DIRS: [&str; 5] = [
+    "etc/sysctl.d",
+    "run/sysctl.d",
+    "usr/local/lib/sysctl.d",
+    "usr/lib/sysctl.d",
+    "lib/sysctl.d",
+];
+
+/// One `key = value` assignment from a sysctl configuration file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SysctlSetting {
+    pub name: String,
+    pub value: String,
+    pub source: PathBuf,
+    pub line: usize,
+    /// Set by a leading `-`, which tells sysctl to ignore failures to apply
+    /// this key (e.g. because the module providing it is not loaded).
+    pub ignore_failure: bool,
+}
+
+/// A configured parameter whose live value differs from the configuration.
+/// `live` is `None` when the parameter does not exist on this kernel.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SysctlDrift {
+    pub name: String,
+    pub configured: String,
+    pub live: Option<String>,
+    pub source: PathBuf,
+}
+
+/// Turn a parameter name into its path under `/proc/sys`.
+///
+/// As with sysctl(8), `/` inside a dotted name stands for a literal `.`,
+/// so `net.ipv4.conf.eth0/100.forwarding` names the `eth0.100` VLAN. Names
+/// already written with `/` separators are accepted as is.
+///
+/// Names with an empty, `.` or `..` component, or a leading `/`, are
+/// refused with `InvalidInput`, as is a path that resolves to somewhere
+/// outside `/proc/sys`. The parameter must exist.
+pub fn path_of(name: &str) -> io::Result<PathBuf> {
+    let relative = relative_path(name)?;
+    let path = fs::canonicalize(Path::new(PROC_SYS).join(relative))?;
+
+    if !path.starts_with(PROC_SYS) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} resolves outside {}", name, PROC_SYS),
+        ));
+    }
+
+    Ok(path)
+}
+
+/// Split a dotted or slash-separated name into a path relative to
+/// `/proc/sys`, refusing any component that could leave it.
+fn relative_path(name: &str) -> io::Result<PathBuf> {
+    let name = name.trim();
+
+    let slash_form = match (name.find('/'), name.find('.')) {
+        (Some(slash), Some(dot)) => slash < dot,
+        (None, Some(_)) => false,
+        (_, None) => true,
+    };
+
+    let parts: Vec<String> = if slash_form {
+        name.split('/').map(str::to_string).collect()
+    } else {
+        name.split('.').map(|part| part.replace('/', ".")).collect()
+    };
+
+    let mut path = PathBuf::new();
+    for part in parts {
+        let mut components = Path::new(&part).components();
+        let normal = matches!(components.next(), Some(Component::Normal(_)))
+            && components.next().is_none();
+
+        if !normal {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid sysctl name: {:?}", name),
+            ));
+        }
+        path.push(part);
+    }
+
+    Ok(path)
+}
+
+/// Turn a path relative to `/proc/sys` into a dotted name.
+fn dotted_name(relative: &Path) -> String {
+    let parts: Vec<String> = relative.components()
+        .map(|part| part.as_os_str().to_string_lossy().replace('.', "/"))
+        .collect();
+
+    parts.join(".")
+}
+
+/// Turn a path under `/proc/sys` back into a dotted name.
+fn name_of(path: &Path) -> Option<String> {
+    Some(dotted_name(path.strip_prefix(PROC_SYS).ok()?))
+}
+
+/// Normalize a parameter name given in either dotted or slash form.
+/// Names that [`path_of`] would refuse are returned trimmed but otherwise
+/// unchanged.
+pub fn normalize_name(name: &str) -> String {
+    relative_path(name)
+        .map(|relative| dotted_name(&relative))
+        .unwrap_or_else(|_| name.trim().to_string())
+}
+
+/// Lists every kernel parameter under `/proc/sys` by dotted name, sorted.
+pub fn list() -> Vec<String> {
+    let mut names = Vec::new();
+    collect_names(Path::new(PROC_SYS), &mut names);
+    names.sort();
+    names
+}
+
+fn collect_names(dir: &Path, names: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            collect_names(&entry.path(), names);
+        } else if let Some(name) = name_of(&entry.path()) {
+            names.push(name);
+        }
+    }
+}
+
+/// Reads a kernel parameter by name, e.g. `vm.swappiness`. Multi-value
+/// parameters keep the kernel's tab separators.
+pub fn read(name: &str) -> io::Result<String> {
+    Ok(fs::read_to_string(path_of(name)?)?.trim_end().to_string())
+}
+
+/// Reads a kernel parameter holding a single unsigned integer.
+pub fn read_u64(name: &str) -> io::Result<u64> {
+    read(name)?.trim().parse::<u64>().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData, format!("Invalid {} value: {}", name, e)
+        )
+    })
+}
+
+/// Reads every readable parameter. Write-only and permission-restricted
+/// entries are left out.
+pub fn read_all() -> BTreeMap<String, String> {
+    list()
+        .into_iter()
+        .filter_map(|name| {
+            let value = read(&name).ok()?;
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Sets a kernel parameter, like `sysctl -w`. Needs the privileges the
+/// kernel asks for that parameter, usually `CAP_SYS_ADMIN`.
+#[cfg(feature = "sysctl-write")]
+pub fn write(name: &str, value: &str) -> io::Result<()> {
+    fs::write(path_of(name)?, value)
+}
+
+/// Loads the effective configuration from the sysctl.d directories and
+/// `/etc/sysctl.conf`, as `sysctl --system` applies it.
+pub fn load_config() -> Vec<SysctlSetting> {
+    load_config_from(Path::new("/"))
+}
+
+/// Like [`load_config`], with configuration paths taken relative to `root`.
+///
+/// Files named `*.conf` are gathered from every sysctl.d directory; a name
+/// found in several directories is read only from the one with the highest
+/// precedence. The files are applied in lexicographic order of their names,
+/// followed by `/etc/sysctl.conf`. When a parameter is set more than once,
+/// the last assignment wins and is the only one returned.
+pub fn load_config_from(root: &Path) -> Vec<SysctlSetting> {
+    let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+
+    for dir in CONFIG_DIRS {
+        let Ok(entries) = fs::read_dir(root.join(dir)) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.ends_with(".conf") {
+                files.entry(file_name).or_insert_with(|| entry.path());
+            }
+        }
+    }
+
+    let mut paths: Vec<PathBuf> = files.into_values().collect();
+    paths.push(root.join("etc/sysctl.conf"));
+
+    let mut settings: Vec<SysctlSetting> = Vec::new();
+    for path in paths {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+
+        for setting in parse_config(&content, &path) {
+            settings.retain(|earlier| earlier.name != setting.name);
+            settings.push(setting);
+        }
+    }
+
+    settings
+}
+
+/// Parse the text of one sysctl configuration file.
+pub fn parse_config(content: &str, source: &Path) -> Vec<SysctlSetting> {
+    content.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';')
+            {
+                return None;
+            }
+
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            let (key, ignore_failure) = match key.strip_prefix('-') {
+                Some(key) => (key, true),
+                None => (key, false),
+            };
+
+            Some(SysctlSetting {
+                name: normalize_name(key),
+                value: value.trim().to_string(),
+                source: source.to_path_buf(),
+                line: index + 1,
+                ignore_failure,
+            })
+        })
+        .collect()
+}
+
+/// Compares the effective configuration against the running kernel.
+pub fn drift() -> Vec<SysctlDrift> {
+    drift_of(&load_config())
+}
+
+/// Compares `settings` against the running kernel, ignoring differences in
+/// whitespace between multi-value fields. Names with `*` or `?` wildcards
+/// are checked against every parameter they match.
+pub fn drift_of(settings: &[SysctlSetting]) -> Vec<SysctlDrift> {
+    let mut all_names: Option<Vec<String>> = None;
+    let mut drift = Vec::new();
+
+    for setting in settings {
+        let names = if setting.name.contains(['*', '?']) {
+            all_names.get_or_insert_with(list)
+                .iter()
+                .filter(|name| glob_match(&setting.name, name))
+                .cloned()
+                .collect()
+        } else {
+            vec![setting.name.clone()]
+        };
+
+        for name in names {
+            let live = read(&name).ok();
+            let matches = live.as_deref()
+                .is_some_and(|live| same_value(live, &setting.value));
+
+            if !matches {
+                drift.push(SysctlDrift {
+                    name,
+                    configured: setting.value.clone(),
+                    live,
+                    source: setting.source.clone(),
+                });
+            }
+        }
+    }
+
+    drift
+}
+
+/// Shell-style match of `name` against a pattern using `*` and `?`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn same_value(live: &str, configured: &str) -> bool {
+    live.split_whitespace().eq(configured.split_whitespace())
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::Path;
+
+/// Read a sysfs or procfs attribute, trimmed of its trailing newline.
+pub(crate) fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+pub(crate) fn read_u64(path: &Path) -> Option<u64> {
+    read_string(path)?.parse().ok()
+}
+
+/// Pick the bracketed entry out of a sysfs choice list like
+/// `lzo lzo-rle [zstd]`, or the whole value if nothing is bracketed.
+pub(crate) fn selected_option(value: &str) -> String {
+    value.split_whitespace()
+        .find(|opt| opt.starts_with('[') && opt.ends_with(']'))
+        .map(|opt| opt.trim_matches(|c| c == '[' || c == ']'))
+        .unwrap_or(value.trim())
+        .to_string()
+}
+
+/// Expand a sysfs list such as `0-3,8,10-11` into its members. An empty
+/// list expands to nothing.
+pub(crate) fn parse_list(value: &str) -> Vec<u32> {
+    value.trim()
+        .split(',')
+        .filter(|range| !range.is_empty())
+        .filter_map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            Some(start.trim().parse::<u32>().ok()?..=end.trim().parse().ok()?)
+        })
+        .flatten()
+        .collect()
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+const UDEV_DATA_DIR: &str = "/run/udev/data";
+
+/// A device entry from the udev database in `/run/udev/data/`.
+///
+/// Each file holds one `<type>:<value>` record per line; the ones we keep
+/// are `S:` (device symlinks, relative to `/dev`), `E:` (properties such as
+/// `ID_SERIAL` or `ID_FS_LABEL`), `G:`/`Q:` (tags), `L:` (link priority)
+/// and `I:` (initialization time).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UdevRecord {
+    pub symlinks: Vec<String>,
+    pub properties: HashMap<String, String>,
+    pub tags: Vec<String>,
+    pub current_tags: Vec<String>,
+    pub link_priority: Option<i32>,
+    pub initialized_usec: Option<u64>,
+}
+
+impl UdevRecord {
+    /// Parse the contents of a udev database file.
+    pub fn parse(content: &str) -> Self {
+        let mut record = UdevRecord::default();
+
+        for line in content.lines() {
+            let Some((kind, value)) = line.split_once(':') else {
+                continue;
+            };
+
+            match kind {
+                "S" => record.symlinks.push(value.to_string()),
+                "E" => {
+                    if let Some((key, val)) = value.split_once('=') {
+                        record.properties.insert(key.to_string(), val.to_string());
+                    }
+                }
+                "G" => record.tags.push(value.to_string()),
+                "Q" => record.current_tags.push(value.to_string()),
+                "L" => record.link_priority = value.parse().ok(),
+                "I" => record.initialized_usec = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        record
+    }
+
+    /// Look up an `E:` property such as `ID_SERIAL`.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// The first of `keys` that is set, for properties udev may report
+    /// under more than one name (e.g. `ID_MODEL_FROM_DATABASE`, `ID_MODEL`).
+    pub fn first_property(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter().find_map(|key| self.property(key))
+    }
+}
+
+/// Returns whether a udev database is present. It is usually missing
+/// inside containers, where callers should fall back to scanning
+/// `/dev/disk/by-*`.
+pub fn is_available() -> bool {
+    Path::new(UDEV_DATA_DIR).is_dir()
+}
+
+/// Reads the udev record of the block device `major:minor`.
+pub fn read_block_device(major: u32, minor: u32) -> Option<UdevRecord> {
+    read_record(&format!("b{}:{}", major, minor))
+}
+
+/// Reads the udev record of the character device `major:minor`.
+pub fn read_char_device(major: u32, minor: u32) -> Option<UdevRecord> {
+    read_record(&format!("c{}:{}", major, minor))
+}
+
+/// Reads the udev record of the network interface with index `ifindex`.
+pub fn read_net_device(ifindex: u32) -> Option<UdevRecord> {
+    read_record(&format!("n{}", ifindex))
+}
+
+fn read_record(id: &str) -> Option<UdevRecord> {
+    fs::read_to_string(Path::new(UDEV_DATA_DIR).join(id))
+        .ok()
+        .map(|content| UdevRecord::parse(&content))
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// Virtual memory counters from `/proc/vmstat`, keyed by kernel name.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VmStat {
+    pub counters: BTreeMap<String, u64>,
+}
+
+/// Per-second rates of the `/proc/vmstat` event counters between two
+/// samples.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct VmStatRates {
+    pub interval_secs: f64,
+    pub per_second: BTreeMap<String, f64>,
+}
+
+/// Generates an accessor per counter on both `VmStat` and `VmStatRates`.
+/// Counters that older kernels split per zone (`pgscan_kswapd_normal`, ...)
+/// are summed when the combined key is missing.
+macro_rules! vmstat_counters {
+    ($($name:ident),* $(,)?) => {
+        impl VmStat {
+            $(
+                pub fn $name(&self) -> Option<u64> {
+                    self.counter(stringify!($name))
+                }
+            )*
+        }
+
+        impl VmStatRates {
+            $(
+                pub fn $name(&self) -> Option<f64> {
+                    self.rate(stringify!($name))
+                }
+            )*
+        }
+    };
+}
+
+vmstat_counters! {
+    pgpgin,
+    pgpgout,
+    pswpin,
+    pswpout,
+    pgfault,
+    pgmajfault,
+    pgscan_kswapd,
+    pgscan_direct,
+    pgscan_khugepaged,
+    pgsteal_kswapd,
+    pgsteal_direct,
+    pgsteal_khugepaged,
+    allocstall,
+    oom_kill,
+    compact_stall,
+    compact_fail,
+    compact_success,
+    compact_migrate_scanned,
+    compact_free_scanned,
+    compact_isolated,
+    thp_fault_alloc,
+    thp_fault_fallback,
+    thp_collapse_alloc,
+    thp_collapse_alloc_failed,
+    thp_split_page,
+    thp_swpout,
+    thp_swpout_fallback,
+    workingset_refault_anon,
+    workingset_refault_file,
+    workingset_activate_anon,
+    workingset_activate_file,
+    workingset_restore_anon,
+    workingset_restore_file,
+    workingset_nodereclaim,
+}
+
+impl VmStat {
+    /// Reads `/proc/vmstat`, returning no counters if it can't be read.
+    pub fn new() -> Self {
+        Self::parse(&fs::read_to_string("/proc/vmstat").unwrap_or_default())
+    }
+
+    /// Parse the `name value` lines of a `/proc/vmstat` file.
+    pub fn parse(content: &str) -> Self {
+        let counters = content.lines()
+            .filter_map(|line| {
+                let (name, value) = line.split_once(' ')?;
+                Some((name.to_string(), value.trim().parse().ok()?))
+            })
+            .collect();
+
+        VmStat { counters }
+    }
+
+    /// Look up a counter by its exact `/proc/vmstat` name.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.counters.get(name).copied()
+    }
+
+    /// Per-second rates of the event counters since `earlier`, taken
+    /// `interval` before this sample.
+    ///
+    /// Gauges (the `nr_*` entries other than a few event counts, and
+    /// `workingset_nodes`) are left out, and a counter that went backwards
+    /// is reported as 0.
+    pub fn rates_since(&self, earlier: &VmStat, interval: Duration)
+        -> VmStatRates {
+        let secs = interval.as_secs_f64();
+        let mut per_second = BTreeMap::new();
+
+        if secs > 0.0 {
+            for (name, now) in &self.counters {
+                if !is_event_counter(name) {
+                    continue;
+                }
+
+                if let Some(before) = earlier.counters.get(name) {
+                    let delta = now.saturating_sub(*before);
+                    per_second.insert(name.clone(), delta as f64 / secs);
+                }
+            }
+        }
+
+        VmStatRates { interval_secs: secs, per_second }
+    }
+
+    /// Reads `/proc/vmstat` twice, sleeping `interval` in between, and
+    /// returns the event rates over the time that actually passed.
+    pub fn sample_rates(interval: Duration) -> VmStatRates {
+        let earlier = VmStat::new();
+        let start = Instant::now();
+        thread::sleep(interval);
+        let later = VmStat::new();
+
+        later.rates_since(&earlier, start.elapsed())
+    }
+
+    fn counter(&self, name: &str) -> Option<u64> {
+        self.get(name).or_else(|| sum_split(&self.counters, name))
+    }
+}
+
+impl VmStatRates {
+    /// Look up a rate by its exact `/proc/vmstat` name.
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.per_second.get(name).copied()
+    }
+
+    /// Pages scanned per second by both kswapd and direct reclaim.
+    pub fn pgscan_total(&self) -> Option<f64> {
+        sum_present(&[self.pgscan_kswapd(), self.pgscan_direct()])
+    }
+
+    /// Pages swapped in and out per second.
+    pub fn swap_pages(&self) -> Option<f64> {
+        sum_present(&[self.pswpin(), self.pswpout()])
+    }
+
+    fn rate(&self, name: &str) -> Option<f64> {
+        self.get(name).or_else(|| sum_split(&self.per_second, name))
+    }
+}
+
+const ZONES: [&str; 5] = ["dma", "dma32", "normal", "movable", "high"];
+
+/// Gauges in `/proc/vmstat` whose names lack the `nr_` prefix.
+const GAUGES: [&str; 1] = ["workingset_nodes"];
+
+/// Event counters in `/proc/vmstat` that carry the `nr_` prefix anyway.
+const NR_EVENTS: [&str; 11] = [
+    "nr_dirtied",
+    "nr_written",
+    "nr_throttled_written",
+    "nr_vmscan_write",
+    "nr_vmscan_immediate_reclaim",
+    "nr_foll_pin_acquired",
+    "nr_foll_pin_released",
+    "nr_tlb_remote_flush",
+    "nr_tlb_remote_flush_received",
+    "nr_tlb_local_flush_all",
+    "nr_tlb_local_flush_one",
+];
+
+fn is_event_counter(name: &str) -> bool {
+    if name.starts_with("nr_") {
+        NR_EVENTS.contains(&name)
+    } else {
+        !GAUGES.contains(&name)
+    }
+}
+
+/// Sum the per-zone variants `<name>_<zone>` of a counter, if any exist.
+fn sum_split<T>(map: &BTreeMap<String, T>, name: &str) -> Option<T>
+where
+    T: Copy + std::iter::Sum<T>,
+{
+    let prefix = format!("{}_", name);
+    let mut parts = map.iter()
+        .filter(|(key, _)| {
+            key.strip_prefix(&prefix).is_some_and(|zone| ZONES.contains(&zone))
+        })
+        .map(|(_, value)| *value)
+        .peekable();
+
+    parts.peek()?;
+    Some(parts.sum())
+}
+
+fn sum_present(values: &[Option<f64>]) -> Option<f64> {
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.is_empty() {
+        None
+    } else {
+        Some(present.iter().sum())
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use serde::{Serialize, Deserialize};
+
+/// Free block counts of one zone from `/proc/buddyinfo`. Entry `n` of
+/// `free_by_order` counts free blocks of `2^n` contiguous pages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BuddyZone {
+    pub node: u32,
+    pub zone: String,
+    pub free_by_order: Vec<u64>,
+}
+
+/// Free blocks of one migrate type in one zone, from `/proc/pagetypeinfo`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MigrateTypeFree {
+    pub node: u32,
+    pub zone: String,
+    pub migrate_type: String,
+    pub free_by_order: Vec<u64>,
+}
+
+/// Number of page blocks of each migrate type in one zone.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ZoneBlocks {
+    pub node: u32,
+    pub zone: String,
+    pub blocks: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PageTypeInfo {
+    pub page_block_order: Option<u32>,
+    pub pages_per_block: Option<u64>,
+    pub free: Vec<MigrateTypeFree>,
+    pub blocks: Vec<ZoneBlocks>,
+}
+
+/// One zone from `/proc/zoneinfo`. Counts are in pages.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Zone {
+    pub node: u32,
+    pub name: String,
+    pub free_pages: u64,
+    pub min: u64,
+    pub low: u64,
+    pub high: u64,
+    pub promo: Option<u64>,
+    pub boost: Option<u64>,
+    pub spanned: u64,
+    pub present: u64,
+    pub managed: u64,
+    pub cma: Option<u64>,
+    /// Pages kept free in this zone against allocations that could have
+    /// been served from each higher zone (`lowmem_reserve`).
+    pub protection: Vec<u64>,
+    pub start_pfn: Option<u64>,
+    pub node_unreclaimable: Option<bool>,
+    /// The zone's `nr_*` and `numa_*` counters.
+    pub stats: BTreeMap<String, u64>,
+    /// Node-wide counters, which the kernel prints under the first zone
+    /// of each node only.
+    pub node_stats: BTreeMap<String, u64>,
+}
+
+impl BuddyZone {
+    /// Total free pages in the zone.
+    pub fn free_pages(&self) -> u64 {
+        self.free_by_order.iter()
+            .enumerate()
+            .map(|(order, count)| count << order)
+            .sum()
+    }
+
+    /// Free blocks at least `2^order` pages large, counted in units of
+    /// `2^order` pages.
+    fn suitable_blocks(&self, order: usize) -> u64 {
+        self.free_by_order.iter()
+            .enumerate()
+            .skip(order)
+            .map(|(o, count)| count << (o - order))
+            .sum()
+    }
+
+    /// The kernel's fragmentation index (`extfrag_index` in debugfs) for an
+    /// allocation of `2^order` pages.
+    ///
+    /// `-1.0` means the allocation can be served from a free block. Otherwise
+    /// values towards `0.0` mean it fails for lack of memory and values
+    /// towards `1.0` that it fails because free memory is fragmented:
+    /// `1 - (1 + free_pages / 2^order) / free_blocks`.
+    pub fn fragmentation_index(&self, order: usize) -> f64 {
+        let total_blocks: u64 = self.free_by_order.iter().sum();
+        if total_blocks == 0 {
+            return 0.0;
+        }
+
+        if self.suitable_blocks(order) > 0 {
+            return -1.0;
+        }
+
+        let requested = (1u64 << order) as f64;
+        1.0 - (1.0 + self.free_pages() as f64 / requested) / total_blocks as f64
+    }
+
+    /// The kernel's unusable free space index (`unusable_index` in debugfs):
+    /// the share of free memory in blocks too small for `2^order` pages.
+    pub fn unusable_index(&self, order: usize) -> f64 {
+        let free_pages = self.free_pages();
+        if free_pages == 0 {
+            return 1.0;
+        }
+
+        let usable = self.suitable_blocks(order) << order;
+        (free_pages - usable) as f64 / free_pages as f64
+    }
+
+    /// [`BuddyZone::fragmentation_index`] for every order the zone reports.
+    pub fn fragmentation_indices(&self) -> Vec<f64> {
+        (0..self.free_by_order.len())
+            .map(|order| self.fragmentation_index(order))
+            .collect()
+    }
+}
+
+impl Zone {
+    /// Whether free pages have dropped below the `min` watermark, where only
+    /// atomic and reclaim allocations still succeed.
+    pub fn below_min(&self) -> bool {
+        self.free_pages < self.min
+    }
+
+    /// Whether free pages have dropped below the `low` watermark, which wakes
+    /// kswapd.
+    pub fn below_low(&self) -> bool {
+        self.free_pages < self.low
+    }
+}
+
+/// Returns per-zone free block counts from `/proc/buddyinfo`.
+pub fn get_buddyinfo() -> Vec<BuddyZone> {
+    fs::read_to_string("/proc/buddyinfo")
+        .map(|content| parse_buddyinfo(&content))
+        .unwrap_or_default()
+}
+
+/// Returns `/proc/pagetypeinfo`, which only root can read.
+pub fn get_pagetypeinfo() -> io::Result<PageTypeInfo> {
+    Ok(parse_pagetypeinfo(&fs::read_to_string("/proc/pagetypeinfo")?))
+}
+
+/// Returns every zone of every node from `/proc/zoneinfo`.
+pub fn get_zoneinfo() -> Vec<Zone> {
+    fs::read_to_string("/proc/zoneinfo")
+        .map(|content| parse_zoneinfo(&content))
+        .unwrap_or_default()
+}
+
+/// Split a `Node 0, zone   Normal` prefix off a line, returning the node,
+/// the zone name and the rest of the line after the zone name.
+fn node_and_zone(line: &str) -> Option<(u32, String, &str)> {
+    let rest = line.trim_start().strip_prefix("Node")?;
+    let (node, rest) = rest.split_once(',')?;
+    let rest = rest.trim_start().strip_prefix("zone")?.trim_start();
+    let end = rest.find(|c: char| c.is_whitespace() || c == ',')
+        .unwrap_or(rest.len());
+
+    Some((node.trim().parse().ok()?, rest[..end].to_string(), &rest[end..]))
+}
+
+fn parse_counts(fields: &str) -> Vec<u64> {
+    fields.split_whitespace().filter_map(|n| n.parse().ok()).collect()
+}
+
+/// Parse the text of `/proc/buddyinfo`.
+pub fn parse_buddyinfo(content: &str) -> Vec<BuddyZone> {
+    content.lines()
+        .filter_map(|line| {
+            let (node, zone, rest) = node_and_zone(line)?;
+            Some(BuddyZone { node, zone, free_by_order: parse_counts(rest) })
+        })
+        .collect()
+}
+
+/// Parse the text of `/proc/pagetypeinfo`.
+pub fn parse_pagetypeinfo(content: &str) -> PageTypeInfo {
+    let mut info = PageTypeInfo::default();
+    let mut block_types: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        if let Some(order) = line.strip_prefix("Page block order:") {
+            info.page_block_order = order.trim().parse().ok();
+        } else if let Some(pages) = line.strip_prefix("Pages per block:") {
+            info.pages_per_block = pages.trim().parse().ok();
+        } else if let Some(types) = line.strip_prefix("Number of blocks type") {
+            block_types = types.split_whitespace().map(String::from).collect();
+        } else if let Some((node, zone, rest)) = node_and_zone(line) {
+            if let Some(rest) = rest.trim_start()
+                .strip_prefix(',')
+                .and_then(|rest| rest.trim_start().strip_prefix("type")) {
+                let rest = rest.trim_start();
+                let (migrate_type, counts) = rest
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((rest, ""));
+
+                info.free.push(MigrateTypeFree {
+                    node,
+                    zone,
+                    migrate_type: migrate_type.to_string(),
+                    free_by_order: parse_counts(counts),
+                });
+            } else {
+                let blocks = block_types.iter()
+                    .cloned()
+                    .zip(parse_counts(rest))
+                    .collect();
+
+                info.blocks.push(ZoneBlocks { node, zone, blocks });
+            }
+        }
+    }
+
+    info
+}
+
+/// Parse the text of `/proc/zoneinfo`.
+pub fn parse_zoneinfo(content: &str) -> Vec<Zone> {
+    let mut zones: Vec<Zone> = Vec::new();
+    let mut in_node_stats = false;
+    let mut in_pagesets = false;
+
+    for line in content.lines() {
+        if let Some((node, name, _)) = node_and_zone(line) {
+            zones.push(Zone { node, name, ..Default::default() });
+            in_node_stats = false;
+            in_pagesets = false;
+            continue;
+        }
+
+        let Some(zone) = zones.last_mut() else {
+            continue;
+        };
+        let trimmed = line.trim();
+
+        match trimmed {
+            "per-node stats" => {
+                in_node_stats = true;
+                continue;
+            }
+            "pagesets" => {
+                in_pagesets = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(protection) = trimmed.strip_prefix("protection:") {
+            zone.protection = protection.trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect();
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.rsplit_once(char::is_whitespace) else {
+            continue;
+        };
+        let key = key.trim().trim_end_matches(':');
+        let Ok(value) = value.parse::<u64>() else {
+            continue;
+        };
+
+        match key {
+            "pages free" => {
+                in_node_stats = false;
+                zone.free_pages = value;
+            }
+            "node_unreclaimable" => {
+                in_pagesets = false;
+                zone.node_unreclaimable = Some(value != 0);
+            }
+            "start_pfn" => zone.start_pfn = Some(value),
+            _ if in_pagesets => {}
+            _ if in_node_stats => {
+                zone.node_stats.insert(key.to_string(), value);
+            }
+            "min" => zone.min = value,
+            "low" => zone.low = value,
+            "high" => zone.high = value,
+            "promo" => zone.promo = Some(value),
+            "boost" => zone.boost = Some(value),
+            "spanned" => zone.spanned = value,
+            "present" => zone.present = value,
+            "managed" => zone.managed = value,
+            "cma" => zone.cma = Some(value),
+            _ => {
+                zone.stats.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    zones
+}
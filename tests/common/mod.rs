@@ -0,0 +1,52 @@
+//! Helpers shared by the tests that set up live network namespaces.
+
+// Each test crate compiles this module and uses only part of it.
+#![allow(dead_code)]
+
+use std::process::Command;
+use std::thread;
+
+use nix::libc;
+
+/// Run `program` with whitespace-separated `args`, reporting success.
+pub fn run(program: &str, args: &str) -> bool {
+    Command::new(program)
+        .args(args.split_whitespace())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+pub fn ip(args: &str) -> bool {
+    run("ip", args)
+}
+
+/// Run `test` on a thread moved into a new network namespace; commands it
+/// spawns inherit the namespace.
+pub fn in_netns(test: impl FnOnce() + Send + 'static) {
+    thread::spawn(move || {
+        let unshared = unsafe { libc::unshare(libc::CLONE_NEWNET) };
+        assert_eq!(unshared, 0, "cannot create a network namespace");
+        test();
+    })
+    .join()
+    .unwrap()
+}
+
+/// Move this thread into new network and mount namespaces, with a sysfs
+/// of its own: sysfs shows the network namespace it was mounted in.
+pub fn unshare_with_sysfs() -> bool {
+    unsafe {
+        if libc::unshare(libc::CLONE_NEWNET | libc::CLONE_NEWNS) != 0 {
+            return false;
+        }
+        let none = c"none".as_ptr();
+        let sysfs = c"sysfs".as_ptr();
+        libc::mount(
+            none, c"/".as_ptr(), std::ptr::null(),
+            libc::MS_REC | libc::MS_PRIVATE, std::ptr::null(),
+        ) == 0
+            && libc::mount(
+                sysfs, c"/sys".as_ptr(), sysfs, 0, std::ptr::null(),
+            ) == 0
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use patagonicus::disks::{self, BlockDevice, LoopInfo, PersistentNames};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/block")
+}
+
+fn sys() -> PathBuf {
+    fixtures().join("sys")
+}
+
+#[test]
+fn reads_loop_info() {
+    let sys_block = sys().join("block");
+
+    let info = disks::read_loop_info(&sys_block, "loop0").unwrap();
+    assert_eq!(info, LoopInfo {
+        backing_file: "/var/lib/images/build cache.img".to_string(),
+        offset: 1048576,
+        sizelimit: 0,
+        autoclear: true,
+        partscan: true,
+        read_only: true,
+    });
+
+    assert_eq!(disks::read_loop_info(&sys_block, "zram0"), None);
+    assert_eq!(disks::read_loop_info(&sys_block, "loop7"), None);
+}
+
+#[test]
+fn reads_zram_info() {
+    let sys_block = sys().join("block");
+
+    let info = disks::read_zram_info(&sys_block, "zram0").unwrap();
+    assert_eq!(info.comp_algorithm, "zstd");
+    assert_eq!(info.disksize, 8589934592);
+    assert_eq!(info.orig_data_size, 1073741824);
+    assert_eq!(info.compr_data_size, 268435456);
+    assert_eq!(info.mem_used_total, 281018368);
+    assert_eq!(info.compression_ratio, Some(4.0));
+
+    assert_eq!(disks::read_zram_info(&sys_block, "loop0"), None);
+}
+
+fn names(devices: &[BlockDevice]) -> Vec<&str> {
+    devices.iter().map(BlockDevice::name).collect()
+}
+
+#[test]
+fn finds_partition_parents_by_sysfs_layout() {
+    let class_block = sys().join("class/block");
+    let cases = [
+        ("mmcblk0p1", "mmcblk0", 1, (179, 1)),
+        ("nvme0n1p2", "nvme0n1", 2, (259, 2)),
+        ("xvda1", "xvda", 1, (202, 1)),
+        ("loop0p1", "loop0", 1, (259, 4)),
+    ];
+
+    for (name, parent, number, dev) in cases {
+        let device = BlockDevice::read_from(&class_block, name).unwrap();
+        assert!(device.is_partition(), "{}", name);
+        assert_eq!(device.partition_number(), Some(number));
+        assert_eq!(device.dev(), Some(dev));
+        assert!(device.children().is_empty());
+
+        let disk = device.parent().unwrap();
+        assert_eq!(disk.name(), parent);
+        assert!(!disk.is_partition());
+        assert_eq!(disk.parent(), None);
+        assert!(names(&disk.children()).contains(&name));
+    }
+}
+
+#[test]
+fn lists_partitions_in_order() {
+    let class_block = sys().join("class/block");
+
+    let nvme = BlockDevice::read_from(&class_block, "nvme0n1").unwrap();
+    assert_eq!(names(&nvme.children()), ["nvme0n1p1", "nvme0n1p2"]);
+
+    // Boot areas sit next to the disk rather than inside it.
+    let mmc = BlockDevice::read_from(&class_block, "mmcblk0").unwrap();
+    assert_eq!(names(&mmc.children()), ["mmcblk0p1"]);
+    let boot = BlockDevice::read_from(&class_block, "mmcblk0boot0").unwrap();
+    assert!(!boot.is_partition());
+    assert!(boot.children().is_empty());
+
+    // The loop/ attribute directory is not a partition.
+    let parent = BlockDevice::read_from(&class_block, "loop0").unwrap();
+    assert_eq!(names(&parent.children()), ["loop0p1"]);
+
+    assert!(BlockDevice::read_from(&class_block, "sdz").is_err());
+}
+
+#[test]
+fn lists_all_block_devices() {
+    let mut devices = disks::read_all_block_devices(&sys().join("class/block"));
+    devices.sort_by(|a, b| a.name().cmp(b.name()));
+
+    assert_eq!(names(&devices), [
+        "dm-0", "loop0", "loop0p1", "mmcblk0", "mmcblk0boot0", "mmcblk0p1",
+        "nvme0n1", "nvme0n1p1", "nvme0n1p2", "sda", "sda1", "sdaa", "xvda",
+        "xvda1", "zram0",
+    ]);
+}
+
+fn resolve(spec: &str) -> Option<String> {
+    disks::resolve_device_in(
+        &fixtures().join("dev"), &sys().join("class/block"), spec
+    )
+}
+
+#[test]
+fn resolves_device_specs() {
+    assert_eq!(resolve("UUID=1111-AAAA").as_deref(), Some("sda1"));
+    assert_eq!(resolve("uuid=2222-BBBB").as_deref(), Some("sdaa"));
+    assert_eq!(resolve("PARTUUID=0a1b2c3d-01").as_deref(), Some("sda1"));
+    assert_eq!(resolve("ID=ata-DISK_A").as_deref(), Some("sda"));
+    assert_eq!(resolve("ID=ata-DISK_AA").as_deref(), Some("sdaa"));
+
+    // Labels match both as written and as udev escapes them.
+    assert_eq!(resolve("LABEL=My Disk").as_deref(), Some("sda1"));
+    assert_eq!(resolve("LABEL=\"My Disk\"").as_deref(), Some("sda1"));
+    assert_eq!(resolve(r"LABEL=My\x20Disk").as_deref(), Some("sda1"));
+    assert_eq!(resolve("LABEL=Données").as_deref(), Some("nvme0n1p2"));
+    assert_eq!(
+        resolve("PARTLABEL=EFI System Partition").as_deref(),
+        Some("nvme0n1p2")
+    );
+
+    assert_eq!(resolve("/dev/mapper/vg-root").as_deref(), Some("dm-0"));
+    assert_eq!(resolve("/dev/disk/by-id/ata-DISK_A").as_deref(), Some("sda"));
+
+    // A kernel name wins over a label that happens to spell it.
+    assert_eq!(resolve("sda").as_deref(), Some("sda"));
+    assert_eq!(resolve("ata-DISK_AA").as_deref(), Some("sdaa"));
+
+    assert_eq!(resolve("UUID=3333-CCCC"), None);
+    assert_eq!(resolve("UUID=1111"), None);
+    assert_eq!(resolve("sdz"), None);
+}
+
+#[test]
+fn matches_links_by_target() {
+    let dev = fixtures().join("dev");
+
+    let sda = PersistentNames::from_links(&dev, "sda").unwrap();
+    assert_eq!(sda.by_id, ["ata-DISK_A", "wwn-0x5002538e40a1b2c3"]);
+    assert_eq!(sda.by_path, ["pci-0000:00:17.0-ata-1"]);
+    assert!(sda.by_label.is_empty() && sda.by_uuid.is_empty());
+
+    let sda1 = PersistentNames::from_links(&dev, "sda1").unwrap();
+    assert_eq!(sda1, PersistentNames {
+        by_id: vec!["ata-DISK_A-part1".to_string()],
+        by_uuid: vec!["1111-AAAA".to_string()],
+        by_partuuid: vec!["0a1b2c3d-01".to_string()],
+        by_label: vec![r"My\x20Disk".to_string()],
+        by_partlabel: Vec::new(),
+        by_path: vec!["pci-0000:00:17.0-ata-1-part1".to_string()],
+        by_diskseq: Vec::new(),
+    });
+
+    let sdaa = PersistentNames::from_links(&dev, "sdaa").unwrap();
+    assert_eq!(sdaa.by_id, ["ata-DISK_AA"]);
+    assert_eq!(sdaa.by_label, ["sda"]);
+
+    assert!(PersistentNames::from_links(&dev, "sdz").unwrap().is_empty());
+}
+
+#[test]
+fn escapes_devnode_names_like_udev() {
+    assert_eq!(disks::encode_devnode_name("My Disk"), r"My\x20Disk");
+    assert_eq!(disks::encode_devnode_name("Données"), "Données");
+    assert_eq!(disks::encode_devnode_name(r"a/b\c"), r"a\x2fb\x5cc");
+    assert_eq!(disks::encode_devnode_name("EFI-1.0:#+=@_"), "EFI-1.0:#+=@_");
+
+    assert_eq!(disks::decode_devnode_name(r"My\x20Disk"), "My Disk");
+    assert_eq!(disks::decode_devnode_name(r"Donn\xc3\xa9es"), "Données");
+    assert_eq!(disks::decode_devnode_name(r"bad\xzz"), r"bad\xzz");
+    assert_eq!(disks::decode_devnode_name(r"short\x2"), r"short\x2");
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use patagonicus::edac::{self, read_memory_controllers};
+
+// Layout captured from an skx_edac host with two memory controllers.
+
+fn controllers() -> Vec<edac::MemoryController> {
+    read_memory_controllers(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/edac/mc")
+    )
+}
+
+#[test]
+fn reads_controller_counts() {
+    let controllers = controllers();
+    let names: Vec<&str> = controllers.iter()
+        .map(|mc| mc.name.as_str())
+        .collect();
+    assert_eq!(names, ["mc0", "mc1"]);
+
+    let mc0 = &controllers[0];
+    assert_eq!(mc0.mc_name.as_deref(), Some("Skylake Socket#0 IMC#0"));
+    assert_eq!(mc0.size, Some(64 * 1024 * 1024 * 1024));
+    assert_eq!(mc0.ce_count, 17);
+    assert_eq!(mc0.ce_noinfo_count, 2);
+    assert_eq!(mc0.seconds_since_reset, Some(8133912));
+
+    assert_eq!(edac::total_ce_count(&controllers), 17);
+    assert_eq!(edac::total_ue_count(&controllers), 1);
+}
+
+#[test]
+fn reads_dimms_in_index_order() {
+    let controllers = controllers();
+    let mc1 = &controllers[1];
+
+    let names: Vec<&str> = mc1.dimms.iter()
+        .map(|dimm| dimm.name.as_str())
+        .collect();
+    assert_eq!(names, ["dimm0", "dimm10"]);
+
+    let dimm = &mc1.dimms[0];
+    assert_eq!(dimm.label.as_deref(), Some("CPU_SrcID#0_MC#1_Chan#0_DIMM#0"));
+    assert_eq!(dimm.location.as_deref(), Some("channel 0 slot 0"));
+    assert_eq!(dimm.size, Some(32 * 1024 * 1024 * 1024));
+    assert_eq!(dimm.mem_type.as_deref(), Some("Registered-DDR4"));
+    assert_eq!(dimm.edac_mode.as_deref(), Some("SECDED"));
+    assert_eq!(dimm.ue_count, 1);
+
+    assert_eq!(mc1.dimms[1].label, None);
+}
+
+#[test]
+fn lists_failing_dimms() {
+    let controllers = controllers();
+    let failing: Vec<&str> = controllers.iter()
+        .flat_map(|mc| mc.failing_dimms())
+        .filter_map(|dimm| dimm.label.as_deref())
+        .collect();
+
+    assert_eq!(failing, [
+        "CPU_SrcID#0_MC#0_Chan#0_DIMM#0",
+        "CPU_SrcID#0_MC#1_Chan#0_DIMM#0",
+    ]);
+}
+
+#[test]
+fn no_edac_driver_means_no_controllers() {
+    assert!(read_memory_controllers(Path::new("/nonexistent")).is_empty());
+}
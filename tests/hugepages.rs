@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use patagonicus::hugepages::{
+    HugePagePool, HugePages, NodeHugePagePool, ShmemThpMode, ThpDefrag,
+    ThpMode,
+};
+
+fn read_fixture() -> HugePages {
+    HugePages::read_from(
+        &Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/hugepages/sys")
+    )
+}
+
+#[test]
+fn parses_selected_thp_modes() {
+    assert_eq!(
+        ThpMode::parse("always [madvise] never"), Some(ThpMode::Madvise)
+    );
+    assert_eq!(
+        ThpMode::parse("always [inherit] madvise never"),
+        Some(ThpMode::Inherit)
+    );
+    assert_eq!(ThpMode::parse("[always] madvise never"), Some(ThpMode::Always));
+    assert_eq!(ThpMode::parse("never"), Some(ThpMode::Never));
+    assert_eq!(ThpMode::parse("always [sometimes] never"), None);
+    assert_eq!(ThpMode::parse(""), None);
+
+    assert_eq!(
+        ThpDefrag::parse("always defer [defer+madvise] madvise never"),
+        Some(ThpDefrag::DeferMadvise)
+    );
+    assert_eq!(
+        ShmemThpMode::parse("always [within_size] advise never deny force"),
+        Some(ShmemThpMode::WithinSize)
+    );
+}
+
+#[test]
+fn reads_hugetlb_pools() {
+    let hugepages = read_fixture();
+
+    assert_eq!(hugepages.pools, [
+        HugePagePool {
+            page_size: 2 << 20,
+            nr: 1024,
+            free: 900,
+            reserved: 12,
+            surplus: 0,
+            overcommit: 64,
+        },
+        HugePagePool {
+            page_size: 1 << 30,
+            nr: 4,
+            free: 4,
+            reserved: 0,
+            surplus: 0,
+            overcommit: 0,
+        },
+    ]);
+}
+
+#[test]
+fn reads_per_node_pools_in_node_order() {
+    let nodes = read_fixture().nodes;
+
+    let order: Vec<(u32, u64)> = nodes.iter()
+        .map(|pool| (pool.node, pool.page_size))
+        .collect();
+    assert_eq!(order, [
+        (0, 2 << 20), (0, 1 << 30), (1, 2 << 20), (10, 2 << 20),
+    ]);
+    assert_eq!(nodes[2], NodeHugePagePool {
+        node: 1,
+        page_size: 2 << 20,
+        nr: 512,
+        free: 500,
+        surplus: 0,
+    });
+}
+
+#[test]
+fn reads_thp_and_mthp_settings() {
+    let thp = read_fixture().transparent;
+
+    assert_eq!(thp.enabled, Some(ThpMode::Madvise));
+    assert_eq!(thp.defrag, Some(ThpDefrag::DeferMadvise));
+    assert_eq!(thp.shmem_enabled, Some(ShmemThpMode::Never));
+    assert_eq!(thp.use_zero_page, Some(true));
+    assert_eq!(thp.hpage_pmd_size, Some(2 << 20));
+    assert_eq!(thp.khugepaged.defrag, Some(true));
+    assert_eq!(thp.khugepaged.max_ptes_none, Some(511));
+    assert_eq!(thp.khugepaged.pages_collapsed, Some(1203));
+
+    let sizes: Vec<u64> = thp.sizes.iter().map(|size| size.page_size).collect();
+    assert_eq!(sizes, [16 << 10, 64 << 10, 2 << 20]);
+
+    assert_eq!(thp.sizes[0].enabled, Some(ThpMode::Never));
+    assert!(thp.sizes[0].stats.is_empty());
+    assert_eq!(thp.sizes[1].enabled, Some(ThpMode::Inherit));
+    assert_eq!(thp.sizes[1].shmem_enabled, Some(ShmemThpMode::Inherit));
+    assert_eq!(thp.sizes[1].stats, BTreeMap::from([
+        ("anon_fault_alloc".to_string(), 5120),
+        ("anon_fault_fallback".to_string(), 17),
+        ("swpout".to_string(), 3),
+    ]));
+    assert_eq!(thp.sizes[2].shmem_enabled, Some(ShmemThpMode::WithinSize));
+}
+
+#[test]
+fn missing_sysfs_is_empty() {
+    let hugepages = HugePages::read_from(Path::new("/nonexistent"));
+
+    assert!(hugepages.pools.is_empty() && hugepages.nodes.is_empty());
+    assert_eq!(hugepages.transparent, Default::default());
+}
@@ -0,0 +1,29 @@
+use std::path::Path;
+
+use patagonicus::ksm::{Ksm, KsmRun};
+
+#[test]
+fn reads_ksm_state() {
+    let ksm = Ksm::read_from(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ksm")
+    );
+
+    assert_eq!(ksm.run, Some(KsmRun::Running));
+    assert_eq!(ksm.pages_sharing, Some(512330));
+    assert_eq!(ksm.general_profit, Some(-1536000));
+    assert_eq!(ksm.merge_across_nodes, Some(false));
+    assert_eq!(ksm.advisor_mode.as_deref(), Some("scan-time"));
+
+    let sharing = ksm.sharing_ratio().unwrap();
+    assert!((sharing - 25.1007).abs() < 1e-4);
+    let unshared = ksm.unshared_ratio().unwrap();
+    assert!((unshared - 2.0456).abs() < 1e-4);
+}
+
+#[test]
+fn missing_ksm_is_empty() {
+    let ksm = Ksm::read_from(Path::new("/nonexistent"));
+
+    assert_eq!(ksm, Ksm::default());
+    assert_eq!(ksm.sharing_ratio(), None);
+}
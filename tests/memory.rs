@@ -0,0 +1,155 @@
+use patagonicus::memory::{Meminfo, MemoryPressure, VmTunables};
+
+// Expected `used`, `buff/cache`, `available` and swap figures were taken
+// from `free -b -w` (procps-ng 4.0.2) with each fixture bind-mounted over
+// `/proc/meminfo`.
+
+const SERVER: &str = include_str!("fixtures/meminfo/server");
+const PRESSURE: &str = include_str!("fixtures/meminfo/pressure");
+const LEGACY: &str = include_str!("fixtures/meminfo/legacy");
+
+#[test]
+fn parses_unitless_hugepage_counts() {
+    let meminfo = Meminfo::parse(PRESSURE);
+
+    assert_eq!(meminfo.huge_pages_total, Some(0));
+    assert_eq!(meminfo.hugepagesize, Some(2048 * 1024));
+    assert_eq!(meminfo.get("HugePages_Rsvd"), Some(0));
+    assert_eq!(meminfo.get("Committed_AS"), Some(31245688 * 1024));
+    assert!(meminfo.other.is_empty());
+}
+
+#[test]
+fn keeps_unknown_keys() {
+    let meminfo = Meminfo::parse("MemTotal: 1024 kB\nFutureKey:  7 kB\n");
+
+    assert_eq!(meminfo.mem_total, Some(1024 * 1024));
+    assert_eq!(meminfo.get("FutureKey"), Some(7 * 1024));
+    assert_eq!(meminfo.to_map().len(), 2);
+    assert_eq!(meminfo.mem_available, None);
+}
+
+#[test]
+fn matches_free_on_current_kernel() {
+    let meminfo = Meminfo::parse(SERVER);
+
+    assert_eq!(meminfo.mem_total, Some(6305947648));
+    assert_eq!(meminfo.used(), 487800832);
+    assert_eq!(meminfo.buff_cache(), 59752448 + 1265594368);
+    assert_eq!(meminfo.available(), 5818146816);
+    assert_eq!(meminfo.swap_used(), 536870912);
+    assert_eq!(meminfo.pressure(), Some(MemoryPressure::Low));
+}
+
+#[test]
+fn matches_free_under_pressure() {
+    let meminfo = Meminfo::parse(PRESSURE);
+
+    assert_eq!(meminfo.used(), 16298389504);
+    assert_eq!(meminfo.buff_cache(), 2097152 + 830218240);
+    assert_eq!(meminfo.swap_used(), 7650406400);
+    assert_eq!(meminfo.pressure(), Some(MemoryPressure::Critical));
+
+    let commit = meminfo.commit_ratio().unwrap();
+    assert!((commit - 1.8882).abs() < 1e-4);
+
+    let swap = meminfo.swap_used_percent().unwrap();
+    assert!((swap - 89.0625).abs() < 1e-3);
+}
+
+#[test]
+fn falls_back_to_memfree_without_memavailable() {
+    let meminfo = Meminfo::parse(LEGACY);
+
+    assert_eq!(meminfo.mem_available, None);
+    assert_eq!(meminfo.available(), 260284416);
+    assert_eq!(meminfo.used(), 3714805760);
+    assert_eq!(meminfo.used_legacy(), 1113628672);
+    assert_eq!(meminfo.pressure(), Some(MemoryPressure::High));
+}
+
+#[test]
+fn htop_excludes_reclaimable_caches() {
+    let meminfo = Meminfo::parse(SERVER);
+
+    assert_eq!(meminfo.used_htop(), 253194240);
+    assert_eq!(meminfo.cached_htop(), 1256083456);
+}
+
+#[test]
+fn ratios_need_a_denominator() {
+    let meminfo = Meminfo::parse("MemTotal: 0 kB\nSwapTotal: 0 kB\n");
+
+    assert_eq!(meminfo.used_percent(), None);
+    assert_eq!(meminfo.swap_used_percent(), None);
+    assert_eq!(meminfo.commit_ratio(), None);
+    assert_eq!(meminfo.pressure(), None);
+}
+
+#[test]
+fn database_lint_skips_missing_and_overridden_tunables() {
+    let tunables = VmTunables {
+        swappiness: Some(60),
+        overcommit_memory: Some(0),
+        dirty_ratio: Some(20),
+        dirty_bytes: Some(256 * 1024 * 1024),
+        dirty_background_ratio: Some(10),
+        zone_reclaim_mode: Some(0),
+        ..Default::default()
+    };
+
+    let mut names: Vec<String> = tunables.lint_database()
+        .into_iter()
+        .map(|finding| finding.name)
+        .collect();
+    names.sort();
+
+    assert_eq!(names, [
+        "vm.dirty_background_ratio",
+        "vm.overcommit_memory",
+        "vm.swappiness",
+    ]);
+}
+
+#[test]
+fn database_lint_flags_zero_swappiness() {
+    let lint = |swappiness| VmTunables {
+        swappiness: Some(swappiness),
+        ..Default::default()
+    }.lint_database();
+
+    assert_eq!(lint(0).len(), 1);
+    assert_eq!(lint(0)[0].name, "vm.swappiness");
+    assert_eq!(lint(1), []);
+    assert_eq!(lint(10), []);
+    assert_eq!(lint(11).len(), 1);
+}
+
+#[test]
+fn keeps_raw_values_by_kernel_name() {
+    let meminfo = Meminfo::parse(
+        "MemTotal:       16384 kB\n\
+         HugePages_Total:     512\n\
+         Hugepagesize:     2048 kB\n\
+         TierDemoted:        96 kB\n\
+         MeminfoCount:        3\n\
+         Broken:\n"
+    );
+
+    // Unitless values are counts and are not scaled.
+    assert_eq!(meminfo.huge_pages_total, Some(512));
+    assert_eq!(meminfo.get("HugePages_Total"), Some(512));
+    assert_eq!(meminfo.get("Hugepagesize"), Some(2048 * 1024));
+
+    assert_eq!(meminfo.other.len(), 2);
+    assert_eq!(meminfo.get("TierDemoted"), Some(96 * 1024));
+    assert_eq!(meminfo.get("MeminfoCount"), Some(3));
+    assert_eq!(meminfo.get("Broken"), None);
+
+    let map = meminfo.to_map();
+    assert_eq!(map.keys().collect::<Vec<_>>(), [
+        "HugePages_Total", "Hugepagesize", "MemTotal", "MeminfoCount",
+        "TierDemoted",
+    ]);
+    assert_eq!(map["MemTotal"], 16384 * 1024);
+}
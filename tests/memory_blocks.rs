@@ -0,0 +1,34 @@
+use std::path::Path;
+
+use patagonicus::memory_blocks::{MemoryBlockState, MemoryBlocks};
+
+#[test]
+fn reads_memory_blocks() {
+    let memory = MemoryBlocks::read_from(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/memory")
+    );
+
+    assert_eq!(memory.block_size, Some(128 * 1024 * 1024));
+    assert_eq!(memory.auto_online_blocks.as_deref(), Some("online_movable"));
+
+    let indexes: Vec<u64> = memory.blocks.iter().map(|b| b.index).collect();
+    assert_eq!(indexes, [32, 33, 40]);
+
+    let first = &memory.blocks[0];
+    assert_eq!(first.start, 0x1_0000_0000);
+    assert_eq!(first.state, MemoryBlockState::Online);
+    assert_eq!(first.node, Some(1));
+    assert_eq!(first.removable, Some(true));
+    assert_eq!(first.zones, ["Normal"]);
+
+    // A block spanning two zones reports `none`.
+    assert!(memory.blocks[1].zones.is_empty());
+
+    let offline = &memory.blocks[2];
+    assert_eq!(offline.state, MemoryBlockState::Offline);
+    assert_eq!(offline.node, Some(2));
+    assert_eq!(offline.zones, ["Normal", "Movable"]);
+
+    assert_eq!(memory.online_bytes(), 2 * 128 * 1024 * 1024);
+    assert_eq!(memory.offline_bytes(), 128 * 1024 * 1024);
+}
@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use patagonicus::net::conntrack::{self, Conntrack};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/net/conntrack")
+}
+
+#[test]
+fn reads_table_usage() {
+    let dir = fixtures();
+    let conntrack = Conntrack::read_from(&dir.join("netfilter"), &dir);
+
+    assert_eq!(conntrack.count, Some(251872));
+    assert_eq!(conntrack.max, Some(262144));
+    assert_eq!(conntrack.buckets, Some(65536));
+    let usage = conntrack.usage().unwrap();
+    assert!((usage - 0.9608).abs() < 0.0001);
+
+    assert_eq!(conntrack.per_cpu.len(), 2);
+    let totals = conntrack.totals();
+    assert_eq!(totals.drop, 0x4d2 + 0x22b);
+    assert_eq!(totals.early_drop, 0x14);
+    assert_eq!(totals.insert_failed, 2);
+    assert_eq!(totals.clash_resolve, 0x25);
+    assert_eq!(totals.chain_length, 11);
+}
+
+#[test]
+fn parses_per_cpu_stats() {
+    let stats = conntrack::parse_conntrack_stat(
+        include_str!("fixtures/net/conntrack/stat/nf_conntrack")
+    );
+    assert_eq!(stats.len(), 2);
+
+    assert_eq!(stats[0].cpu, 0);
+    assert_eq!(stats[0].found, 0x1a2f4);
+    assert_eq!(stats[0].invalid, 0xb3e);
+    assert_eq!(stats[0].icmp_error, 1);
+    assert_eq!(stats[0].search_restart, 0x3a);
+    assert_eq!(stats[1].cpu, 1);
+    assert_eq!(stats[1].drop, 0x22b);
+}
+
+#[test]
+fn parses_older_column_sets() {
+    // Kernels before 5.x name more columns, some of them since removed.
+    let stats = conntrack::parse_conntrack_stat(
+        "entries  searched found new invalid ignore delete delete_list \
+         insert insert_failed drop early_drop icmp_error  expect_new \
+         expect_create expect_delete search_restart\n\
+         00000021  00000000 0000002a 00000000 00000003 00000000 00000000 \
+         00000000 00000000 00000000 00000005 00000000 00000000  00000000 \
+         00000000 00000000 00000000\n"
+    );
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].found, 42);
+    assert_eq!(stats[0].invalid, 3);
+    assert_eq!(stats[0].drop, 5);
+    assert_eq!(stats[0].clash_resolve, 0);
+}
+
+#[test]
+fn missing_module_reads_as_empty() {
+    let dir = fixtures().join("missing");
+    let conntrack = Conntrack::read_from(&dir, &dir);
+    assert_eq!(conntrack, Conntrack::default());
+    assert_eq!(conntrack.usage(), None);
+}
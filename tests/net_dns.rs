@@ -0,0 +1,145 @@
+use std::net::IpAddr;
+use std::path::Path;
+
+use patagonicus::net::dns::{
+    DnsConfig, HostSource, NsAction, NsActionKind, NsStatus, ResolvConf,
+    ResolvedMode,
+};
+
+fn read(name: &str) -> DnsConfig {
+    DnsConfig::read_from(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dns")
+            .join(name)
+    )
+}
+
+fn addr(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn parses_resolv_conf() {
+    let config = read("plain");
+    let conf = &config.resolv_conf;
+
+    assert_eq!(conf.nameservers.len(), 4);
+    assert_eq!(conf.effective_nameservers(), [
+        addr("192.0.2.1"), addr("2001:db8::53"), addr("192.0.2.2"),
+    ]);
+    // `search` came after `domain`, so it wins.
+    assert_eq!(conf.domain, None);
+    assert_eq!(conf.search, ["lab.example.net", "example.net"]);
+
+    assert_eq!(conf.options.ndots, 3);
+    assert_eq!(conf.options.timeout, 30);
+    assert_eq!(conf.options.attempts, 4);
+    assert!(conf.options.rotate);
+    assert!(!conf.options.edns0);
+    assert_eq!(conf.options.other, ["inet6"]);
+
+    assert_eq!(config.resolved_mode, ResolvedMode::NotManaged);
+    assert_eq!(config.resolved_upstream, None);
+}
+
+#[test]
+fn detects_resolved_stub() {
+    let config = read("stub");
+
+    assert_eq!(config.resolved_mode, ResolvedMode::Stub);
+    assert_eq!(config.resolv_conf.nameservers, [addr("127.0.0.53")]);
+    assert!(config.resolv_conf.options.trust_ad);
+    assert_eq!(config.resolv_conf.options.ndots, 1);
+
+    let upstream = config.resolved_upstream.as_ref().unwrap();
+    assert_eq!(upstream.nameservers[2], addr("fe80::1"));
+
+    // A copy of the stub file, rather than a link to it.
+    let copied = ResolvConf::parse("nameserver 127.0.0.53\n");
+    assert!(copied.is_resolved_stub());
+    assert!(!ResolvConf::parse("").is_resolved_stub());
+}
+
+#[test]
+fn parses_hosts() {
+    let hosts = read("stub").hosts;
+
+    assert_eq!(hosts.entries.len(), 5);
+    assert_eq!(hosts.lookup("localhost"), [addr("127.0.0.1"), addr("::1")]);
+    assert_eq!(hosts.lookup("ARTIFACTS"), [addr("10.20.4.17")]);
+    assert!(hosts.lookup("missing").is_empty());
+    assert_eq!(hosts.hostname_of(addr("127.0.1.1")),
+               Some("build01.corp.example.com"));
+    assert_eq!(hosts.entries[4].aliases, ["artifacts"]);
+}
+
+#[test]
+fn parses_nsswitch() {
+    let nsswitch = read("stub").nsswitch.unwrap();
+
+    let group = nsswitch.services("group").unwrap();
+    assert_eq!(group[0].actions[0].action, NsActionKind::Merge);
+
+    let names: Vec<&str> = nsswitch.services("hosts").unwrap().iter()
+        .map(|service| service.name.as_str())
+        .collect();
+    assert_eq!(names, ["mymachines", "resolve", "files", "myhostname", "dns"]);
+
+    let plain = read("plain").nsswitch.unwrap();
+    let mdns = &plain.services("hosts").unwrap()[1];
+    assert_eq!(mdns.actions, [
+        NsAction {
+            negated: false,
+            status: NsStatus::NotFound,
+            action: NsActionKind::Return,
+        },
+        NsAction {
+            negated: false,
+            status: NsStatus::TryAgain,
+            action: NsActionKind::Continue,
+        },
+    ]);
+}
+
+#[test]
+fn reports_resolution_order() {
+    let order = read("stub").resolution_order();
+    let upstream = vec![
+        addr("10.20.0.2"), addr("10.20.0.3"), addr("fe80::1"),
+    ];
+
+    assert_eq!(order[0].source, HostSource::Other("mymachines".into()));
+    assert_eq!(order[1].source, HostSource::Resolve {
+        upstream: upstream.clone(),
+    });
+    assert_eq!(order[1].actions, [NsAction {
+        negated: true,
+        status: NsStatus::Unavail,
+        action: NsActionKind::Return,
+    }]);
+    assert_eq!(order[2].source, HostSource::Files { entries: 5 });
+    assert_eq!(order[3].source, HostSource::Myhostname);
+    assert_eq!(order[4].source, HostSource::Dns {
+        nameservers: vec![addr("127.0.0.53")],
+        upstream,
+    });
+
+    let plain = read("plain").resolution_order();
+    assert_eq!(plain[1].source, HostSource::Mdns("mdns4_minimal".into()));
+    assert_eq!(plain[2].source, HostSource::Dns {
+        nameservers: vec![
+            addr("192.0.2.1"), addr("2001:db8::53"), addr("192.0.2.2"),
+        ],
+        upstream: Vec::new(),
+    });
+}
+
+#[test]
+fn defaults_without_nsswitch() {
+    let config = DnsConfig::default();
+    let order = config.resolution_order();
+
+    assert_eq!(order.len(), 2);
+    assert!(matches!(order[0].source, HostSource::Dns { .. }));
+    assert!(order[0].actions[0].negated);
+    assert_eq!(order[1].source, HostSource::Files { entries: 0 });
+}
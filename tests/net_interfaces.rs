@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use patagonicus::net::{self, Duplex, Interface, InterfaceKind, OperState};
+
+fn sys_class_net() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/net/interfaces/sys/class/net")
+}
+
+fn read(name: &str) -> Interface {
+    Interface::read_from(&sys_class_net(), name).unwrap()
+}
+
+#[test]
+fn lists_interfaces_by_index() {
+    let interfaces = net::read_interfaces(&sys_class_net());
+
+    let kinds: Vec<(&str, InterfaceKind)> = interfaces.iter()
+        .map(|interface| (interface.name.as_str(), interface.kind.clone()))
+        .collect();
+    assert_eq!(kinds, [
+        ("lo", InterfaceKind::Loopback),
+        ("eno1", InterfaceKind::Ether),
+        ("wlp2s0", InterfaceKind::Wireless),
+        ("bond0", InterfaceKind::Bond),
+        ("br0", InterfaceKind::Bridge),
+        ("veth0", InterfaceKind::Veth),
+        ("tap0", InterfaceKind::Tap),
+        ("enx001122334455", InterfaceKind::Ether),
+    ]);
+
+    assert!(net::read_interfaces(Path::new("/nonexistent")).is_empty());
+    assert!(Interface::read_from(&sys_class_net(), "eth9").is_err());
+}
+
+#[test]
+fn reads_a_physical_interface() {
+    let eno1 = read("eno1");
+
+    assert_eq!(eno1.ifindex, 2);
+    assert_eq!(eno1.iflink, Some(2));
+    assert_eq!(eno1.link_type, Some(1));
+    assert_eq!(eno1.mac.as_deref(), Some("3c:ec:ef:10:20:30"));
+    assert_eq!(eno1.mtu, Some(1500));
+    assert!(eno1.is_up());
+    assert_eq!(eno1.operstate, OperState::Up);
+    assert_eq!(eno1.carrier, Some(true));
+    assert_eq!(eno1.speed, Some(1000));
+    assert_eq!(eno1.duplex, Some(Duplex::Full));
+    assert_eq!(eno1.driver.as_deref(), Some("e1000e"));
+    assert_eq!(eno1.pci_slot.as_deref(), Some("0000:00:1f.6"));
+    assert_eq!(eno1.master.as_deref(), Some("bond0"));
+    assert_eq!(eno1.upper, ["bond0"]);
+    assert!(eno1.lower.is_empty());
+    assert!(!eno1.is_virtual);
+
+    // The PCI function is found through the USB devices in between.
+    let usb = read("enx001122334455");
+    assert_eq!(usb.driver.as_deref(), Some("cdc_ether"));
+    assert_eq!(usb.pci_slot.as_deref(), Some("0000:00:14.0"));
+    assert!(!usb.is_virtual);
+}
+
+#[test]
+fn reads_virtual_interfaces() {
+    let lo = read("lo");
+    assert_eq!(lo.link_type, Some(772));
+    assert_eq!(lo.operstate, OperState::Unknown);
+    assert_eq!(lo.driver, None);
+    assert_eq!(lo.pci_slot, None);
+    assert!(lo.is_virtual);
+
+    // A veth sends through its peer but has no lower interface.
+    let veth = read("veth0");
+    assert_eq!(veth.iflink, Some(7));
+    assert!(!veth.is_up());
+    assert_eq!(veth.operstate, OperState::Down);
+    assert_eq!(veth.carrier, None);
+    assert_eq!(veth.speed, None);
+
+    let tap = read("tap0");
+    assert_eq!(tap.kind, InterfaceKind::Tap);
+    assert_eq!(tap.carrier, Some(true));
+    assert!(tap.is_virtual);
+}
+
+#[test]
+fn reads_bonds_and_bridges() {
+    let bond = read("bond0");
+    assert_eq!(bond.lower, ["eno1"]);
+    assert_eq!(bond.upper, ["br0"]);
+    assert_eq!(bond.master.as_deref(), Some("br0"));
+    assert_eq!(bond.pci_slot, None);
+
+    let bridge = read("br0");
+    assert_eq!(bridge.lower, ["bond0"]);
+    assert!(bridge.upper.is_empty());
+    assert_eq!(bridge.master, None);
+}
+
+#[test]
+fn reads_a_wireless_interface() {
+    let wlan = read("wlp2s0");
+
+    assert_eq!(wlan.kind, InterfaceKind::Wireless);
+    assert_eq!(wlan.operstate, OperState::Dormant);
+    assert_eq!(wlan.carrier, Some(false));
+    assert_eq!(wlan.speed, None);
+    assert_eq!(wlan.duplex, None);
+    assert_eq!(wlan.driver.as_deref(), Some("iwlwifi"));
+    assert_eq!(wlan.pci_slot.as_deref(), Some("0000:02:00.0"));
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use std::fs;
+use std::net::TcpListener;
+use std::os::unix::fs::MetadataExt;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use patagonicus::net;
+use patagonicus::net::namespace::{self, NetNamespace};
+use patagonicus::net::sockets::{SocketProtocol, TcpState};
+
+use common::ip;
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/net/namespaces")
+}
+
+#[test]
+fn lists_namespaces_by_inode() {
+    let dir = fixtures();
+    let namespaces = namespace::read_namespaces(
+        &dir.join("proc"), &dir.join("run/netns")
+    );
+    assert_eq!(namespaces.len(), 3);
+    let by_inode = |inode: u64| namespaces.iter()
+        .find(|namespace| namespace.inode == inode)
+        .unwrap();
+
+    let host = by_inode(4026531840);
+    assert_eq!(host.pids, [1, 2]);
+    assert_eq!(host.path, dir.join("proc/1/ns/net"));
+    assert_eq!(host.name, None);
+    assert_eq!(by_inode(4026532411).pids, [300, 812]);
+
+    // Not a real namespace file, so it counts as a namespace of its own.
+    let blue = dir.join("run/netns/blue");
+    let named = by_inode(fs::metadata(&blue).unwrap().ino());
+    assert_eq!(named.name.as_deref(), Some("blue"));
+    assert_eq!(named.path, blue);
+    assert!(named.pids.is_empty());
+}
+
+#[test]
+fn finds_own_namespace() {
+    let pid = std::process::id();
+    let own = NetNamespace::of_pid(pid).unwrap();
+    assert_eq!(own.inode, fs::metadata("/proc/self/ns/net").unwrap().ino());
+
+    let listed = namespace::list_namespaces();
+    let found = listed.iter()
+        .find(|namespace| namespace.inode == own.inode)
+        .unwrap();
+    assert!(found.pids.contains(&pid));
+
+    assert!(NetNamespace::named("patagonicus-missing").is_err());
+}
+
+// Sets up a namespace on one thread and inspects it from another.
+#[test]
+#[ignore = "needs CAP_SYS_ADMIN and CAP_NET_ADMIN"]
+fn collects_from_another_namespace() {
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel::<()>();
+
+    let setup = thread::spawn(move || {
+        let unshared = unsafe { nix::libc::unshare(nix::libc::CLONE_NEWNET) };
+        assert_eq!(unshared, 0, "cannot create a network namespace");
+        assert!(ip("link set lo up"));
+        assert!(ip("link add tns0 type veth peer tns1"));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tid = unsafe { nix::libc::gettid() };
+        ready_tx.send((tid, listener.local_addr().unwrap())).unwrap();
+        done_rx.recv().unwrap();
+    });
+
+    // A failed setup drops the sender; pass its panic on.
+    let Ok((tid, local)) = ready_rx.recv() else {
+        panic::resume_unwind(setup.join().unwrap_err());
+    };
+
+    let target = NetNamespace::from_path(
+        format!("/proc/self/task/{}/ns/net", tid)
+    )
+    .unwrap();
+    let own = NetNamespace::of_pid(std::process::id()).unwrap();
+    assert_ne!(target.inode, own.inode);
+
+    let snapshot = target.collect();
+    // Netlink sockets opened inside belong to the namespace too.
+    let addresses = target.run(|_| net::route::get_addresses());
+    done_tx.send(()).unwrap();
+    setup.join().unwrap();
+    let snapshot = snapshot.unwrap();
+
+    let names: Vec<&str> = snapshot.interfaces.iter()
+        .map(|interface| interface.name.as_str())
+        .collect();
+    assert_eq!(names.len(), 3);
+    assert!(names.contains(&"tns0") && names.contains(&"tns1"));
+
+    // Entering the namespace the process is already in works too.
+    let host: Vec<String> = own.collect().unwrap().interfaces.into_iter()
+        .map(|interface| interface.name)
+        .collect();
+    let direct: Vec<String> = net::get_interfaces().into_iter()
+        .map(|interface| interface.name)
+        .collect();
+    assert_eq!(host, direct);
+    assert!(!host.iter().any(|name| name == "tns0"));
+
+    let socket = snapshot.inet_sockets.iter()
+        .find(|socket| socket.local == local)
+        .unwrap();
+    assert_eq!(socket.protocol, SocketProtocol::Tcp);
+    assert_eq!(socket.state, TcpState::Listen);
+    assert_eq!(snapshot.inet_sockets.len(), 1);
+
+    let addresses = addresses.unwrap().unwrap();
+    assert!(!addresses.is_empty());
+    assert!(addresses.iter()
+        .all(|address| address.interface.as_deref() == Some("lo")));
+}
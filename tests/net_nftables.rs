@@ -0,0 +1,226 @@
+#![cfg(feature = "nftables")]
+
+use std::env;
+use std::os::fd::AsRawFd;
+use std::process::Command;
+
+use nix::sys::socket::{
+    self, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol,
+    SockType,
+};
+use patagonicus::net::nftables::{self, Counter, Family, Verdict};
+
+/// Set when the test runs again inside its own namespaces.
+const IN_NAMESPACE: &str = "PATAGONICUS_NFTABLES_NAMESPACE";
+
+const UNSHARE: &[&str] = &["--user", "--map-root-user", "--net"];
+
+// From <linux/netlink.h>, <linux/netfilter/nfnetlink.h> and
+// <linux/netfilter/nf_tables.h>.
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_CREATE: u16 = 0x400;
+const NLM_F_APPEND: u16 = 0x800;
+const NLA_F_NESTED: u16 = 0x8000;
+const NLMSG_ERROR: u16 = 2;
+const NFNL_MSG_BATCH_BEGIN: u16 = 0x10;
+const NFNL_MSG_BATCH_END: u16 = 0x11;
+const NFNL_SUBSYS_NFTABLES: u16 = 10;
+const NFPROTO_INET: u8 = 1;
+
+/// Build netlink messages and attributes the way nft(8) sends them.
+struct Batch {
+    buf: Vec<u8>,
+    seq: u32,
+    acks: usize,
+}
+
+impl Batch {
+    fn new() -> Self {
+        let mut batch = Batch { buf: Vec::new(), seq: 0, acks: 0 };
+        let res_id = NFNL_SUBSYS_NFTABLES.to_be_bytes();
+        batch.message(NFNL_MSG_BATCH_BEGIN, 0, 0, &res_id, &[]);
+        batch
+    }
+
+    fn message(&mut self, msg_type: u16, flags: u16, family: u8,
+               res_id: &[u8], attrs: &[u8]) {
+        self.seq += 1;
+        if flags & NLM_F_ACK != 0 {
+            self.acks += 1;
+        }
+
+        let len = 16 + 4 + attrs.len();
+        self.buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        self.buf.extend_from_slice(&msg_type.to_ne_bytes());
+        self.buf.extend_from_slice(&(flags | NLM_F_REQUEST).to_ne_bytes());
+        self.buf.extend_from_slice(&self.seq.to_ne_bytes());
+        self.buf.extend_from_slice(&0u32.to_ne_bytes());
+        self.buf.extend_from_slice(&[family, 0]);
+        self.buf.extend_from_slice(res_id);
+        self.buf.extend_from_slice(attrs);
+    }
+
+    fn nftables(&mut self, msg_type: u16, flags: u16, attrs: &[u8]) {
+        self.message(
+            NFNL_SUBSYS_NFTABLES << 8 | msg_type,
+            flags | NLM_F_CREATE | NLM_F_ACK,
+            NFPROTO_INET,
+            &[0, 0],
+            attrs,
+        );
+    }
+
+    fn finish(mut self) -> (Vec<u8>, usize) {
+        self.message(NFNL_MSG_BATCH_END, 0, 0, &[0, 0], &[]);
+        (self.buf, self.acks)
+    }
+}
+
+fn attr(buf: &mut Vec<u8>, kind: u16, value: &[u8]) {
+    let len = 4 + value.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(buf.len() + (4 - len % 4) % 4, 0);
+}
+
+fn nested(buf: &mut Vec<u8>, kind: u16, build: impl FnOnce(&mut Vec<u8>)) {
+    let mut value = Vec::new();
+    build(&mut value);
+    attr(buf, kind | NLA_F_NESTED, &value);
+}
+
+fn counter_attrs(buf: &mut Vec<u8>, packets: u64, bytes: u64) {
+    attr(buf, 1, &bytes.to_be_bytes());
+    attr(buf, 2, &packets.to_be_bytes());
+}
+
+/// Create `table inet patagonicus` with a counter object `dropped` and
+/// a base chain `input` holding one counted, commented rule.
+fn create_ruleset() {
+    let mut batch = Batch::new();
+
+    let mut table = Vec::new();
+    attr(&mut table, 1, b"patagonicus\0");
+    batch.nftables(0, 0, &table);
+
+    let mut chain = Vec::new();
+    attr(&mut chain, 1, b"patagonicus\0");
+    attr(&mut chain, 3, b"input\0");
+    nested(&mut chain, 4, |hook| {
+        attr(hook, 1, &1u32.to_be_bytes());
+        attr(hook, 2, &(-10i32).to_be_bytes());
+    });
+    attr(&mut chain, 5, &0u32.to_be_bytes());
+    attr(&mut chain, 7, b"filter\0");
+    batch.nftables(3, 0, &chain);
+
+    let mut object = Vec::new();
+    attr(&mut object, 1, b"patagonicus\0");
+    attr(&mut object, 2, b"dropped\0");
+    attr(&mut object, 3, &1u32.to_be_bytes());
+    nested(&mut object, 4, |data| counter_attrs(data, 3, 180));
+    batch.nftables(18, 0, &object);
+
+    let mut rule = Vec::new();
+    attr(&mut rule, 1, b"patagonicus\0");
+    attr(&mut rule, 2, b"input\0");
+    nested(&mut rule, 4, |list| {
+        nested(list, 1, |expression| {
+            attr(expression, 1, b"counter\0");
+            nested(expression, 2, |data| counter_attrs(data, 42, 4200));
+        });
+    });
+    attr(&mut rule, 7, b"\x00\x04ssh\0");
+    batch.nftables(6, NLM_F_APPEND, &rule);
+
+    let (buf, mut acks) = batch.finish();
+
+    let fd = socket::socket(
+        AddressFamily::Netlink,
+        SockType::Raw,
+        SockFlag::SOCK_CLOEXEC,
+        SockProtocol::NetlinkNetFilter,
+    )
+    .unwrap();
+    socket::bind(fd.as_raw_fd(), &NetlinkAddr::new(0, 0)).unwrap();
+    socket::send(fd.as_raw_fd(), &buf, MsgFlags::empty()).unwrap();
+
+    let mut reply = vec![0u8; 8192];
+    while acks > 0 {
+        let received = socket::recv(
+            fd.as_raw_fd(), &mut reply, MsgFlags::empty()
+        )
+        .unwrap();
+        let mut data = &reply[..received];
+        while data.len() >= 16 {
+            let len = u32::from_ne_bytes(data[..4].try_into().unwrap());
+            let msg_type = u16::from_ne_bytes(data[4..6].try_into().unwrap());
+            if msg_type == NLMSG_ERROR {
+                let errno = i32::from_ne_bytes(
+                    data[16..20].try_into().unwrap()
+                );
+                assert_eq!(errno, 0, "nftables rejected the batch");
+                acks -= 1;
+            }
+            data = &data[(len as usize + 3) & !3..];
+        }
+    }
+}
+
+/// Run `test` again under unshare(1) in new user and network namespaces,
+/// where it may change the ruleset without privileges.
+fn run_in_namespace(test: &str) {
+    let usable = Command::new("unshare")
+        .args(UNSHARE)
+        .arg("true")
+        .status()
+        .is_ok_and(|status| status.success());
+    assert!(usable, "cannot create user namespaces");
+
+    let status = Command::new("unshare")
+        .args(UNSHARE)
+        .arg(env::current_exe().unwrap())
+        .args(["--exact", test, "--include-ignored"])
+        .args(["--test-threads=1", "--nocapture"])
+        .env(IN_NAMESPACE, "1")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+#[ignore = "needs unshare(1) and unprivileged user namespaces"]
+fn dumps_a_ruleset_in_a_user_namespace() {
+    if env::var_os(IN_NAMESPACE).is_none() {
+        run_in_namespace("dumps_a_ruleset_in_a_user_namespace");
+        return;
+    }
+
+    assert_eq!(nftables::get_ruleset().unwrap(), Default::default());
+    create_ruleset();
+    let ruleset = nftables::get_ruleset().unwrap();
+
+    assert_eq!(ruleset.tables.len(), 1);
+    let table = ruleset.table(Family::Inet, "patagonicus").unwrap();
+    assert_eq!(table.use_count, 2);
+
+    assert_eq!(ruleset.chains.len(), 1);
+    let chain = ruleset.chain(Family::Inet, "patagonicus", "input").unwrap();
+    assert!(chain.is_base_chain());
+    assert_eq!(chain.hook, Some(1));
+    assert_eq!(chain.priority, Some(-10));
+    assert_eq!(chain.chain_type.as_deref(), Some("filter"));
+    assert_eq!(chain.policy, Some(Verdict::Drop));
+
+    let rules: Vec<_> = ruleset.rules_of(chain).collect();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].expressions, ["counter"]);
+    assert_eq!(rules[0].counter, Some(Counter { packets: 42, bytes: 4200 }));
+    assert_eq!(rules[0].comment.as_deref(), Some("ssh"));
+
+    let dropped = ruleset.counter(Family::Inet, "patagonicus", "dropped")
+        .unwrap();
+    assert_eq!(dropped.counter, Counter { packets: 3, bytes: 180 });
+}
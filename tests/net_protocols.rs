@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use patagonicus::net::protocols::{ProtocolStats, SockStat};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/net")
+}
+
+#[test]
+fn reads_protocol_counters() {
+    let stats = ProtocolStats::read_from(&fixtures());
+
+    assert_eq!(stats.ip.in_receives, Some(18230455));
+    assert_eq!(stats.ip.reasm_reqds, Some(420));
+    assert_eq!(stats.ip.reasm_fails, Some(4));
+    assert_eq!(stats.ip.frag_creates, Some(288));
+    assert_eq!(stats.ip_ext.in_octets, Some(24011352033));
+
+    assert_eq!(stats.tcp.retrans_segs, Some(49600));
+    assert_eq!(stats.tcp.out_rsts, Some(6022));
+    assert_eq!(stats.tcp_curr_estab, Some(57));
+    assert_eq!(stats.tcp.retransmit_ratio(), Some(49600.0 / 19840112.0));
+
+    assert_eq!(stats.tcp_ext.listen_overflows, Some(118));
+    assert_eq!(stats.tcp_ext.listen_drops, Some(131));
+    assert_eq!(stats.tcp_ext.time_waited, Some(30321));
+    assert_eq!(stats.tcp_ext.syn_retrans, Some(402));
+
+    assert_eq!(stats.udp.rcvbuf_errors, Some(2215));
+    assert_eq!(stats.udp.ignored_multi, Some(5120));
+    assert_eq!(stats.icmp.in_dest_unreachs, Some(1210));
+
+    assert_eq!(stats.ip6.in_too_big_errors, Some(2));
+    assert_eq!(stats.ip6.reasm_oks, Some(6));
+    assert_eq!(stats.ip6.out_octets, Some(70214990));
+    // Missing from the fixture, as on kernels that predate a counter.
+    assert_eq!(stats.ip6.out_forw_datagrams, None);
+    assert_eq!(stats.icmp6.in_msgs, Some(412));
+    assert_eq!(stats.udp6.rcvbuf_errors, Some(88));
+}
+
+#[test]
+fn computes_deltas_and_rates() {
+    let bump = |counter: &mut Option<u64>, by: u64| {
+        *counter = counter.map(|value| value + by);
+    };
+
+    let earlier = ProtocolStats::read_from(&fixtures());
+    let mut later = earlier.clone();
+    bump(&mut later.tcp.retrans_segs, 300);
+    bump(&mut later.tcp.out_segs, 30_000);
+    bump(&mut later.tcp_ext.listen_overflows, 10);
+    later.tcp_curr_estab = Some(60);
+    bump(&mut later.udp.rcvbuf_errors, 4);
+    bump(&mut later.udp6.rcvbuf_errors, 6);
+    bump(&mut later.ip.reasm_fails, 2);
+    bump(&mut later.ip.frag_creates, 8);
+    bump(&mut later.ip6.frag_creates, 4);
+    // Counters reset when the namespace is recreated.
+    later.ip.in_receives = Some(5);
+
+    let delta = later.delta_since(&earlier);
+    assert_eq!(delta.tcp.retrans_segs, Some(300));
+    assert_eq!(delta.tcp.retransmit_ratio(), Some(0.01));
+    assert_eq!(delta.tcp_curr_estab, Some(60));
+    assert_eq!(delta.ip.in_receives, Some(0));
+    assert_eq!(delta.tcp_ext.listen_drops, Some(0));
+    assert_eq!(delta.ip6.out_forw_datagrams, None);
+
+    let rates = later.rates_since(&earlier, Duration::from_secs(2));
+    assert_eq!(rates.interval_secs, 2.0);
+    assert_eq!(rates.tcp_out_segs_per_sec, Some(15_000.0));
+    assert_eq!(rates.tcp_retransmits_per_sec, Some(150.0));
+    assert_eq!(rates.listen_overflows_per_sec, Some(5.0));
+    assert_eq!(rates.listen_drops_per_sec, Some(0.0));
+    assert_eq!(rates.udp_rcvbuf_errors_per_sec, Some(5.0));
+    assert_eq!(rates.ip_frag_creates_per_sec, Some(6.0));
+    // IPv6 lacks ReasmFails and FragFails in the fixture, so only IPv4
+    // counts.
+    assert_eq!(rates.ip_reasm_fails_per_sec, Some(1.0));
+    assert_eq!(rates.ip_frag_fails_per_sec, Some(0.0));
+
+    let mut old_kernel = earlier.clone();
+    old_kernel.tcp_ext.listen_drops = None;
+    let rates = later.rates_since(&old_kernel, Duration::from_secs(2));
+    assert_eq!(rates.listen_drops_per_sec, None);
+
+    let none = later.rates_since(&earlier, Duration::ZERO);
+    assert_eq!(none.tcp_retransmits_per_sec, None);
+}
+
+#[test]
+fn reads_sockstat() {
+    let sockstat = SockStat::read_from(&fixtures());
+
+    assert_eq!(sockstat.sockets_used, 1342);
+    assert_eq!(sockstat.tcp_inuse, 57);
+    assert_eq!(sockstat.tcp_orphan, 2);
+    assert_eq!(sockstat.tcp_time_wait, 211);
+    assert_eq!(sockstat.tcp_mem, 19);
+    assert_eq!(sockstat.udp_mem, 9);
+    assert_eq!(sockstat.frag_memory, 8192);
+    assert_eq!(sockstat.tcp6_inuse, 14);
+    assert_eq!(sockstat.raw6_inuse, 1);
+}
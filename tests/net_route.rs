@@ -0,0 +1,287 @@
+mod common;
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+use patagonicus::net::route::{
+    self, IpFamily, NextHop, RouteType, RuleAction, Scope,
+};
+
+use common::{in_netns, ip};
+
+/// A message payload, without its netlink header, captured from a
+/// little-endian host where `nl0` had index 3.
+fn payload(name: &str) -> Vec<u8> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/net/route");
+    fs::read(dir.join(name)).unwrap()
+}
+
+fn names() -> HashMap<u32, String> {
+    HashMap::from([(3, "nl0".to_string())])
+}
+
+fn addr(text: &str) -> IpAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn parses_addresses() {
+    let v4 = route::parse_address(&payload("addr_v4"), &names()).unwrap();
+    assert_eq!(v4.family, IpFamily::V4);
+    assert_eq!(v4.ifindex, 3);
+    assert_eq!(v4.interface.as_deref(), Some("nl0"));
+    assert_eq!(v4.address, addr("192.0.2.10"));
+    assert_eq!(v4.peer, None);
+    assert_eq!(v4.prefix_len, 24);
+    assert_eq!(v4.scope, Scope::Universe);
+    assert_eq!(v4.broadcast, Some(addr("192.0.2.255")));
+    assert_eq!(v4.label.as_deref(), Some("nl0:web"));
+    assert_eq!(v4.flags, ["permanent"]);
+    assert_eq!(v4.valid_lifetime, Some(u32::MAX));
+    assert_eq!(v4.preferred_lifetime, Some(u32::MAX));
+
+    let p2p = route::parse_address(&payload("addr_peer"), &names()).unwrap();
+    assert_eq!(p2p.address, addr("198.51.100.1"));
+    assert_eq!(p2p.peer, Some(addr("198.51.100.2")));
+    assert_eq!(p2p.prefix_len, 32);
+
+    // Without a link dump the interface has no name.
+    let v6 = route::parse_address(&payload("addr_v6"), &HashMap::new())
+        .unwrap();
+    assert_eq!(v6.family, IpFamily::V6);
+    assert_eq!(v6.interface, None);
+    assert_eq!(v6.address, addr("2001:db8:1::10"));
+    assert_eq!(v6.flags, ["nodad", "deprecated"]);
+    assert!(v6.is_deprecated());
+    assert_eq!(v6.valid_lifetime, Some(3600));
+    assert_eq!(v6.preferred_lifetime, Some(0));
+
+    assert!(route::parse_address(&payload("addr_v4")[..4], &names())
+        .is_none());
+}
+
+#[test]
+fn parses_routes_with_metrics() {
+    let route = route::parse_route(&payload("route_metrics"), &names())
+        .unwrap();
+
+    assert_eq!(route.family, IpFamily::V4);
+    assert_eq!(route.table, 100);
+    assert_eq!(route.route_type, RouteType::Unicast);
+    assert_eq!(route.destination, Some(addr("203.0.113.0")));
+    assert_eq!(route.dst_len, 24);
+    assert_eq!(route.gateway, Some(addr("192.0.2.1")));
+    assert_eq!(route.interface.as_deref(), Some("nl0"));
+    assert_eq!(route.priority, Some(50));
+    assert_eq!(route.protocol, "static");
+    assert_eq!(route.scope, Scope::Universe);
+    assert!(!route.is_default());
+
+    // The RTAX_LOCK bitmask for `mtu lock` is not a metric of its own.
+    assert_eq!(route.metrics, BTreeMap::from([
+        ("initcwnd".to_string(), 10),
+        ("mtu".to_string(), 1400),
+    ]));
+    assert_eq!(route.congestion_control.as_deref(), Some("cubic"));
+    assert!(route.nexthops.is_empty());
+}
+
+#[test]
+fn parses_multipath_routes() {
+    let route = route::parse_route(&payload("route_multipath"), &names())
+        .unwrap();
+
+    assert_eq!(route.destination, Some(addr("203.0.113.128")));
+    assert_eq!(route.dst_len, 25);
+    assert_eq!(route.gateway, None);
+    assert_eq!(route.ifindex, None);
+
+    let hop = |gateway: &str, weight| NextHop {
+        gateway: Some(addr(gateway)),
+        ifindex: 3,
+        interface: Some("nl0".to_string()),
+        weight,
+    };
+    assert_eq!(route.nexthops, [hop("192.0.2.1", 2), hop("192.0.2.2", 1)]);
+}
+
+#[test]
+fn parses_rules() {
+    let v4 = route::parse_rule(&payload("rule_v4")).unwrap();
+    assert_eq!(v4.family, IpFamily::V4);
+    assert_eq!(v4.priority, 1000);
+    assert_eq!(v4.action, RuleAction::Table);
+    assert_eq!(v4.table, 100);
+    assert!(!v4.invert);
+    assert_eq!(v4.source, Some(addr("192.0.2.0")));
+    assert_eq!(v4.src_len, 24);
+    assert_eq!(v4.destination, None);
+    assert_eq!(v4.fwmark, Some(0x10));
+    assert_eq!(v4.fwmask, Some(0xff));
+    // ip(8) sends -1 for "no suppress_prefixlength".
+    assert_eq!(v4.suppress_prefixlen, None);
+    assert_eq!(v4.protocol.as_deref(), Some("unspec"));
+
+    let v6 = route::parse_rule(&payload("rule_v6")).unwrap();
+    assert_eq!(v6.family, IpFamily::V6);
+    assert_eq!(v6.priority, 2000);
+    assert!(v6.invert);
+    assert_eq!(v6.destination, Some(addr("2001:db8::")));
+    assert_eq!(v6.dst_len, 32);
+    assert_eq!(v6.source, None);
+}
+
+// The tests below build their interfaces, addresses, routes and rules with
+// ip(8) inside a fresh network namespace, so they need CAP_SYS_ADMIN and
+// CAP_NET_ADMIN and only run with `cargo test -- --ignored`.
+
+/// Bring up loopback and a dummy interface `nl0`, falling back to a veth
+/// pair on kernels without the dummy driver.
+fn setup_links() {
+    assert!(ip("link set lo up"));
+    if !ip("link add nl0 type dummy") {
+        assert!(ip("link add nl0 type veth peer name nl1"));
+    }
+    assert!(ip("link set nl0 up"));
+    assert!(ip("addr add 192.0.2.10/24 dev nl0"));
+}
+
+#[test]
+#[ignore = "needs CAP_SYS_ADMIN and CAP_NET_ADMIN"]
+fn dumps_addresses() {
+    in_netns(|| {
+        setup_links();
+        assert!(ip("addr add 198.51.100.1 peer 198.51.100.2/32 dev nl0"));
+        assert!(ip("-6 addr add 2001:db8:1::10/64 dev nl0 nodad \
+                    preferred_lft 0 valid_lft 3600"));
+        // Duplicate address detection cannot start on a link that is down.
+        assert!(ip("link add nl2 type veth peer name nl3"));
+        assert!(ip("-6 addr add 2001:db8:2::10/64 dev nl2"));
+
+        let addresses = route::get_addresses().unwrap();
+        let find = |address: &str| {
+            addresses.iter()
+                .find(|a| a.address == addr(address))
+                .unwrap_or_else(|| panic!("{} not found", address))
+        };
+
+        let loopback = find("127.0.0.1");
+        assert_eq!(loopback.interface.as_deref(), Some("lo"));
+        assert_eq!(loopback.prefix_len, 8);
+        assert_eq!(loopback.scope, Scope::Host);
+        assert!(find("::1").has_flag("permanent"));
+
+        let v4 = find("192.0.2.10");
+        assert_eq!(v4.family, IpFamily::V4);
+        assert_eq!(v4.interface.as_deref(), Some("nl0"));
+        assert_eq!(v4.prefix_len, 24);
+        assert_eq!(v4.scope, Scope::Universe);
+        assert_eq!(v4.peer, None);
+
+        assert_eq!(find("198.51.100.1").peer, Some(addr("198.51.100.2")));
+
+        let deprecated = find("2001:db8:1::10");
+        assert_eq!(deprecated.family, IpFamily::V6);
+        assert!(deprecated.is_deprecated());
+        assert!(deprecated.has_flag("nodad"));
+        assert_eq!(deprecated.valid_lifetime, Some(3600));
+        assert_eq!(deprecated.preferred_lifetime, Some(0));
+
+        let tentative = find("2001:db8:2::10");
+        assert!(tentative.is_tentative());
+        assert!(!tentative.is_deprecated());
+        assert_eq!(tentative.interface.as_deref(), Some("nl2"));
+    });
+}
+
+#[test]
+#[ignore = "needs CAP_SYS_ADMIN and CAP_NET_ADMIN"]
+fn dumps_routes_of_all_tables() {
+    in_netns(|| {
+        setup_links();
+        assert!(ip("route add 203.0.113.0/24 via 192.0.2.1 dev nl0 \
+                    table 100 metric 50 mtu 1400 initcwnd 10 proto static"));
+        assert!(ip("route add 203.0.113.128/25 table 100 \
+                    nexthop via 192.0.2.1 weight 2 nexthop via 192.0.2.2"));
+
+        let routes = route::get_routes().unwrap();
+
+        let connected = routes.iter()
+            .find(|r| {
+                r.table == 254 && r.destination == Some(addr("192.0.2.0"))
+            })
+            .unwrap();
+        assert_eq!(connected.dst_len, 24);
+        assert_eq!(connected.protocol, "kernel");
+        assert_eq!(connected.scope, Scope::Link);
+        assert_eq!(connected.prefsrc, Some(addr("192.0.2.10")));
+        assert_eq!(connected.interface.as_deref(), Some("nl0"));
+
+        assert!(routes.iter().any(|r| {
+            r.table == 255 && r.destination == Some(addr("127.0.0.1"))
+        }));
+
+        let custom = routes.iter()
+            .find(|r| r.destination == Some(addr("203.0.113.0")))
+            .unwrap();
+        assert_eq!(custom.table, 100);
+        assert_eq!(route::table_name(custom.table), "100");
+        assert_eq!(custom.gateway, Some(addr("192.0.2.1")));
+        assert_eq!(custom.priority, Some(50));
+        assert_eq!(custom.protocol, "static");
+        assert_eq!(custom.metrics.get("mtu"), Some(&1400));
+        assert_eq!(custom.metrics.get("initcwnd"), Some(&10));
+        assert!(!custom.is_default());
+
+        let multipath = routes.iter()
+            .find(|r| r.destination == Some(addr("203.0.113.128")))
+            .unwrap();
+        let hops: Vec<(Option<IpAddr>, u16)> = multipath.nexthops.iter()
+            .map(|hop| (hop.gateway, hop.weight))
+            .collect();
+        assert_eq!(hops, [
+            (Some(addr("192.0.2.1")), 2),
+            (Some(addr("192.0.2.2")), 1),
+        ]);
+        assert_eq!(multipath.nexthops[0].interface.as_deref(), Some("nl0"));
+    });
+}
+
+#[test]
+#[ignore = "needs CAP_SYS_ADMIN and CAP_NET_ADMIN"]
+fn dumps_policy_rules() {
+    in_netns(|| {
+        assert!(ip("rule add from 192.0.2.0/24 fwmark 0x10/0xff \
+                    table 100 priority 1000"));
+        assert!(ip("-6 rule add not to 2001:db8::/32 lookup 100 pref 2000"));
+
+        let rules = route::get_rules().unwrap();
+
+        let v4: Vec<u32> = rules.iter()
+            .filter(|rule| rule.family == IpFamily::V4)
+            .map(|rule| rule.priority)
+            .collect();
+        assert_eq!(v4, [0, 1000, 32766, 32767]);
+
+        let marked = rules.iter().find(|rule| rule.priority == 1000).unwrap();
+        assert_eq!(marked.action, RuleAction::Table);
+        assert_eq!(marked.table, 100);
+        assert_eq!(marked.source, Some(addr("192.0.2.0")));
+        assert_eq!(marked.src_len, 24);
+        assert_eq!(marked.fwmark, Some(0x10));
+        assert_eq!(marked.fwmask, Some(0xff));
+        assert!(!marked.invert);
+
+        let inverted = rules.iter().find(|rule| rule.priority == 2000).unwrap();
+        assert_eq!(inverted.family, IpFamily::V6);
+        assert!(inverted.invert);
+        assert_eq!(inverted.destination, Some(addr("2001:db8::")));
+        assert_eq!(inverted.dst_len, 32);
+
+        let main = rules.iter().find(|rule| rule.priority == 32766).unwrap();
+        assert_eq!(route::table_name(main.table), "main");
+    });
+}
@@ -0,0 +1,131 @@
+use std::io::Write;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+use patagonicus::net::sockets::{
+    self, SocketOwners, SocketProtocol, TcpState, UnixSocketState,
+    UnixSocketType,
+};
+
+// The /proc/net fixtures print addresses in little-endian host order.
+
+fn addr(text: &str) -> SocketAddr {
+    text.parse().unwrap()
+}
+
+#[test]
+fn parses_tcp_tables() {
+    let tcp = sockets::parse_inet_sockets(
+        include_str!("fixtures/net/tcp"), SocketProtocol::Tcp
+    );
+    assert_eq!(tcp.len(), 3);
+
+    let listener = &tcp[0];
+    assert_eq!(listener.local, addr("127.0.0.1:8080"));
+    assert_eq!(listener.remote, addr("0.0.0.0:0"));
+    assert_eq!(listener.state, TcpState::Listen);
+    assert_eq!(listener.rx_queue, 3);
+    assert_eq!(listener.inode, 21044);
+    assert_eq!(listener.drops, None);
+
+    let ssh = &tcp[1];
+    assert_eq!(ssh.local, addr("192.168.2.2:22"));
+    assert_eq!(ssh.remote, addr("192.168.2.100:54321"));
+    assert_eq!(ssh.state, TcpState::Established);
+    assert_eq!(ssh.tx_queue, 0x124);
+    assert_eq!(ssh.retransmits, 2);
+    assert_eq!(ssh.uid, 1000);
+
+    assert_eq!(tcp[2].state, TcpState::TimeWait);
+    assert_eq!(tcp[2].remote, addr("34.216.184.93:443"));
+
+    let tcp6 = sockets::parse_inet_sockets(
+        include_str!("fixtures/net/tcp6"), SocketProtocol::Tcp6
+    );
+    assert_eq!(tcp6[0].local, addr("[::1]:631"));
+    assert_eq!(tcp6[1].local, addr("[2001:db8::1]:443"));
+    assert_eq!(tcp6[1].remote, addr("[2001:db8::2]:57554"));
+    assert_eq!(tcp6[1].rx_queue, 0x200);
+    assert_eq!(tcp6[1].uid, 33);
+}
+
+#[test]
+fn parses_udp_drops() {
+    let udp = sockets::parse_inet_sockets(
+        include_str!("fixtures/net/udp"), SocketProtocol::Udp
+    );
+
+    assert_eq!(udp[0].local, addr("127.0.0.53:53"));
+    assert_eq!(udp[0].state, TcpState::Close);
+    assert_eq!(udp[0].drops, Some(0));
+    assert_eq!(udp[1].state, TcpState::Established);
+    assert_eq!(udp[1].rx_queue, 0xa00);
+    assert_eq!(udp[1].drops, Some(17));
+}
+
+#[test]
+fn parses_unix_sockets() {
+    let unix = sockets::parse_unix_sockets(include_str!("fixtures/net/unix"));
+    assert_eq!(unix.len(), 4);
+
+    let journal = &unix[0];
+    assert_eq!(journal.path.as_deref(), Some("/run/systemd/journal/stdout"));
+    assert!(journal.listening);
+    assert!(!journal.is_abstract);
+    assert_eq!(journal.socket_type, UnixSocketType::Stream);
+    assert_eq!(journal.inode, 16930);
+
+    assert!(unix[1].is_abstract);
+    assert_eq!(unix[1].socket_type, UnixSocketType::SeqPacket);
+
+    let unnamed = &unix[2];
+    assert_eq!(unnamed.path, None);
+    assert_eq!(unnamed.state, UnixSocketState::Connected);
+    assert_eq!(unnamed.ref_count, 3);
+
+    assert_eq!(unix[3].path.as_deref(), Some("/run/user/1000/my socket"));
+    assert_eq!(unix[3].socket_type, UnixSocketType::Dgram);
+}
+
+#[test]
+fn finds_socket_owners() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let local = listener.local_addr().unwrap();
+
+    let socket = sockets::get_inet_sockets(SocketProtocol::Tcp)
+        .into_iter()
+        .find(|socket| socket.local == local)
+        .unwrap();
+    assert_eq!(socket.state, TcpState::Listen);
+
+    let owners = SocketOwners::scan();
+    assert!(owners.get(socket.inode).iter()
+        .any(|owner| owner.pid == std::process::id()));
+}
+
+#[test]
+fn sock_diag_reports_tcp_info() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut client = TcpStream::connect(listener.local_addr().unwrap())
+        .unwrap();
+    let (_server, _) = listener.accept().unwrap();
+    client.write_all(b"ping").unwrap();
+
+    let diag = sockets::diag_inet_sockets(SocketProtocol::Tcp).unwrap();
+    let proc = sockets::get_inet_sockets(SocketProtocol::Tcp);
+
+    let local = client.local_addr().unwrap();
+    let socket = diag.iter().find(|socket| socket.local == local).unwrap();
+    assert_eq!(socket.remote, listener.local_addr().unwrap());
+    assert_eq!(socket.state, TcpState::Established);
+
+    let from_proc = proc.iter().find(|socket| socket.local == local).unwrap();
+    assert_eq!(socket.inode, from_proc.inode);
+    assert_eq!(socket.uid, from_proc.uid);
+
+    let info = socket.tcp_info.as_ref().unwrap();
+    assert!(info.snd_cwnd > 0);
+    assert!(info.snd_mss > 0);
+    assert_eq!(info.total_retrans, 0);
+
+    assert!(sockets::diag_inet_sockets(SocketProtocol::Raw).is_err());
+}
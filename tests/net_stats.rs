@@ -0,0 +1,78 @@
+use std::path::Path;
+use std::time::Duration;
+
+use patagonicus::net::stats::{
+    InterfaceStats, parse_proc_net_dev, rates_between,
+};
+
+const DEV: &str = include_str!("fixtures/net/dev");
+
+#[test]
+fn parses_proc_net_dev() {
+    let stats = parse_proc_net_dev(DEV);
+
+    assert_eq!(stats.keys().collect::<Vec<_>>(), ["br0", "eno1", "lo"]);
+
+    let eno1 = &stats["eno1"];
+    assert_eq!(eno1.rx_bytes, 1934023112847);
+    assert_eq!(eno1.rx_errors, 12);
+    assert_eq!(eno1.rx_dropped, 381);
+    assert_eq!(eno1.rx_frame_errors, 9);
+    assert_eq!(eno1.multicast, 4410251);
+    assert_eq!(eno1.tx_packets, 901223450);
+    assert_eq!(eno1.tx_carrier_errors, 3);
+}
+
+#[test]
+fn reads_sysfs_statistics() {
+    let sys_class_net = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/net/interfaces/sys/class/net");
+
+    let eno1 = InterfaceStats::from_sysfs_in(&sys_class_net, "eno1").unwrap();
+    assert_eq!(eno1.rx_bytes, 1934023112847);
+    assert_eq!(eno1.rx_dropped, 4);
+    // Unlike `/proc/net/dev`, sysfs keeps missed and CRC errors apart.
+    assert_eq!(eno1.rx_missed_errors, 377);
+    assert_eq!(eno1.rx_crc_errors, 5);
+    assert_eq!(eno1.rx_frame_errors, 0);
+    assert_eq!(eno1.tx_carrier_errors, 3);
+
+    // Interfaces without a statistics directory are not found.
+    assert!(InterfaceStats::from_sysfs_in(&sys_class_net, "lo").is_err());
+    assert!(InterfaceStats::from_sysfs_in(&sys_class_net, "eth9").is_err());
+}
+
+#[test]
+fn computes_bitrates() {
+    let earlier = parse_proc_net_dev(DEV);
+    let mut later = earlier.clone();
+    let eno1 = later.get_mut("eno1").unwrap();
+    eno1.rx_bytes += 250_000_000;
+    eno1.tx_bytes += 1_250_000;
+    eno1.rx_packets += 20_000;
+    // A recreated interface starts counting from zero again.
+    later.get_mut("br0").unwrap().rx_bytes = 0;
+    later.remove("lo");
+
+    let rates = rates_between(&earlier, &later, Duration::from_secs(2));
+
+    let eno1 = &rates["eno1"];
+    assert_eq!(eno1.rx_bits_per_sec, 1_000_000_000);
+    assert_eq!(eno1.tx_bits_per_sec, 5_000_000);
+    assert_eq!(eno1.rx_packets_per_sec, 10_000.0);
+    assert_eq!(eno1.rx_bitrate(), "1.00 Gbps");
+    assert_eq!(eno1.tx_bitrate(), "5.00 Mbps");
+
+    assert_eq!(rates["br0"].rx_bits_per_sec, 0);
+    assert!(!rates.contains_key("lo"));
+}
+
+#[test]
+fn zero_interval_has_no_rates() {
+    let stats = parse_proc_net_dev(DEV);
+    let rates = rates_between(&stats, &stats, Duration::ZERO);
+
+    assert_eq!(rates["eno1"].interval_secs, 0.0);
+    assert_eq!(rates["eno1"].rx_bits_per_sec, 0);
+    assert_eq!(rates.len(), 3);
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use patagonicus::net::Duplex;
+use patagonicus::net::InterfaceKind;
+use patagonicus::net::topology::{
+    self, BondMode, PortState, Relation, StpMode, Topology,
+};
+
+use common::{ip, unshare_with_sysfs};
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/net/topology")
+}
+
+fn read_fixtures() -> Topology {
+    let dir = fixtures();
+    Topology::read_from(&dir.join("proc"), &dir.join("sys"))
+}
+
+#[test]
+fn parses_bonds() {
+    let topology = read_fixtures();
+    assert_eq!(topology.bonds.len(), 2);
+
+    let lacp = topology.bond("bond0").unwrap();
+    assert_eq!(lacp.mode, BondMode::Lacp);
+    assert_eq!(lacp.transmit_hash_policy.as_deref(), Some("layer3+4"));
+    assert_eq!(lacp.lacp_rate.as_deref(), Some("fast"));
+    assert_eq!(lacp.active_slave, None);
+    assert_eq!(lacp.up_delay_ms, Some(200));
+    assert!(lacp.mii_up);
+    assert_eq!(lacp.slaves.len(), 2);
+    assert_eq!(lacp.slaves[1].name, "ens1f1");
+    assert_eq!(lacp.slaves[1].speed, Some(25000));
+    assert_eq!(lacp.slaves[1].duplex, Some(Duplex::Full));
+    assert_eq!(lacp.slaves[1].link_failure_count, 4);
+    assert_eq!(lacp.slaves[1].aggregator_id, Some(1));
+    assert_eq!(lacp.slaves[1].permanent_mac.as_deref(),
+               Some("3c:fd:fe:a1:22:11"));
+
+    let backup = topology.bond("bond1").unwrap();
+    assert_eq!(backup.mode, BondMode::ActiveBackup);
+    assert_eq!(backup.primary_slave.as_deref(), Some("eno1"));
+    assert_eq!(backup.active_slave.as_deref(), Some("eno2"));
+    let failed: Vec<&str> = backup.failed_slaves().iter()
+        .map(|slave| slave.name.as_str())
+        .collect();
+    assert_eq!(failed, ["eno1"]);
+    assert_eq!(backup.slaves[0].speed, None);
+    assert_eq!(backup.slaves[0].link_failure_count, 7);
+}
+
+#[test]
+fn parses_bridges_and_vlans() {
+    let topology = read_fixtures();
+
+    assert_eq!(topology.bridges.len(), 1);
+    let bridge = topology.bridge("br100").unwrap();
+    assert_eq!(bridge.stp, StpMode::Kernel);
+    assert!(!bridge.is_root());
+    assert_eq!(bridge.forward_delay, Some(15.0));
+    assert_eq!(bridge.ageing_time, Some(300.0));
+    assert_eq!(bridge.vlan_filtering, Some(false));
+
+    let names: Vec<&str> = bridge.ports.iter()
+        .map(|port| port.name.as_str())
+        .collect();
+    assert_eq!(names, ["bond0.100", "vnet3"]);
+    let vnet = bridge.port("vnet3").unwrap();
+    assert_eq!(vnet.port_no, Some(2));
+    assert_eq!(vnet.state, PortState::Blocking);
+    assert_eq!(vnet.path_cost, Some(100));
+    assert!(vnet.hairpin_mode);
+
+    assert_eq!(topology.vlans.len(), 3);
+    let vlan = topology.vlan("eno2.42").unwrap();
+    assert_eq!(vlan.id, 42);
+    assert_eq!(vlan.parent, "eno2");
+}
+
+#[test]
+fn walks_the_graph() {
+    let topology = read_fixtures();
+
+    let lower = topology.lower("br100");
+    assert_eq!(lower.len(), 2);
+    assert!(lower.iter().all(|edge| edge.relation == Relation::BridgePort));
+
+    let upper: Vec<(String, Relation)> = topology.upper("bond0").into_iter()
+        .map(|edge| (edge.upper, edge.relation))
+        .collect();
+    assert_eq!(upper, [
+        ("bond0.100".to_string(), Relation::VlanParent),
+        ("bond0.200".to_string(), Relation::VlanParent),
+    ]);
+
+    let bottom: Vec<String> = topology.bottom("br100").into_iter().collect();
+    assert_eq!(bottom, ["ens1f0", "ens1f1", "vnet3"]);
+    assert_eq!(topology.bottom("eno2").len(), 1);
+}
+
+#[test]
+fn attaches_the_graph_to_interfaces() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/net/interfaces");
+    let nodes = topology::read_interface_graph(
+        &dir.join("proc"), &dir.join("sys/class/net")
+    );
+    let node = |name: &str| nodes.iter()
+        .find(|node| node.interface.name == name)
+        .unwrap();
+
+    assert_eq!(nodes.len(), 8);
+    assert_eq!(nodes[0].interface.name, "lo");
+
+    let eno1 = node("eno1");
+    assert_eq!(eno1.interface.kind, InterfaceKind::Ether);
+    assert!(eno1.bond.is_none() && eno1.lower.is_empty());
+    assert_eq!(eno1.upper.len(), 1);
+    assert_eq!(eno1.upper[0].upper, "bond0");
+    assert_eq!(eno1.upper[0].relation, Relation::BondSlave);
+
+    let bond0 = node("bond0");
+    let bond = bond0.bond.as_ref().unwrap();
+    assert_eq!(bond.mode, BondMode::ActiveBackup);
+    assert_eq!(bond.active_slave.as_deref(), Some("eno1"));
+    assert_eq!(bond0.lower[0].lower, "eno1");
+    assert_eq!(bond0.upper[0].upper, "br0");
+    assert_eq!(bond0.upper[0].relation, Relation::BridgePort);
+
+    let br0 = node("br0");
+    let bridge = br0.bridge.as_ref().unwrap();
+    assert_eq!(bridge.stp, StpMode::Disabled);
+    assert_eq!(bridge.port("bond0").unwrap().state, PortState::Forwarding);
+    assert!(br0.bond.is_none() && br0.vlan.is_none());
+    assert!(br0.upper.is_empty());
+
+    let veth = node("veth0");
+    assert!(veth.lower.is_empty() && veth.upper.is_empty());
+}
+
+#[test]
+#[ignore = "needs CAP_SYS_ADMIN, CAP_NET_ADMIN and the bridge driver"]
+fn reads_a_live_bridge() {
+    thread::spawn(|| {
+        assert!(unshare_with_sysfs(), "cannot create namespaces");
+        assert!(ip("link add tbr0 type bridge stp_state 1 forward_delay 400"));
+        assert!(ip("link add tp0 type veth peer name tp1"));
+        assert!(ip("link set tp0 master tbr0"));
+
+        let topology = Topology::read_from(
+            Path::new("/proc/thread-self/net"), Path::new("/sys/class/net")
+        );
+        assert!(topology.bonds.is_empty());
+        assert_eq!(topology.bridges.len(), 1);
+
+        let bridge = &topology.bridges[0];
+        assert_eq!(bridge.name, "tbr0");
+        assert_eq!(bridge.stp, StpMode::Kernel);
+        assert_eq!(bridge.forward_delay, Some(4.0));
+        assert!(bridge.is_root());
+        assert_eq!(bridge.ports.len(), 1);
+        assert_eq!(bridge.ports[0].name, "tp0");
+        assert_eq!(bridge.ports[0].port_no, Some(1));
+        assert_eq!(topology.bottom("tbr0").into_iter().collect::<Vec<_>>(),
+                   ["tp0"]);
+    })
+    .join()
+    .unwrap()
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use patagonicus::net::{self, InterfaceKind};
+use patagonicus::net::wireless::{self, WirelessMode};
+
+use common::{run, unshare_with_sysfs};
+
+#[test]
+fn parses_proc_net_wireless() {
+    let stats = wireless::parse_proc_net_wireless(
+        include_str!("fixtures/net/wireless")
+    );
+    assert_eq!(stats.len(), 2);
+
+    let laptop = &stats[0];
+    assert_eq!(laptop.interface, "wlp2s0");
+    assert_eq!(laptop.link_quality, 54);
+    assert_eq!(laptop.signal_level, -56);
+    assert_eq!(laptop.noise_level, None);
+    assert_eq!(laptop.discarded_retry, 17);
+    assert_eq!(laptop.discarded_misc, 412);
+
+    assert_eq!(stats[1].noise_level, Some(-92));
+    assert_eq!(stats[1].discarded_nwid, 3);
+    assert_eq!(stats[1].missed_beacons, 5);
+}
+
+#[test]
+fn maps_frequencies_to_channels() {
+    assert_eq!(wireless::frequency_to_channel(2412), Some(1));
+    assert_eq!(wireless::frequency_to_channel(2484), Some(14));
+    assert_eq!(wireless::frequency_to_channel(5180), Some(36));
+    assert_eq!(wireless::frequency_to_channel(5825), Some(165));
+    assert_eq!(wireless::frequency_to_channel(5955), Some(1));
+    assert_eq!(wireless::frequency_to_channel(5935), Some(2));
+    assert_eq!(wireless::frequency_to_channel(60480), Some(2));
+    assert_eq!(wireless::frequency_to_channel(900), None);
+}
+
+/// Radios registered by mac80211_hwsim, e.g. `phy3`.
+fn hwsim_radios() -> Vec<String> {
+    let mut radios: Vec<String> = match fs::read_dir("/sys/class/ieee80211") {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| {
+                fs::read_link(entry.path().join("device/driver"))
+                    .is_ok_and(|driver| driver.ends_with("mac80211_hwsim"))
+            })
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    radios.sort();
+    radios
+}
+
+// Joins two mac80211_hwsim radios into an IBSS inside a private network
+// namespace. The radios return to the initial namespace when the test's
+// namespace goes away.
+#[test]
+#[ignore = "needs root, iw(8) and `modprobe mac80211_hwsim radios=2`"]
+fn queries_hwsim_radios() {
+    let radios = hwsim_radios();
+    assert!(radios.len() >= 2, "needs two mac80211_hwsim radios");
+    assert!(run("iw", "--version"), "needs iw");
+
+    thread::spawn(move || {
+        assert!(unshare_with_sysfs(), "cannot create namespaces");
+        // The kernel looks the thread up by its id like a process.
+        let tid = unsafe { nix::libc::gettid() };
+        for radio in &radios[..2] {
+            assert!(run("iw", &format!("phy {} set netns {}", radio, tid)));
+        }
+
+        let interfaces: Vec<net::Interface> = net::get_interfaces()
+            .into_iter()
+            .filter(|interface| interface.kind == InterfaceKind::Wireless)
+            .collect();
+        assert_eq!(interfaces.len(), 2);
+
+        for interface in &interfaces {
+            let link = wireless::get_wireless_link(&interface.name).unwrap();
+            assert_eq!(link.interface, interface.name);
+            assert_eq!(link.mode, Some(WirelessMode::Station));
+            assert_eq!(link.mac, interface.mac);
+            assert!(link.wiphy.is_some());
+            assert_eq!(link.ssid, None);
+
+            let name = &interface.name;
+            assert!(run("iw", &format!("dev {} set type ibss", name)));
+            assert!(run("ip", &format!("link set {} up", name)));
+            assert!(run("iw", &format!(
+                "dev {} ibss join patagonicus 2437 fixed-freq \
+                 02:00:00:00:0a:00", name
+            )));
+        }
+
+        let links = wireless::get_wireless_links().unwrap();
+        assert_eq!(links.len(), 2);
+        for link in &links {
+            assert_eq!(link.mode, Some(WirelessMode::Adhoc));
+            assert_eq!(link.ssid.as_deref(), Some("patagonicus"));
+            assert_eq!(link.frequency, Some(2437));
+            assert_eq!(link.channel, Some(6));
+        }
+
+        // Each radio hears the other's beacons after a moment.
+        let start = Instant::now();
+        let peer = loop {
+            let link = wireless::get_wireless_link(&interfaces[0].name)
+                .unwrap();
+            if let Some(station) = link.stations.first() {
+                break station.clone();
+            }
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(200));
+        };
+        assert_eq!(Some(&peer.mac), interfaces[1].mac.as_ref());
+        assert!(peer.signal_dbm.is_some());
+    })
+    .join()
+    .unwrap()
+}
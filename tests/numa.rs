@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use patagonicus::numa::{parse_node_meminfo, NumaTopology};
+
+fn topology() -> NumaTopology {
+    NumaTopology::read_from(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/numa")
+    )
+}
+
+#[test]
+fn reads_nodes_and_cpus() {
+    let topology = topology();
+
+    assert_eq!(topology.possible, [0, 1, 2, 3]);
+    assert_eq!(topology.online, [0, 1, 2]);
+    assert_eq!(topology.nodes.len(), 3);
+
+    let node1 = topology.node(1).unwrap();
+    assert_eq!(node1.cpus.len(), 16);
+    assert_eq!(node1.cpus[8], 24);
+    assert_eq!(topology.node_of_cpu(17), Some(0));
+    assert_eq!(topology.node_of_cpu(31), Some(1));
+    assert_eq!(topology.node_of_cpu(32), None);
+}
+
+#[test]
+fn finds_memory_only_nodes() {
+    let topology = topology();
+    let memory_only: Vec<u32> = topology.memory_only_nodes()
+        .iter()
+        .map(|node| node.id)
+        .collect();
+
+    assert_eq!(memory_only, [2]);
+
+    let cxl = topology.node(2).unwrap();
+    assert!(cxl.cpus.is_empty());
+    assert!(!cxl.has_normal_memory);
+    assert_eq!(cxl.meminfo.mem_total, Some(134217728 * 1024));
+}
+
+#[test]
+fn builds_distance_matrix() {
+    let topology = topology();
+
+    assert_eq!(topology.distance(0, 2), Some(14));
+    assert_eq!(topology.distance(2, 1), Some(24));
+    assert_eq!(topology.distance(0, 3), None);
+    assert_eq!(topology.distance_matrix(), [
+        [Some(10), Some(21), Some(14)],
+        [Some(21), Some(10), Some(24)],
+        [Some(14), Some(24), Some(10)],
+    ]);
+    assert_eq!(topology.nearest_nodes(0), [2, 1]);
+    assert_eq!(topology.nearest_nodes(1), [0, 2]);
+}
+
+#[test]
+fn reads_numastat() {
+    let topology = topology();
+    let node0 = topology.node(0).unwrap();
+
+    assert_eq!(node0.numastat.numa_hit, 982341563);
+    assert_eq!(node0.numastat.numa_miss, 120);
+    assert_eq!(node0.numastat.numa_foreign, 8832);
+    assert_eq!(node0.numastat.other_node, 4683);
+    assert_eq!(topology.node(1).unwrap().numastat.numa_foreign, 120);
+    assert!(node0.miss_ratio().unwrap() < 1e-6);
+}
+
+#[test]
+fn strips_node_prefix_from_meminfo() {
+    let meminfo = parse_node_meminfo(
+        "Node 12 MemTotal:  2048 kB\nNode 12 MemUsed:  1024 kB\n\
+         Node 12 HugePages_Total:     4\n"
+    );
+
+    assert_eq!(meminfo.mem_total, Some(2048 * 1024));
+    assert_eq!(meminfo.get("MemUsed"), Some(1024 * 1024));
+    assert_eq!(meminfo.huge_pages_total, Some(4));
+}
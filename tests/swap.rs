@@ -0,0 +1,36 @@
+use patagonicus::swap::{self, SwapDevice, SwapType};
+
+const SWAPS: &str = include_str!("fixtures/swap/swaps");
+
+#[test]
+fn parses_swap_areas() {
+    let devices = swap::parse_swaps(SWAPS);
+
+    assert_eq!(devices.len(), 3);
+    assert_eq!(devices[0], SwapDevice {
+        path: "/dev/nvme0n1p3".to_string(),
+        swap_type: SwapType::Partition,
+        size: 8388604 * 1024,
+        used: 1048576 * 1024,
+        priority: -2,
+        block_device: None,
+    });
+
+    assert_eq!(devices[1].path, "/var/lib/swap files/swap.img");
+    assert_eq!(devices[1].swap_type, SwapType::File);
+    assert_eq!(devices[1].priority, 10);
+    assert_eq!(devices[2].path, "/dev/zram0");
+    assert_eq!(devices[2].priority, 100);
+
+    assert!(swap::parse_swaps("").is_empty());
+}
+
+#[test]
+fn unescapes_octal_sequences() {
+    assert_eq!(swap::unescape_octal(r"/mnt/a\040b"), "/mnt/a b");
+    assert_eq!(swap::unescape_octal(r"tab\011and\134"), "tab\tand\\");
+    assert_eq!(swap::unescape_octal(r"/srv/caf\303\251"), "/srv/café");
+    // Too short or not octal: kept as written.
+    assert_eq!(swap::unescape_octal(r"end\04"), r"end\04");
+    assert_eq!(swap::unescape_octal(r"not\089"), r"not\089");
+}
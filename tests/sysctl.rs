@@ -0,0 +1,98 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use patagonicus::sysctl::{self, SysctlSetting};
+
+fn setting<'a>(settings: &'a [SysctlSetting], name: &str) -> &'a SysctlSetting {
+    settings.iter()
+        .find(|setting| setting.name == name)
+        .unwrap_or_else(|| panic!("{} not configured", name))
+}
+
+fn fixture_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysctl")
+}
+
+#[test]
+fn applies_sysctl_d_precedence() {
+    let root = fixture_root();
+    let settings = sysctl::load_config_from(&root);
+
+    // /etc/sysctl.conf is applied after every sysctl.d file.
+    let swappiness = setting(&settings, "vm.swappiness");
+    assert_eq!(swappiness.value, "1");
+    assert_eq!(swappiness.source, root.join("etc/sysctl.conf"));
+    assert_eq!(swappiness.line, 2);
+
+    // A file in /etc/sysctl.d masks the vendor file of the same name.
+    let dirty_ratio = setting(&settings, "vm.dirty_ratio");
+    assert_eq!(dirty_ratio.value, "10");
+    assert_eq!(dirty_ratio.source, root.join("etc/sysctl.d/50-tuning.conf"));
+
+    assert_eq!(setting(&settings, "net.core.somaxconn").value, "128");
+    assert_eq!(settings.len(), 6);
+}
+
+#[test]
+fn normalizes_names_and_flags() {
+    let root = fixture_root();
+    let settings = sysctl::load_config_from(&root);
+
+    let vlan = setting(&settings, "net.ipv4.conf.eth0/100.forwarding");
+    assert!(vlan.ignore_failure);
+    assert_eq!(
+        sysctl::normalize_name("net/ipv4/conf/eth0.100/forwarding"),
+        vlan.name
+    );
+
+    let pid_max = setting(&settings, "kernel.pid_max");
+    assert!(!pid_max.ignore_failure);
+    assert_eq!(pid_max.value, "4194304");
+
+    let rmem = setting(&settings, "net.ipv4.tcp_rmem");
+    assert_eq!(rmem.value, "4096 131072\t6291456");
+}
+
+#[test]
+fn resolves_existing_parameters() {
+    let path = Path::new("/proc/sys/kernel/ostype");
+
+    assert_eq!(sysctl::path_of("kernel.ostype").unwrap(), path);
+    assert_eq!(sysctl::path_of("kernel/ostype").unwrap(), path);
+    assert_eq!(sysctl::path_of(" kernel.ostype\n").unwrap(), path);
+    assert_eq!(
+        sysctl::path_of("kernel.no_such_parameter").unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn refuses_names_leaving_proc_sys() {
+    let names = [
+        // Slash form.
+        "../../etc/shadow",
+        "net/../../../etc/shadow",
+        "kernel/./ostype",
+        "/etc/shadow",
+        "kernel//ostype",
+        "kernel/ostype/",
+        "",
+        // Dotted form, where `/` stands for `.`.
+        "net.//.//.//etc.shadow",
+        "kernel./.ostype",
+        "kernel..ostype",
+        "kernel.ostype.",
+        ".kernel.ostype",
+    ];
+
+    for name in names {
+        let error = sysctl::path_of(name).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+        assert_eq!(
+            sysctl::read(name).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    assert_eq!(sysctl::normalize_name(" ../etc "), "../etc");
+}
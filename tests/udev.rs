@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::Path;
+
+use patagonicus::udev::UdevRecord;
+
+#[test]
+fn parses_a_block_device_record() {
+    let content = fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/udev/data/b8:0")
+    )
+    .unwrap();
+    let record = UdevRecord::parse(&content);
+
+    assert_eq!(record.symlinks, [
+        "disk/by-id/ata-Samsung_SSD_860_EVO_500GB_S3Z2NB0K123456A",
+        "disk/by-path/pci-0000:00:17.0-ata-1",
+        "disk/by-diskseq/1",
+    ]);
+    assert_eq!(record.property("ID_PATH"), Some("pci-0000:00:17.0-ata-1"));
+    assert_eq!(record.property("ID_PART_TABLE_TYPE"), Some("gpt"));
+    assert_eq!(
+        record.first_property(&["ID_MODEL", "ID_SERIAL"]),
+        Some("Samsung_SSD_860_EVO_500GB_S3Z2NB0K123456A")
+    );
+    // Neither the line without a type nor the property without a value
+    // makes it in.
+    assert_eq!(record.properties.len(), 5);
+    assert_eq!(record.property("ID_BROKEN"), None);
+
+    assert_eq!(record.tags, ["systemd"]);
+    assert_eq!(record.current_tags, ["systemd"]);
+    assert_eq!(record.link_priority, Some(0));
+    assert_eq!(record.initialized_usec, Some(2364571));
+}
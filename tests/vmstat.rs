@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use patagonicus::vmstat::{VmStat, VmStatRates};
+
+const BEFORE: &str = include_str!("fixtures/vmstat/before");
+const AFTER: &str = include_str!("fixtures/vmstat/after");
+const ZONED: &str = include_str!("fixtures/vmstat/zoned");
+
+#[test]
+fn parses_counters_and_skips_malformed_lines() {
+    let vmstat = VmStat::parse(ZONED);
+
+    assert_eq!(vmstat.counters.len(), 10);
+    assert_eq!(vmstat.get("pgfault"), Some(123456));
+    assert_eq!(vmstat.pgfault(), Some(123456));
+    assert_eq!(vmstat.get("malformed"), None);
+    assert_eq!(vmstat.pgmajfault(), None);
+}
+
+#[test]
+fn sums_per_zone_counters_of_older_kernels() {
+    let vmstat = VmStat::parse(ZONED);
+
+    assert_eq!(vmstat.pgscan_kswapd(), Some(10 + 200 + 3000));
+    // `_throttle` is not a zone.
+    assert_eq!(vmstat.pgscan_direct(), Some(40));
+    // The combined key wins over the zones when both are present.
+    assert_eq!(vmstat.pgsteal_kswapd(), Some(9));
+    assert_eq!(vmstat.pgscan_khugepaged(), None);
+
+    let rates = VmStatRates {
+        interval_secs: 1.0,
+        per_second: BTreeMap::from([
+            ("pswpin".to_string(), 4.0),
+            ("pgscan_direct_dma32".to_string(), 1.5),
+            ("pgscan_direct_normal".to_string(), 2.5),
+        ]),
+    };
+    assert_eq!(rates.pgscan_direct(), Some(4.0));
+    assert_eq!(rates.pgscan_total(), Some(4.0));
+    assert_eq!(rates.swap_pages(), Some(4.0));
+    assert_eq!(rates.pgscan_kswapd(), None);
+}
+
+#[test]
+fn rates_cover_event_counters_only() {
+    let before = VmStat::parse(BEFORE);
+    let after = VmStat::parse(AFTER);
+    let rates = after.rates_since(&before, Duration::from_secs(2));
+
+    assert_eq!(rates.interval_secs, 2.0);
+    assert_eq!(rates.pgpgin(), Some(2000.0));
+    assert_eq!(rates.pgfault(), Some(100000.0));
+    assert_eq!(rates.pgscan_total(), Some(1000.0 + 250.0));
+    assert_eq!(rates.swap_pages(), Some(20.0));
+    assert_eq!(rates.workingset_refault_file(), Some(100.0));
+    assert_eq!(rates.get("nr_dirtied"), Some(200.0));
+    assert_eq!(rates.get("nr_written"), Some(300.0));
+    assert_eq!(rates.get("nr_vmscan_write"), Some(5.0));
+
+    // A counter that went backwards, e.g. across a reset.
+    assert_eq!(rates.oom_kill(), Some(0.0));
+    // Missing from the earlier sample.
+    assert_eq!(rates.compact_stall(), None);
+
+    for gauge in [
+        "nr_free_pages", "nr_dirty", "nr_dirty_threshold", "workingset_nodes",
+    ] {
+        assert_eq!(rates.get(gauge), None, "{}", gauge);
+    }
+    assert_eq!(rates.per_second.len(), 15);
+}
+
+#[test]
+fn no_rates_without_an_interval() {
+    let before = VmStat::parse(BEFORE);
+    let rates = VmStat::parse(AFTER).rates_since(&before, Duration::ZERO);
+
+    assert!(rates.per_second.is_empty());
+}
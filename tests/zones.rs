@@ -0,0 +1,78 @@
+use patagonicus::zones::{parse_buddyinfo, parse_pagetypeinfo, parse_zoneinfo};
+
+const ZONEINFO: &str = include_str!("fixtures/zones/zoneinfo");
+const PAGETYPEINFO: &str = include_str!("fixtures/zones/pagetypeinfo");
+
+#[test]
+fn fragmentation_matches_kernel_formula() {
+    let zones = parse_buddyinfo(
+        "Node 0, zone   Normal    100     50      0      0 \n"
+    );
+    let zone = &zones[0];
+
+    assert_eq!(zone.node, 0);
+    assert_eq!(zone.zone, "Normal");
+    assert_eq!(zone.free_pages(), 200);
+
+    // Blocks of order 0 and 1 are free, so those allocations succeed.
+    assert_eq!(zone.fragmentation_index(1), -1.0);
+    // 1 - (1 + 200 / 4) / 150
+    assert!((zone.fragmentation_index(2) - 0.66).abs() < 1e-9);
+
+    assert_eq!(zone.unusable_index(0), 0.0);
+    assert_eq!(zone.unusable_index(1), 0.5);
+    assert_eq!(zone.unusable_index(2), 1.0);
+}
+
+#[test]
+fn empty_zone_is_not_fragmented() {
+    let zones = parse_buddyinfo("Node 1, zone  Movable  0 0 0\n");
+
+    assert_eq!(zones[0].node, 1);
+    assert_eq!(zones[0].fragmentation_indices(), [0.0, 0.0, 0.0]);
+    assert_eq!(zones[0].unusable_index(0), 1.0);
+}
+
+#[test]
+fn parses_pagetypeinfo() {
+    let info = parse_pagetypeinfo(PAGETYPEINFO);
+
+    assert_eq!(info.page_block_order, Some(9));
+    assert_eq!(info.pages_per_block, Some(512));
+    assert_eq!(info.free.len(), 15);
+
+    let movable = info.free.iter()
+        .find(|free| free.zone == "DMA32" && free.migrate_type == "Movable")
+        .unwrap();
+    assert_eq!(movable.free_by_order, [2, 2, 2, 2, 2, 2, 5, 2, 2, 2, 754]);
+
+    let normal = &info.blocks[2];
+    assert_eq!(normal.zone, "Normal");
+    assert_eq!(normal.blocks["Unmovable"], 56);
+    assert_eq!(normal.blocks["Movable"], 888);
+    assert_eq!(normal.blocks.len(), 5);
+}
+
+#[test]
+fn parses_zoneinfo() {
+    let zones = parse_zoneinfo(ZONEINFO);
+    let names: Vec<&str> = zones.iter().map(|zone| zone.name.as_str()).collect();
+    assert_eq!(names, ["DMA", "DMA32", "Normal", "Movable", "Device"]);
+
+    let dma = &zones[0];
+    assert_eq!(dma.free_pages, 3840);
+    assert_eq!((dma.min, dma.low, dma.high), (51, 63, 75));
+    assert_eq!(dma.promo, Some(87));
+    assert_eq!(dma.managed, 3840);
+    assert_eq!(dma.protection, [0, 3024, 4944, 4944, 4944]);
+    assert_eq!(dma.start_pfn, Some(1));
+    assert_eq!(dma.node_unreclaimable, Some(false));
+    assert_eq!(dma.node_stats.get("nr_inactive_anon"), Some(&47132));
+    assert_eq!(dma.stats.get("nr_free_pages"), Some(&3840));
+    assert!(!dma.stats.contains_key("count"));
+
+    // Node-wide counters are only printed under the first zone.
+    assert!(zones[1].node_stats.is_empty());
+    assert_eq!(zones[2].start_pfn, Some(1048576));
+    assert_eq!(zones[4].start_pfn, None);
+}